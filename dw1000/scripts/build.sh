@@ -2,5 +2,5 @@
 
 export RUSTFLAGS="-D warnings"
 
-cargo test --verbose &&
+cargo test --verbose --features sim &&
 cargo doc
@@ -21,6 +21,47 @@ pub struct TxConfig {
     pub channel: UwbChannel,
     /// The SFD sequence that is used to transmit a frame.
     pub sfd_sequence: SfdSequence,
+    /// The transmit power.
+    ///
+    /// If `None`, the recommended smart TX power setting for the configured
+    /// channel and PRF is used (see [`UwbChannel::get_recommended_tx_power`]).
+    ///
+    /// Defaults to `None`.
+    ///
+    /// [`UwbChannel::get_recommended_tx_power`]: enum.UwbChannel.html#method.get_recommended_tx_power
+    pub tx_power: Option<TxPower>,
+    /// Request an acknowledgement from the recipient.
+    ///
+    /// If true, the acknowledgement request bit is set in the transmitted
+    /// frame, and the receiver is enabled after the transmission, to wait for
+    /// the acknowledgement. Use `DW1000::wait_ack` to find out whether it
+    /// arrived.
+    ///
+    /// Defaults to `false`.
+    pub ack_request: bool,
+    /// How long to wait for an acknowledgement.
+    ///
+    /// Only used, if `ack_request` is true. The unit is roughly 1 µs (512/499.2
+    /// MHz, to be precise), measured from the end of the transmission. The
+    /// default is long enough for the default configuration.
+    pub ack_timeout: u16,
+    /// Use the extended address as the source address
+    ///
+    /// If true, the frame's source address is the extended address (EUI-64)
+    /// set with `DW1000::set_extended_address`. Otherwise, it's the short
+    /// address set with `DW1000::set_address`.
+    ///
+    /// Defaults to `false`.
+    pub extended_source: bool,
+    /// Allow frames longer than 127 bytes
+    ///
+    /// If true, the DW1000's non-standard long frame mode is used, which
+    /// allows frames of up to 1023 bytes, including the FCS. Such frames can
+    /// only be received by a DW1000 that has `RxConfig::long_frames` enabled.
+    /// Frames of standard length are sent as usual.
+    ///
+    /// Defaults to `false`.
+    pub long_frames: bool,
 }
 
 impl Default for TxConfig {
@@ -32,6 +73,11 @@ impl Default for TxConfig {
             preamble_length: Default::default(),
             channel: Default::default(),
             sfd_sequence: Default::default(),
+            tx_power: None,
+            ack_request: false,
+            ack_timeout: 1000,
+            extended_source: false,
+            long_frames: false,
         }
     }
 }
@@ -44,7 +90,8 @@ pub struct RxConfig {
     /// Enable frame filtering
     ///
     /// If true, only frames directly addressed to this node and broadcasts will
-    /// be received.
+    /// be received. Frames are addressed to this node, if their destination is
+    /// either its short address, or its extended address (EUI-64).
     ///
     /// Defaults to `true`.
     pub frame_filtering: bool,
@@ -60,6 +107,77 @@ pub struct RxConfig {
     pub channel: UwbChannel,
     /// The type of SFD sequence that will be scanned for.
     pub sfd_sequence: SfdSequence,
+    /// Automatically acknowledge frames that request it
+    ///
+    /// If true, the DW1000 sends an acknowledgement for every received frame
+    /// that is addressed to this node and has the acknowledgement request bit
+    /// set. Only has an effect, if `frame_filtering` is enabled too.
+    ///
+    /// Defaults to `false`.
+    pub auto_ack: bool,
+    /// How long to wait for a frame, before giving up
+    ///
+    /// If set, the receiver is disabled, if no frame has been received within
+    /// this time after it was enabled, and `Receiving::wait` returns
+    /// `Error::FrameWaitTimeout`. The unit is roughly 1 µs (512/499.2 MHz, to
+    /// be precise). Zero is not a valid timeout.
+    ///
+    /// Defaults to `None`, which means the receiver waits forever.
+    pub frame_wait_timeout: Option<u16>,
+    /// How long to wait for a preamble, before giving up
+    ///
+    /// If set, the receiver is disabled, if no preamble has been detected
+    /// within this time after it was enabled, and `Receiving::wait` returns
+    /// `Error::PreambleDetectionTimeout`. This saves more power than the frame
+    /// wait timeout, as the receiver gives up as soon as it's clear that there
+    /// is no frame. The unit is the PAC size (see
+    /// [`PreambleLength::get_recommended_pac_size`]) in preamble symbols.
+    /// Zero is not a valid timeout.
+    ///
+    /// Defaults to `None`, which means the receiver waits forever.
+    ///
+    /// [`PreambleLength::get_recommended_pac_size`]: enum.PreambleLength.html#method.get_recommended_pac_size
+    pub preamble_detection_timeout: Option<u16>,
+    /// Use both receive buffers
+    ///
+    /// If true, the DW1000 keeps receiving into its second buffer, while the
+    /// host reads a frame from the first one. Call `Receiving::wait` again to
+    /// get the next frame. This reduces the time the receiver is off between
+    /// frames, which helps with frames that arrive in quick succession.
+    ///
+    /// Defaults to `false`.
+    pub double_buffered: bool,
+    /// Keep receiving after the first frame
+    ///
+    /// If true, the receiver is re-enabled after every frame, and
+    /// `Receiving::wait` can be called repeatedly to receive one frame after
+    /// the other. Frames that fail to be received (FCS, PHY header or
+    /// Reed-Solomon errors) are reported as errors by `Receiving::wait`, but
+    /// don't stop the receiver. Combine with `double_buffered` to keep the
+    /// receiver running while frames are read.
+    ///
+    /// Defaults to `false`.
+    pub continuous: bool,
+    /// Duty-cycle the receiver while waiting for a preamble
+    ///
+    /// If set, the receiver alternates between listening for a preamble and
+    /// being switched off, which saves power while waiting for rare frames.
+    /// Frames are only received reliably, if their preamble is longer than a
+    /// full cycle of on and off time. See [`SniffMode`].
+    ///
+    /// Defaults to `None`, which means the receiver listens all the time.
+    ///
+    /// [`SniffMode`]: struct.SniffMode.html
+    pub sniff_mode: Option<SniffMode>,
+    /// Receive frames longer than 127 bytes
+    ///
+    /// If true, the DW1000's non-standard long frame mode is used, which
+    /// allows frames of up to 1023 bytes, as sent with
+    /// `TxConfig::long_frames`. Frames of standard length are still received.
+    /// Make sure the buffer passed to `Receiving::wait` is large enough.
+    ///
+    /// Defaults to `false`.
+    pub long_frames: bool,
 }
 
 impl Default for RxConfig {
@@ -71,6 +189,74 @@ impl Default for RxConfig {
             expected_preamble_length: Default::default(),
             channel: Default::default(),
             sfd_sequence: Default::default(),
+            auto_ack: false,
+            frame_wait_timeout: None,
+            preamble_detection_timeout: None,
+            double_buffered: false,
+            continuous: false,
+            sniff_mode: None,
+            long_frames: false,
+        }
+    }
+}
+
+/// SNIFF mode configuration
+///
+/// See user manual, section 4.5.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SniffMode {
+    /// How long the receiver listens for a preamble in every cycle
+    ///
+    /// In units of the PAC size (see
+    /// [`PreambleLength::get_recommended_pac_size`]). The DW1000 adds one PAC
+    /// to this value. Must be between 1 and 15.
+    ///
+    /// [`PreambleLength::get_recommended_pac_size`]: enum.PreambleLength.html#method.get_recommended_pac_size
+    pub on_time: u8,
+    /// How long the receiver is switched off in every cycle
+    ///
+    /// In units of 128/125 µs, roughly 1 µs.
+    pub off_time: u8,
+}
+
+/// Sleep configuration
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SleepConfig {
+    /// How long to sleep, before waking up on its own
+    ///
+    /// If set, the DW1000 enters the SLEEP state and wakes up when its sleep
+    /// counter elapses. The unit depends on the frequency of the low-power
+    /// oscillator, which varies between chips (see user manual, section
+    /// 7.2.44.4). If `None`, the DW1000 enters the DEEPSLEEP state, which uses
+    /// less power, and only wakes up when told to by the host.
+    ///
+    /// Defaults to `None`.
+    pub sleep_time: Option<u16>,
+    /// Wake up when chip select is held low
+    ///
+    /// Defaults to `true`.
+    pub wake_on_spi: bool,
+    /// Wake up when the WAKEUP pin is driven high
+    ///
+    /// Defaults to `true`.
+    pub wake_on_pin: bool,
+    /// Keep the configuration while sleeping
+    ///
+    /// If true, the DW1000 restores its configuration from the always-on
+    /// memory when waking up. Otherwise, it wakes up with its default
+    /// configuration, and the driver repeats the initialization.
+    ///
+    /// Defaults to `true`.
+    pub preserve_config: bool,
+}
+
+impl Default for SleepConfig {
+    fn default() -> Self {
+        Self {
+            sleep_time: None,
+            wake_on_spi: true,
+            wake_on_pin: true,
+            preserve_config: true,
         }
     }
 }
@@ -87,6 +273,7 @@ pub enum BitRate {
     Kbps6800 = 0b10,
 }
 
+#[allow(clippy::derivable_impls)] // `#[default]` requires Rust 1.62
 impl Default for BitRate {
     fn default() -> Self {
         BitRate::Kbps6800
@@ -106,6 +293,33 @@ impl BitRate {
             (BitRate::Kbps6800, _) => 0x0002,
         }
     }
+
+    /// Gets the recommended ack_tim value for the bitrate
+    ///
+    /// This is the turn-around time between receiving a frame and sending the
+    /// automatic acknowledgement, in preamble symbols.
+    pub fn get_recommended_ack_tim(&self) -> u8 {
+        // Values are taken from the description of ACK_RESP_T in the DW1000
+        // User Manual. Only 6.8 Mbps needs a turn-around time.
+        match self {
+            BitRate::Kbps110 => 0,
+            BitRate::Kbps850 => 0,
+            BitRate::Kbps6800 => 3,
+        }
+    }
+
+    /// Gets the resolution of the carrier recovery integrator
+    ///
+    /// The integrator counts carrier frequency offset in steps of
+    /// `998.4 MHz / 2^n`, where `n` is the value returned here.
+    pub fn get_carrier_integrator_shift(&self) -> u32 {
+        // See the description of DRX_CAR_INT in the DW1000 User Manual,
+        // section 7.2.40.11.
+        match self {
+            BitRate::Kbps110 => 31,
+            BitRate::Kbps850 | BitRate::Kbps6800 => 28,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -117,6 +331,7 @@ pub enum PulseRepetitionFrequency {
     Mhz64 = 0b10,
 }
 
+#[allow(clippy::derivable_impls)] // `#[default]` requires Rust 1.62
 impl Default for PulseRepetitionFrequency {
     fn default() -> Self {
         PulseRepetitionFrequency::Mhz16
@@ -194,6 +409,7 @@ pub enum PreambleLength {
     Symbols4096 = 0b1100,
 }
 
+#[allow(clippy::derivable_impls)] // `#[default]` requires Rust 1.62
 impl Default for PreambleLength {
     fn default() -> Self {
         PreambleLength::Symbols128
@@ -276,6 +492,7 @@ pub enum SfdSequence {
     User,
 }
 
+#[allow(clippy::derivable_impls)] // `#[default]` requires Rust 1.62
 impl Default for SfdSequence {
     fn default() -> Self {
         SfdSequence::IEEE
@@ -313,6 +530,7 @@ pub enum UwbChannel {
     Channel7 = 7,
 }
 
+#[allow(clippy::derivable_impls)] // `#[default]` requires Rust 1.62
 impl Default for UwbChannel {
     fn default() -> Self {
         UwbChannel::Channel5
@@ -320,6 +538,16 @@ impl Default for UwbChannel {
 }
 
 impl UwbChannel {
+    /// Gets the center frequency of the channel, as a multiple of 499.2 MHz
+    pub fn get_carrier_multiple(&self) -> u32 {
+        match self {
+            UwbChannel::Channel1 => 7,
+            UwbChannel::Channel2 | UwbChannel::Channel4 => 8,
+            UwbChannel::Channel3 => 9,
+            UwbChannel::Channel5 | UwbChannel::Channel7 => 13,
+        }
+    }
+
     /// Gets the recommended preamble code
     pub fn get_recommended_preamble_code(&self, prf_value: PulseRepetitionFrequency) -> u8 {
         // Many have overlapping possibilities, so the numbers have been chosen so that there's no overlap here
@@ -387,6 +615,47 @@ impl UwbChannel {
         }
     }
 
+    /// Gets the recommended transmit power
+    ///
+    /// Returns a smart TX power setting, if `smart_power` is true, and a manual
+    /// one otherwise. Smart TX power control only makes a difference at a
+    /// bitrate of 6.8 Mbps.
+    pub fn get_recommended_tx_power(
+        &self,
+        prf_value: PulseRepetitionFrequency,
+        smart_power: bool,
+    ) -> TxPower {
+        // Values based on Table 20 of the DW1000 User Manual
+        let value = match (self, prf_value, smart_power) {
+            (UwbChannel::Channel1, PulseRepetitionFrequency::Mhz16, true) => 0x15355575,
+            (UwbChannel::Channel2, PulseRepetitionFrequency::Mhz16, true) => 0x15355575,
+            (UwbChannel::Channel3, PulseRepetitionFrequency::Mhz16, true) => 0x0F2F4F6F,
+            (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz16, true) => 0x1F1F3F5F,
+            (UwbChannel::Channel5, PulseRepetitionFrequency::Mhz16, true) => 0x0E082848,
+            (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz16, true) => 0x32527292,
+            (UwbChannel::Channel1, PulseRepetitionFrequency::Mhz64, true) => 0x07274767,
+            (UwbChannel::Channel2, PulseRepetitionFrequency::Mhz64, true) => 0x07274767,
+            (UwbChannel::Channel3, PulseRepetitionFrequency::Mhz64, true) => 0x2B4B6B8B,
+            (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz64, true) => 0x3A5A7A9A,
+            (UwbChannel::Channel5, PulseRepetitionFrequency::Mhz64, true) => 0x25456585,
+            (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz64, true) => 0x5171B1D1,
+            (UwbChannel::Channel1, PulseRepetitionFrequency::Mhz16, false) => 0x75757575,
+            (UwbChannel::Channel2, PulseRepetitionFrequency::Mhz16, false) => 0x75757575,
+            (UwbChannel::Channel3, PulseRepetitionFrequency::Mhz16, false) => 0x6F6F6F6F,
+            (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz16, false) => 0x5F5F5F5F,
+            (UwbChannel::Channel5, PulseRepetitionFrequency::Mhz16, false) => 0x48484848,
+            (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz16, false) => 0x92929292,
+            (UwbChannel::Channel1, PulseRepetitionFrequency::Mhz64, false) => 0x67676767,
+            (UwbChannel::Channel2, PulseRepetitionFrequency::Mhz64, false) => 0x67676767,
+            (UwbChannel::Channel3, PulseRepetitionFrequency::Mhz64, false) => 0x8B8B8B8B,
+            (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz64, false) => 0x9A9A9A9A,
+            (UwbChannel::Channel5, PulseRepetitionFrequency::Mhz64, false) => 0x85858585,
+            (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz64, false) => 0xD1D1D1D1,
+        };
+
+        TxPower::from_register_value(value, smart_power)
+    }
+
     /// Gets the recommended value for the rf_rxctrlh register
     pub fn get_recommended_rf_rxctrlh(&self) -> u8 {
         // Values based on Table 37 of the DW1000 User Manual
@@ -399,3 +668,170 @@ impl UwbChannel {
         }
     }
 }
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// The transmit power configuration
+///
+/// The DW1000 can either use the same transmit power for the whole frame, or
+/// boost the power of short frames (smart TX power control). For the register
+/// layout, see section 7.2.31 of the user manual. Recommended values can be
+/// obtained from [`UwbChannel::get_recommended_tx_power`].
+///
+/// [`UwbChannel::get_recommended_tx_power`]: enum.UwbChannel.html#method.get_recommended_tx_power
+pub enum TxPower {
+    /// Smart TX power control
+    ///
+    /// The DW1000 boosts the transmit power of frames that are shorter than
+    /// 0.5 ms, 0.25 ms and 0.125 ms, as these contribute less energy to the
+    /// 1 ms period over which the regulatory limit is measured.
+    Smart {
+        /// The gain for frames longer than 0.5 ms
+        boost_norm: TxGain,
+        /// The gain for frames shorter than 0.5 ms
+        boost_500: TxGain,
+        /// The gain for frames shorter than 0.25 ms
+        boost_250: TxGain,
+        /// The gain for frames shorter than 0.125 ms
+        boost_125: TxGain,
+    },
+    /// Manual TX power control
+    Manual {
+        /// The gain for the PHY header
+        phr: TxGain,
+        /// The gain for the synchronization header and the data
+        shr_data: TxGain,
+    },
+}
+
+impl TxPower {
+    /// Creates a `TxPower` from the value of the TX_POWER register
+    ///
+    /// `smart_power` determines how the register value is interpreted. It
+    /// should match the inverted value of SYS_CFG.DIS_STXP.
+    pub fn from_register_value(value: u32, smart_power: bool) -> Self {
+        let [byte0, byte1, byte2, byte3] = value.to_le_bytes();
+
+        if smart_power {
+            TxPower::Smart {
+                boost_norm: TxGain::from_byte(byte0),
+                boost_500:  TxGain::from_byte(byte1),
+                boost_250:  TxGain::from_byte(byte2),
+                boost_125:  TxGain::from_byte(byte3),
+            }
+        }
+        else {
+            TxPower::Manual {
+                phr:      TxGain::from_byte(byte1),
+                shr_data: TxGain::from_byte(byte2),
+            }
+        }
+    }
+
+    /// Gets the value for the TX_POWER register
+    ///
+    /// In manual mode, the unused bytes of the register are set to the gain
+    /// for the synchronization header and the data, as in the reference values
+    /// from the user manual.
+    pub fn to_register_value(&self) -> u32 {
+        let bytes = match self {
+            TxPower::Smart { boost_norm, boost_500, boost_250, boost_125 } => [
+                boost_norm.to_byte(),
+                boost_500.to_byte(),
+                boost_250.to_byte(),
+                boost_125.to_byte(),
+            ],
+            TxPower::Manual { phr, shr_data } => [
+                shr_data.to_byte(),
+                phr.to_byte(),
+                shr_data.to_byte(),
+                shr_data.to_byte(),
+            ],
+        };
+
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Whether this is a smart TX power setting
+    ///
+    /// SYS_CFG.DIS_STXP must be set to the inverse of this.
+    pub fn is_smart(&self) -> bool {
+        match self {
+            TxPower::Smart { .. } => true,
+            TxPower::Manual { .. } => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// The gain of the transmitter for one part of a frame
+///
+/// The total gain is the sum of the coarse and the fine gain.
+pub struct TxGain {
+    /// The coarse gain of the DA (digital-to-analog) stage
+    pub coarse: CoarseGain,
+    /// The fine gain of the mixer, in steps of 0.5 dB
+    ///
+    /// Valid values range from 0 (0 dB) to 31 (15.5 dB). Only the lower five
+    /// bits are used.
+    pub fine: u8,
+}
+
+impl TxGain {
+    /// Gets the total gain in dB, or `None`, if the output is off
+    pub fn db(&self) -> Option<f32> {
+        self.coarse.db().map(|coarse| coarse + self.fine as f32 * 0.5)
+    }
+
+    fn from_byte(byte: u8) -> Self {
+        let coarse = match byte >> 5 {
+            0b000 => CoarseGain::Db15,
+            0b001 => CoarseGain::Db12_5,
+            0b010 => CoarseGain::Db10,
+            0b011 => CoarseGain::Db7_5,
+            0b100 => CoarseGain::Db5,
+            0b101 => CoarseGain::Db2_5,
+            0b110 => CoarseGain::Db0,
+            _ => CoarseGain::Off,
+        };
+
+        TxGain {
+            coarse,
+            fine: byte & 0b11111,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        (self.coarse as u8) << 5 | self.fine & 0b11111
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+/// The coarse gain setting of the transmitter's DA stage
+pub enum CoarseGain {
+    /// 15 dB gain
+    Db15 = 0b000,
+    /// 12.5 dB gain
+    Db12_5 = 0b001,
+    /// 10 dB gain
+    Db10 = 0b010,
+    /// 7.5 dB gain
+    Db7_5 = 0b011,
+    /// 5 dB gain
+    Db5 = 0b100,
+    /// 2.5 dB gain
+    Db2_5 = 0b101,
+    /// 0 dB gain
+    Db0 = 0b110,
+    /// Output off
+    Off = 0b111,
+}
+
+impl CoarseGain {
+    /// Gets the gain in dB, or `None`, if the output is off
+    pub fn db(&self) -> Option<f32> {
+        match self {
+            CoarseGain::Off => None,
+            gain => Some(15.0 - *gain as u8 as f32 * 2.5),
+        }
+    }
+}
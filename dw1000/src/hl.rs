@@ -16,7 +16,10 @@ use core::{
 };
 
 use embedded_hal::{
-    blocking::spi,
+    blocking::{
+        delay::DelayUs,
+        spi,
+    },
     digital::v2::OutputPin,
 };
 use nb;
@@ -25,6 +28,7 @@ use ssmarshal;
 use crate::{
     ll,
     mac,
+    otp::Otp,
     time::{
         Duration,
         Instant,
@@ -32,11 +36,58 @@ use crate::{
     configs::{
         TxConfig,
         RxConfig,
+        SleepConfig,
         SfdSequence,
         BitRate,
+        PulseRepetitionFrequency,
+        UwbChannel,
     },
 };
 
+/// The size of a channel impulse response sample in the accumulator memory
+///
+/// Each sample consists of a 16-bit real part and a 16-bit imaginary part.
+const CIR_SAMPLE_LEN: usize = 4;
+
+/// How long the SAR ADC is given to finish a conversion, in µs
+const SAR_CONVERSION_TIME_US: u16 = 1000;
+
+/// How long the DW1000 takes to reach the IDLE state after waking up, in µs
+const WAKE_UP_TIME_US: u16 = 5000;
+
+/// The maximum length of a standard frame, including the FCS
+const MAX_FRAME_LEN: usize = 127;
+
+/// The maximum length of a frame in long frame mode, including the FCS
+///
+/// See user manual, section 3.4.
+const MAX_LONG_FRAME_LEN: usize = 1023;
+
+/// The maximum length of a MAC header, as written by `prepare_send`
+///
+/// Frame control, sequence number, and PAN ID and extended address for both
+/// destination and source.
+const MAX_HEADER_LEN: usize = 2 + 1 + 2 * (2 + 8);
+
+/// The length of an acknowledgement frame, including the FCS
+const ACK_FRAME_LEN: usize = 5;
+
+/// The length of the frame check sequence that the DW1000 appends to frames
+const FCS_LEN: usize = 2;
+
+/// The largest crystal trim value
+pub const XTAL_TRIM_MAX: u8 = 0x1f;
+
+/// The crystal trim value used, if none has been programmed into OTP memory
+pub const XTAL_TRIM_DEFAULT: u8 = 0x10;
+
+/// How much one crystal trim step changes the clock frequency, in ppm
+///
+/// This is a typical value. The actual value depends on the crystal and the
+/// board it is mounted on.
+pub const XTAL_TRIM_PPM_PER_STEP: f32 = 1.5;
+
+
 /// Entry point to the DW1000 driver API
 pub struct DW1000<SPI, CS, State> {
     ll:    ll::DW1000<SPI, CS>,
@@ -77,67 +128,7 @@ impl<SPI, CS> DW1000<SPI, CS, Uninitialized>
     /// configuration. It is generally recommended not to change configuration
     /// before calling this method.
     pub fn init(mut self) -> Result<DW1000<SPI, CS, Ready>, Error<SPI, CS>> {
-        // Set AGC_TUNE1. See user manual, section 2.5.5.1.
-        self.ll.agc_tune1().write(|w| w.value(0x8870))?;
-
-        // Set AGC_TUNE2. See user manual, section 2.5.5.2.
-        self.ll.agc_tune2().write(|w| w.value(0x2502A907))?;
-
-        // Set DRX_TUNE2. See user manual, section 2.5.5.3.
-        self.ll.drx_tune2().write(|w| w.value(0x311A002D))?;
-
-        // Set NTM. See user manual, section 2.5.5.4. This improves performance
-        // in line-of-sight conditions, but might not be the best choice if non-
-        // line-of-sight performance is important.
-        self.ll.lde_cfg1().modify(|_, w| w.ntm(0xD))?;
-
-        // Set LDE_CFG2. See user manual, section 2.5.5.5.
-        self.ll.lde_cfg2().write(|w| w.value(0x1607))?;
-
-        // Set TX_POWER. See user manual, section 2.5.5.6.
-        self.ll.tx_power().write(|w| w.value(0x0E082848))?;
-
-        // Set RF_TXCTRL. See user manual, section 2.5.5.7.
-        self.ll.rf_txctrl().modify(|_, w|
-            w
-                .txmtune(0b1111)
-                .txmq(0b111)
-        )?;
-
-        // Set TC_PGDELAY. See user manual, section 2.5.5.8.
-        self.ll.tc_pgdelay().write(|w| w.value(0xC0))?;
-
-        // Set FS_PLLTUNE. See user manual, section 2.5.5.9.
-        self.ll.fs_plltune().write(|w| w.value(0xBE))?;
-
-        // Set LDELOAD. See user manual, section 2.5.5.10.
-        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(0b01))?;
-        self.ll.otp_ctrl().modify(|_, w| w.ldeload(0b1))?;
-        while self.ll.otp_ctrl().read()?.ldeload() == 0b1 {}
-        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(0b00))?;
-
-        // Set LDOTUNE. See user manual, section 2.5.5.11.
-        self.ll.otp_addr().write(|w| w.value(0x004))?;
-        self.ll.otp_ctrl().modify(|_, w|
-            w
-                .otprden(0b1)
-                .otpread(0b1)
-        )?;
-        while self.ll.otp_ctrl().read()?.otpread() == 0b1 {}
-        let ldotune_low = self.ll.otp_rdat().read()?.value();
-        if ldotune_low != 0 {
-            self.ll.otp_addr().write(|w| w.value(0x005))?;
-            self.ll.otp_ctrl().modify(|_, w|
-                w
-                    .otprden(0b1)
-                    .otpread(0b1)
-            )?;
-            while self.ll.otp_ctrl().read()?.otpread() == 0b1 {}
-            let ldotune_high = self.ll.otp_rdat().read()?.value();
-
-            let ldotune = ldotune_low as u64 | (ldotune_high as u64) << 32;
-            self.ll.ldotune().write(|w| w.value(ldotune))?;
-        }
+        self.configure_defaults()?;
 
         Ok(DW1000 {
             ll:    self.ll,
@@ -166,6 +157,58 @@ impl<SPI, CS> DW1000<SPI, CS, Ready>
         Ok(())
     }
 
+    /// Sets the extended address (EUI-64) used for sending and receiving
+    ///
+    /// Frame filtering accepts frames addressed to this address, in addition
+    /// to the short address set with [`set_address`]. It is used as the source
+    /// address of sent frames, if [`TxConfig::extended_source`] is set.
+    ///
+    /// [`set_address`]: #method.set_address
+    /// [`TxConfig::extended_source`]: ../configs/struct.TxConfig.html#structfield.extended_source
+    pub fn set_extended_address(&mut self, address: mac::ExtendedAddress)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll.eui().write(|w| w.value(address.0))?;
+
+        Ok(())
+    }
+
+    /// Uses the factory-programmed EUI-64 as the extended address
+    ///
+    /// Reads the EUI from OTP memory and passes it to
+    /// [`set_extended_address`]. Returns the EUI.
+    ///
+    /// [`set_extended_address`]: #method.set_extended_address
+    pub fn set_extended_address_from_otp(&mut self)
+        -> Result<mac::ExtendedAddress, Error<SPI, CS>>
+    {
+        let address = mac::ExtendedAddress(self.otp().eui()?);
+        self.set_extended_address(address)?;
+
+        Ok(address)
+    }
+
+    /// Sets the crystal trim value
+    ///
+    /// Trimming the crystal adjusts the frequency of the DW1000's clock.
+    /// Higher values make the clock run slower. On initialization, the trim
+    /// value is loaded from OTP memory, if one has been programmed there.
+    /// [`ClockOffset`] can help with finding a better value.
+    ///
+    /// Returns [`Error::InvalidConfiguration`], if `trim` is larger than
+    /// [`XTAL_TRIM_MAX`].
+    ///
+    /// [`ClockOffset`]: struct.ClockOffset.html
+    /// [`Error::InvalidConfiguration`]: enum.Error.html#variant.InvalidConfiguration
+    /// [`XTAL_TRIM_MAX`]: constant.XTAL_TRIM_MAX.html
+    pub fn set_xtal_trim(&mut self, trim: u8) -> Result<(), Error<SPI, CS>> {
+        if trim > XTAL_TRIM_MAX {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        self.write_xtal_trim(trim)
+    }
+
     /// Sets the network id and address used for sending and receiving
     pub fn set_address(&mut self, pan_id: mac::PanId, addr: mac::ShortAddress)
         -> Result<(), Error<SPI, CS>>
@@ -207,114 +250,207 @@ impl<SPI, CS> DW1000<SPI, CS, Ready>
     )
         -> Result<DW1000<SPI, CS, Sending>, Error<SPI, CS>>
     {
-        // Clear event counters
-        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1))?;
-        while self.ll.evc_ctrl().read()?.evc_clr() == 0b1 {}
+        let seq = self.prepare_send(data, destination, delayed_time, &config)?;
+        self.start_tx(delayed_time.is_some(), config.ack_request)?;
 
-        // (Re-)Enable event counters
-        self.ll.evc_ctrl().write(|w| w.evc_en(0b1))?;
-        while self.ll.evc_ctrl().read()?.evc_en() == 0b1 {}
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Sending {
+                finished:    false,
+                ack_seq:     if config.ack_request { Some(seq) } else { None },
+                ack_timeout: config.ack_timeout,
+                tx_done:     None,
+            },
+        })
+    }
 
-        // Sometimes, for unknown reasons, the DW1000 gets stuck in RX mode.
-        // Starting the transmitter won't get it to enter TX mode, which means
-        // all subsequent send operations will fail. Let's disable the
-        // transceiver and force the chip into IDLE mode to make sure that
-        // doesn't happen.
-        self.force_idle()?;
+    /// Send raw data, without wrapping it into an IEEE 802.15.4 MAC frame
+    ///
+    /// Works like [`send`], except that `data` is sent as-is. Only the
+    /// two-octet FCS is appended by the DW1000. This is useful for
+    /// interoperating with devices that don't use IEEE 802.15.4 MAC framing.
+    /// Use [`Receiving::wait_raw`] to receive such frames.
+    ///
+    /// Returns [`Error::FrameTooLong`], if `data` doesn't fit into a frame
+    /// (see [`TxConfig::long_frames`]). Returns
+    /// [`Error::InvalidConfiguration`], if `config` requests an
+    /// acknowledgement, as the driver can't know whether `data` is a frame
+    /// that could be acknowledged.
+    ///
+    /// [`send`]: #method.send
+    /// [`Receiving::wait_raw`]: #method.wait_raw
+    /// [`Error::FrameTooLong`]: enum.Error.html#variant.FrameTooLong
+    /// [`TxConfig::long_frames`]: ../configs/struct.TxConfig.html#structfield.long_frames
+    /// [`Error::InvalidConfiguration`]: enum.Error.html#variant.InvalidConfiguration
+    pub fn send_raw(mut self,
+        data:         &[u8],
+        delayed_time: Option<Instant>,
+        config:       TxConfig,
+    )
+        -> Result<DW1000<SPI, CS, Sending>, Error<SPI, CS>>
+    {
+        if config.ack_request {
+            return Err(Error::InvalidConfiguration);
+        }
+        check_frame_len(data.len(), &config)?;
 
-        let seq = self.seq.0;
-        self.seq += Wrapping(1);
+        self.prepare_transmitter()?;
+        self.ll.tx_buffer().write_at(0, data)?;
+        self.configure_transmitter(0, data.len(), delayed_time, &config)?;
+        self.start_tx(delayed_time.is_some(), false)?;
 
-        let frame = mac::Frame {
-            header: mac::Header {
-                frame_type:      mac::FrameType::Data,
-                version:         mac::FrameVersion::Ieee802154_2006,
-                security:        mac::Security::None,
-                frame_pending:   false,
-                ack_request:     false,
-                pan_id_compress: false,
-                destination:     destination,
-                source:          self.get_address()?,
-                seq:             seq,
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Sending {
+                finished:    false,
+                ack_seq:     None,
+                ack_timeout: config.ack_timeout,
+                tx_done:     None,
             },
-            content: mac::FrameContent::Data,
-            payload: data,
-            footer: [0; 2],
-        };
-
-        delayed_time.map(|time| {
-            self.ll
-                .dx_time()
-                .write(|w|
-                    w.value(time.value())
-                )
-        });
+        })
+    }
 
-        // Prepare transmitter
-        let mut len = 0;
-        self.ll
-            .tx_buffer()
-            .write(|w| {
-                len += frame.encode(&mut w.data(), mac::WriteFooter::No);
-                w
-            })?;
-        self.ll
-            .tx_fctrl()
-            .modify(|_, w| {
-                let tflen = len as u8 + 2;
-                w
-                    .tflen(tflen) // data length + two-octet CRC
-                    .tfle(0)      // no non-standard length extension
-                    .txboffs(0)   // no offset in TX_BUFFER
-                    .txbr(config.bitrate as u8) // configured bitrate
-                    .tr(config.ranging_enable as u8) // configured ranging bit
-                    .txprf(config.pulse_repetition_frequency as u8) // configured PRF
-                    .txpsr(((config.preamble_length as u8) & 0b1100) >> 2) // first two bits of configured preamble length
-                    .pe((config.preamble_length as u8) & 0b0011) // last two bits of configured preamble length
-            })?;
+    /// Writes an IEEE 802.15.4 MAC frame to TX_BUFFER, to be sent later
+    ///
+    /// The frame is built like in [`send`], from `data`, `destination` and
+    /// `config`, and written to TX_BUFFER, starting at `offset`. It can be sent
+    /// any number of times using [`send_preloaded`], and modified in between
+    /// using [`patch_preloaded`]. This moves most of the work of sending a
+    /// frame out of time-critical code, like replying to a ranging message.
+    ///
+    /// Multiple frames can be kept in TX_BUFFER at the same time, as long as
+    /// they don't overlap. Frames sent using [`send`] or [`send_raw`] are
+    /// written to offset 0, and will overwrite preloaded frames there.
+    ///
+    /// The frame's sequence number is fixed when it's preloaded. Returns
+    /// [`Error::TxBufferOutOfRange`], if the frame doesn't fit into TX_BUFFER
+    /// at `offset`.
+    ///
+    /// [`send`]: #method.send
+    /// [`send_raw`]: #method.send_raw
+    /// [`send_preloaded`]: #method.send_preloaded
+    /// [`patch_preloaded`]: #method.patch_preloaded
+    /// [`Error::TxBufferOutOfRange`]: enum.Error.html#variant.TxBufferOutOfRange
+    pub fn preload(&mut self,
+        offset:      u16,
+        data:        &[u8],
+        destination: mac::Address,
+        config:      &TxConfig,
+    )
+        -> Result<PreloadedFrame, Error<SPI, CS>>
+    {
+        let (len, header_len, seq) =
+            self.write_frame(offset, data, destination, config)?;
+
+        Ok(PreloadedFrame {
+            offset,
+            len:            len as u16,
+            payload_offset: header_len as u16,
+            seq:            Some(seq),
+        })
+    }
 
-        // Set the channel and sfd settings
-        self.ll
-            .chan_ctrl()
-            .modify(|_, w| {
-                w
-                    .tx_chan(config.channel as u8)
-                    .rx_chan(config.channel as u8)
-                    .dwsfd((config.sfd_sequence == SfdSequence::Decawave || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                    .rxprf(config.pulse_repetition_frequency as u8)
-                    .tnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                    .rnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                    .tx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
-                    .rx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
-            })?;
+    /// Writes raw data to TX_BUFFER, to be sent later
+    ///
+    /// Works like [`preload`], except that `data` is written as-is, like
+    /// [`send_raw`] does.
+    ///
+    /// [`preload`]: #method.preload
+    /// [`send_raw`]: #method.send_raw
+    pub fn preload_raw(&mut self, offset: u16, data: &[u8])
+        -> Result<PreloadedFrame, Error<SPI, CS>>
+    {
+        check_tx_buffer_range(offset, data.len())?;
+        self.ll.tx_buffer().write_at(offset, data)?;
+
+        Ok(PreloadedFrame {
+            offset,
+            len:            data.len() as u16,
+            payload_offset: 0,
+            seq:            None,
+        })
+    }
 
-        match config.sfd_sequence {
-            SfdSequence::IEEE => {}, // IEEE has predefined sfd lengths and the register has no effect.
-            SfdSequence::Decawave => self.ll.sfd_length().write(|w| w.value(8))?, // This isn't entirely necessary as the Decawave8 settings in chan_ctrl already force it to 8
-            SfdSequence::DecawaveAlt => self.ll.sfd_length().write(|w| w.value(16))?, // Set to 16
-            SfdSequence::User => {}, // Users are responsible for setting the lengths themselves
+    /// Overwrites part of a preloaded frame
+    ///
+    /// Writes `data` to the payload of `frame`, starting `position` bytes into
+    /// the payload. Only those bytes are transferred to the DW1000, which makes
+    /// this much faster than writing the whole frame, for example to add a
+    /// time stamp right before sending.
+    ///
+    /// Returns [`Error::TxBufferOutOfRange`], if `data` doesn't fit into the
+    /// payload at `position`.
+    ///
+    /// [`Error::TxBufferOutOfRange`]: enum.Error.html#variant.TxBufferOutOfRange
+    pub fn patch_preloaded(&mut self,
+        frame:    &PreloadedFrame,
+        position: u16,
+        data:     &[u8],
+    )
+        -> Result<(), Error<SPI, CS>>
+    {
+        let end = position as usize + data.len();
+        if end > frame.payload_len() {
+            return Err(Error::TxBufferOutOfRange);
         }
 
-        // Tune for the correct channel
-        self.ll.rf_txctrl().write(|w| w.value(config.channel.get_recommended_rf_txctrl()))?;
-        self.ll.tc_pgdelay().write(|w| w.value(config.channel.get_recommended_tc_pgdelay()))?;
-        self.ll.fs_pllcfg().write(|w| w.value(config.channel.get_recommended_fs_pllcfg()))?;
-        self.ll.fs_plltune().write(|w| w.value(config.channel.get_recommended_fs_plltune()))?;
+        let offset = frame.offset + frame.payload_offset + position;
+        self.ll.tx_buffer().write_at(offset, data)?;
 
-        // Todo: Power control (register 0x1E)
+        Ok(())
+    }
 
-        // Start transmission
-        self.ll
-            .sys_ctrl()
-            .modify(|_, w|
-                if delayed_time.is_some() { w.txdlys(0b1) } else { w }
-                    .txstrt(0b1)
-            )?;
+    /// Sends a preloaded frame
+    ///
+    /// Works like [`send`], except that the frame has already been written to
+    /// TX_BUFFER by [`preload`] or [`preload_raw`]. It is sent from its
+    /// position in TX_BUFFER, which is left unchanged.
+    ///
+    /// `config` should be the same configuration the frame was preloaded with.
+    /// Returns [`Error::InvalidConfiguration`], if it requests an
+    /// acknowledgement for a frame preloaded with [`preload_raw`]. Returns
+    /// [`Error::FrameTooLong`], if the frame requires long frames, but
+    /// `config` doesn't enable them.
+    ///
+    /// [`send`]: #method.send
+    /// [`preload`]: #method.preload
+    /// [`preload_raw`]: #method.preload_raw
+    /// [`Error::InvalidConfiguration`]: enum.Error.html#variant.InvalidConfiguration
+    /// [`Error::FrameTooLong`]: enum.Error.html#variant.FrameTooLong
+    pub fn send_preloaded(mut self,
+        frame:        &PreloadedFrame,
+        delayed_time: Option<Instant>,
+        config:       TxConfig,
+    )
+        -> Result<DW1000<SPI, CS, Sending>, Error<SPI, CS>>
+    {
+        let ack_seq = match (config.ack_request, frame.seq) {
+            (false, _)        => None,
+            (true, Some(seq)) => Some(seq),
+            (true, None)      => return Err(Error::InvalidConfiguration),
+        };
+        check_frame_len(frame.len as usize, &config)?;
+
+        self.prepare_transmitter()?;
+        self.configure_transmitter(
+            frame.offset,
+            frame.len as usize,
+            delayed_time,
+            &config,
+        )?;
+        self.start_tx(delayed_time.is_some(), config.ack_request)?;
 
         Ok(DW1000 {
             ll:    self.ll,
             seq:   self.seq,
-            state: Sending { finished: false },
+            state: Sending {
+                finished:    false,
+                ack_seq,
+                ack_timeout: config.ack_timeout,
+                tx_done:     None,
+            },
         })
     }
 
@@ -331,233 +467,1422 @@ impl<SPI, CS> DW1000<SPI, CS, Ready>
     pub fn receive(mut self, config: RxConfig)
         -> Result<DW1000<SPI, CS, Receiving>, Error<SPI, CS>>
     {
-        // For unknown reasons, the DW1000 gets stuck in RX mode without ever
-        // receiving anything, after receiving one good frame. Reset the
-        // receiver to make sure its in a valid state before attempting to
-        // receive anything.
-        self.ll
-            .pmsc_ctrl0()
-            .modify(|_, w|
-                w.softreset(0b1110) // reset receiver
-            )?;
-        self.ll
-            .pmsc_ctrl0()
-            .modify(|_, w|
-                w.softreset(0b1111) // clear reset
-            )?;
+        self.configure_receiver(config)?;
+        self.start_rx(None)?;
 
-        // We're already resetting the receiver in the previous step, and that's
-        // good enough to make my example program that's both sending and
-        // receiving work very reliably over many hours (that's not to say it
-        // becomes unreliable after those hours, that's just when my test
-        // stopped). However, I've seen problems with an example program that
-        // only received, never sent, data. That got itself into some weird
-        // state where it couldn't receive anymore.
-        // I suspect that's because that example didn't have the following line
-        // of code, while the send/receive example had that line of code, being
-        // called from `send`.
-        // While I haven't, as of this writing, run any hours-long tests to
-        // confirm this does indeed fix the receive-only example, it seems
-        // (based on my eyeball-only measurements) that the RX/TX example is
-        // dropping fewer frames now.
-        self.force_idle()?;
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Receiving {
+                finished:        false,
+                double_buffered: config.double_buffered,
+                continuous:      config.continuous,
+            },
+        })
+    }
 
-        if config.frame_filtering {
-            self.ll
-                .sys_cfg()
-                .modify(|_, w|
-                    w
-                        .ffen(0b1) // enable frame filtering
-                        .ffab(0b1) // receive beacon frames
-                        .ffad(0b1) // receive data frames
-                        .ffaa(0b1) // receive acknowledgement frames
-                        .ffam(0b1) // receive MAC command frames
-                )?;
-        }
-        else {
-            self.ll
-                .sys_cfg()
-                .modify(|_, w| w.ffen(0b0))?; // disable frame filtering
-        }
+    /// Attempt to receive an IEEE 802.15.4 MAC frame, starting at a given time
+    ///
+    /// Works like [`receive`], except that the receiver is only enabled once
+    /// the system time reaches `delayed_time`. This saves power, if the time
+    /// a frame is going to arrive is known, for example when waiting for the
+    /// reply in a ranging exchange. The lower 9 bits of `delayed_time` are
+    /// ignored, so the receiver might be enabled up to 8 ns earlier.
+    ///
+    /// If `delayed_time` has already passed, [`Receiving::wait`] cancels the
+    /// receive operation and returns [`Error::DelayedReceiveTooLate`].
+    ///
+    /// [`receive`]: #method.receive
+    /// [`Receiving::wait`]: #method.wait-1
+    /// [`Error::DelayedReceiveTooLate`]: enum.Error.html#variant.DelayedReceiveTooLate
+    pub fn receive_delayed(mut self, delayed_time: Instant, config: RxConfig)
+        -> Result<DW1000<SPI, CS, Receiving>, Error<SPI, CS>>
+    {
+        self.configure_receiver(config)?;
+        self.start_rx(Some(delayed_time))?;
 
-        // Set PLLLDT bit in EC_CTRL. According to the documentation of the
-        // CLKPLL_LL bit in SYS_STATUS, this bit needs to be set to ensure the
-        // reliable operation of the CLKPLL_LL bit. Since I've seen that bit
-        // being set, I want to make sure I'm not just seeing crap.
-        self.ll
-            .ec_ctrl()
-            .modify(|_, w|
-                w.pllldt(0b1)
-            )?;
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Receiving {
+                finished:        false,
+                double_buffered: config.double_buffered,
+                continuous:      config.continuous,
+            },
+        })
+    }
 
-        // Now that PLLLDT is set, clear all bits in SYS_STATUS that depend on
-        // it for reliable operation. After that is done, these bits should work
-        // reliably.
-        self.ll
-            .sys_status()
-            .write(|w|
+    /// Send an IEEE 802.15.4 MAC frame, then receive the response
+    ///
+    /// Works like [`send`], except that the receiver is enabled automatically
+    /// after the transmission has finished, without any involvement from the
+    /// host. This makes sure that responses which arrive shortly after the
+    /// transmission are not missed. `response_delay` is the time between the
+    /// end of the transmission and the receiver being enabled, in units of
+    /// roughly 1 µs (512/499.2 MHz, to be precise). It must fit into 20 bits.
+    ///
+    /// `tx_config` and `rx_config` must use the same channel and PRF, as the
+    /// DW1000 can't switch between them without the host's help. If either
+    /// enables long frames, both the frame and the response may be long.
+    ///
+    /// This method starts the transmission and returns immediately thereafter.
+    /// It consumes this instance of `DW1000` and returns another instance which
+    /// is in the `Exchanging` state, and can be used to wait for the
+    /// transmission to finish and for the response to arrive.
+    ///
+    /// [`send`]: #method.send
+    pub fn send_and_receive(mut self,
+        data:           &[u8],
+        destination:    mac::Address,
+        delayed_time:   Option<Instant>,
+        tx_config:      TxConfig,
+        rx_config:      RxConfig,
+        response_delay: u32,
+    )
+        -> Result<DW1000<SPI, CS, Exchanging>, Error<SPI, CS>>
+    {
+        if response_delay >= 1 << 20 {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        self.prepare_send(data, destination, delayed_time, &tx_config)?;
+        self.configure_receiver(rx_config)?;
+
+        // Transmitter and receiver share the PHR mode. The long frame mode
+        // handles frames of standard length just fine, so it wins.
+        if tx_config.long_frames {
+            self.ll.sys_cfg().modify(|_, w| w.phr_mode(phr_mode(true)))?;
+        }
+        self.ll.ack_resp_t().modify(|_, w| w.w4r_tim(response_delay))?;
+        self.start_tx(delayed_time.is_some(), true)?;
+
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Exchanging { sent: false, finished: false },
+        })
+    }
+
+    /// Enables transmit interrupts for the events that `wait` checks
+    ///
+    /// Overwrites any interrupt flags that were previously set.
+    pub fn enable_tx_interrupts(&mut self)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll.sys_mask().modify(|_, w| w.mtxfrs(0b1))?;
+        Ok(())
+    }
+
+    /// Enables receive interrupts for the events that `wait` checks
+    ///
+    /// Overwrites any interrupt flags that were previously set.
+    pub fn enable_rx_interrupts(&mut self)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll()
+            .sys_mask()
+            .modify(|_, w|
                 w
-                    .cplock(0b1)
-                    .clkpll_ll(0b1)
+                    .mrxdfr(0b1)
+                    .mrxfce(0b1)
+                    .mrxphe(0b1)
+                    .mrxrfsl(0b1)
+                    .mrxrfto(0b1)
+                    .mrxovrr(0b1)
+                    .mrxpto(0b1)
+                    .mrxsfdto(0b1)
+                    .maffrej(0b1)
+                    .mldedone(0b1)
+                    .mhpdwarn(0b1)
             )?;
 
-        // Apply the config
-        self.ll.chan_ctrl().modify(|_, w| {
+        Ok(())
+    }
+
+    /// Disables all interrupts
+    pub fn disable_interrupts(&mut self)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll.sys_mask().write(|w| w)?;
+        Ok(())
+    }
+
+    /// Configures the gpio pins to operate as LED output.
+    ///
+    /// - Note: This means that the function of the gpio pins change
+    /// - Note: Both the kilohertz and debounce clock will be turned on or off
+    /// ---
+    /// - RXOKLED will change GPIO0
+    /// - SFDLED will change GPIO1
+    /// - RXLED will change GPIO2
+    /// - TXLED will change GPIO3
+    ///
+    /// blink_time is in units of 14 ms
+    pub fn configure_leds(
+        &mut self,
+        enable_rx_ok: bool,
+        enable_sfd: bool,
+        enable_rx: bool,
+        enable_tx: bool,
+        blink_time: u8)
+        -> Result<(), Error<SPI, CS>> {
+        // Turn on the timer that will control the blinking (The debounce clock)
+        self.ll.pmsc_ctrl0().modify(|_, w| {
             w
-                .tx_chan(config.channel as u8)
-                .rx_chan(config.channel as u8)
-                .dwsfd((config.sfd_sequence == SfdSequence::Decawave || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                .rxprf(config.pulse_repetition_frequency as u8)
-                .tnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                .rnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
-                .tx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
-                .rx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
+                .gpdce((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
+                .khzclken((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
         })?;
 
-        match config.sfd_sequence {
-            SfdSequence::IEEE => {}, // IEEE has predefined sfd lengths and the register has no effect.
-            SfdSequence::Decawave => self.ll.sfd_length().write(|w| w.value(8))?, // This isn't entirely necessary as the Decawave8 settings in chan_ctrl already force it to 8
-            SfdSequence::DecawaveAlt => self.ll.sfd_length().write(|w| w.value(16))?, // Set to 16
-            SfdSequence::User => {}, // Users are responsible for setting the lengths themselves
+        // Turn on the led blinking
+        self.ll.pmsc_ledc().modify(|_, w| {
+           w
+               .blnken((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
+               .blink_tim(blink_time)
+        })?;
+
+        // Set the proper gpio mode
+        self.ll.gpio_mode().modify(|_, w| {
+            w
+                .msgp0(enable_rx_ok as u8)
+                .msgp1(enable_sfd as u8)
+                .msgp2(enable_rx as u8)
+                .msgp3(enable_tx as u8)
+        })?;
+
+        Ok(())
+    }
+
+    /// Provides access to the OTP memory
+    pub fn otp(&mut self) -> Otp<'_, SPI, CS> {
+        Otp::new(&mut self.ll)
+    }
+
+    /// Measures the chip temperature and supply voltage
+    ///
+    /// Runs the SAR ADC, as described in the user manual, section 6.4, and
+    /// converts the readings using the calibration values that were
+    /// programmed into OTP memory during production. `delay` is used to wait
+    /// for the conversion to finish.
+    pub fn read_temperature_and_voltage<D>(&mut self, delay: &mut D)
+        -> Result<SarReading, Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        // Enable the sensor and ADC biases, then the outputs. These must be
+        // separate writes, in this order.
+        self.ll.rf_sensor_bias().write(|w| w.value(0x80))?;
+        self.ll.rf_sar_cfg().write(|w| w.value(0x0A))?;
+        self.ll.rf_sar_cfg().write(|w| w.value(0x0F))?;
+
+        // Run the conversion.
+        self.ll.tc_sarc().write(|w| w.sar_ctrl(0b0))?;
+        self.ll.tc_sarc().write(|w| w.sar_ctrl(0b1))?;
+        delay.delay_us(SAR_CONVERSION_TIME_US);
+
+        let tc_sarl = self.ll.tc_sarl().read()?;
+        self.ll.tc_sarc().write(|w| w.sar_ctrl(0b0))?;
+
+        let mut otp = Otp::new(&mut self.ll);
+        let voltage_cal     = otp.voltage_calibration()?.at_3_3_v;
+        let temperature_cal = otp.temperature_calibration()?;
+
+        Ok(SarReading::new(
+            tc_sarl.sar_ltemp(),
+            tc_sarl.sar_lvbat(),
+            temperature_cal,
+            voltage_cal,
+        ))
+    }
+
+    /// Puts the DW1000 to sleep
+    ///
+    /// Aborts any ongoing operation and puts the DW1000 into the SLEEP or
+    /// DEEPSLEEP state, depending on [`SleepConfig::sleep_time`]. Use
+    /// [`DW1000::wake_up`] or [`DW1000::wake_up_with_pin`] to get it back.
+    ///
+    /// Returns [`Error::InvalidConfiguration`], if `config` wouldn't allow the
+    /// DW1000 to ever wake up again.
+    ///
+    /// [`SleepConfig::sleep_time`]: ../configs/struct.SleepConfig.html#structfield.sleep_time
+    /// [`DW1000::wake_up`]: #method.wake_up
+    /// [`DW1000::wake_up_with_pin`]: #method.wake_up_with_pin
+    /// [`Error::InvalidConfiguration`]: enum.Error.html#variant.InvalidConfiguration
+    pub fn enter_sleep(mut self, config: SleepConfig)
+        -> Result<DW1000<SPI, CS, Sleeping>, Error<SPI, CS>>
+    {
+        if config.sleep_time.is_none()
+            && !config.wake_on_spi
+            && !config.wake_on_pin
+        {
+            return Err(Error::InvalidConfiguration);
         }
 
-        // Set general tuning
-        self.ll.drx_tune0b().write(|w| w.value(config.bitrate.get_recommended_drx_tune0b(config.sfd_sequence)))?;
-        self.ll.drx_tune1a().write(|w| w.value(config.pulse_repetition_frequency.get_recommended_drx_tune1a()))?;
-        let drx_tune1b = config.expected_preamble_length.get_recommended_drx_tune1b(config.bitrate)?;
-        self.ll.drx_tune1b().write(|w| w.value(drx_tune1b))?;
-        let drx_tune2 = config.pulse_repetition_frequency.get_recommended_drx_tune2(config.expected_preamble_length.get_recommended_pac_size())?;
-        self.ll.drx_tune2().write(|w| w.value(drx_tune2))?;
-        self.ll.drx_tune4h().write(|w| w.value(config.expected_preamble_length.get_recommended_dxr_tune4h()))?;
+        self.force_idle()?;
 
-        // Set channel tuning
-        self.ll.rf_rxctrlh().write(|w| w.value(config.channel.get_recommended_rf_rxctrlh()))?;
-        self.ll.fs_pllcfg().write(|w| w.value(config.channel.get_recommended_fs_pllcfg()))?;
-        self.ll.fs_plltune().write(|w| w.value(config.channel.get_recommended_fs_plltune()))?;
+        // The RX antenna delay lives in the LDE's memory, which doesn't
+        // survive sleep.
+        let rx_antenna_delay = self.ll.lde_rxantd().read()?.value();
 
-        // Set the rx bitrate
-        self.ll.sys_cfg().modify(|_, w| w.rxm110k((config.bitrate == BitRate::Kbps110) as u8))?;
+        // Configure what happens on wake-up, and how to wake up. See user
+        // manual, sections 7.2.44.1 and 7.2.44.4.
+        self.ll.aon_wcfg().write(|w|
+            w
+                .onw_ldc(config.preserve_config as u8)
+                .onw_lld0(config.preserve_config as u8)
+        )?;
+        self.ll.aon_cfg1().modify(|_, w|
+            w.sleep_ce(config.sleep_time.is_some() as u8)
+        )?;
+        self.ll.aon_cfg0().modify(|r, w|
+            w
+                .sleep_en(0b1)
+                .wake_pin(config.wake_on_pin as u8)
+                .wake_spi(config.wake_on_spi as u8)
+                .wake_cnt(config.sleep_time.is_some() as u8)
+                .sleep_tim(config.sleep_time.unwrap_or(r.sleep_tim()))
+        )?;
 
-        self.ll
-            .sys_ctrl()
-            .modify(|_, w|
-                w.rxenab(0b1)
-            )?;
+        // Upload the configuration into the AON block, then save the
+        // register set to the AON memory. Saving enters sleep.
+        self.ll.aon_ctrl().write(|w| w.upl_cfg(0b1))?;
+        self.ll.aon_ctrl().write(|w| w)?;
+        self.ll.aon_ctrl().write(|w| w.save(0b1))?;
 
         Ok(DW1000 {
             ll:    self.ll,
             seq:   self.seq,
-            state: Receiving { finished: false },
+            state: Sleeping {
+                preserve_config: config.preserve_config,
+                rx_antenna_delay,
+            },
         })
     }
 
-    /// Enables transmit interrupts for the events that `wait` checks
+    /// Prepares the transmission of a frame, without starting it
     ///
-    /// Overwrites any interrupt flags that were previously set.
-    pub fn enable_tx_interrupts(&mut self)
-        -> Result<(), Error<SPI, CS>>
+    /// Returns the sequence number of the frame.
+    fn prepare_send(&mut self,
+        data:         &[u8],
+        destination:  mac::Address,
+        delayed_time: Option<Instant>,
+        config:       &TxConfig,
+    )
+        -> Result<u8, Error<SPI, CS>>
     {
-        self.ll.sys_mask().modify(|_, w| w.mtxfrs(0b1))?;
+        self.prepare_transmitter()?;
+
+        let (len, _, seq) = self.write_frame(0, data, destination, config)?;
+        self.configure_transmitter(0, len, delayed_time, config)?;
+
+        Ok(seq)
+    }
+
+    /// Writes a MAC frame to TX_BUFFER, starting at `offset`
+    ///
+    /// Returns the length of the frame (without FCS), the length of its
+    /// header, and its sequence number.
+    fn write_frame(&mut self,
+        offset:      u16,
+        data:        &[u8],
+        destination: mac::Address,
+        config:      &TxConfig,
+    )
+        -> Result<(usize, usize, u8), Error<SPI, CS>>
+    {
+        let seq = self.seq.0;
+
+        let frame = mac::Frame {
+            header: mac::Header {
+                frame_type:      mac::FrameType::Data,
+                version:         mac::FrameVersion::Ieee802154_2006,
+                security:        mac::Security::None,
+                frame_pending:   false,
+                ack_request:     config.ack_request,
+                pan_id_compress: false,
+                destination,
+                source:          if config.extended_source {
+                    self.get_extended_address()?
+                }
+                else {
+                    self.get_address()?
+                },
+                seq,
+            },
+            content: mac::FrameContent::Data,
+            payload: data,
+            footer: [0; 2],
+        };
+
+        // Only the header is encoded here. The payload goes into TX_BUFFER
+        // directly, so it doesn't need to be copied into a frame-sized buffer
+        // first.
+        let mut header = [0; MAX_HEADER_LEN];
+        let header_len = frame.header.encode(&mut header);
+        let len        = header_len + frame.payload.len();
+        check_frame_len(len, config)?;
+        check_tx_buffer_range(offset, len)?;
+
+        let payload_offset = offset + header_len as u16;
+        self.ll.tx_buffer().write_at(offset, &header[..header_len])?;
+        self.ll.tx_buffer().write_at(payload_offset, frame.payload)?;
+
+        self.seq += Wrapping(1);
+
+        Ok((len, header_len, seq))
+    }
+
+    /// Gets the transmitter ready for a new frame
+    fn prepare_transmitter(&mut self) -> Result<(), Error<SPI, CS>> {
+        // Clear event counters
+        self.ll.evc_ctrl().write(|w| w.evc_clr(0b1))?;
+        while self.ll.evc_ctrl().read()?.evc_clr() == 0b1 {}
+
+        // (Re-)Enable event counters
+        self.ll.evc_ctrl().write(|w| w.evc_en(0b1))?;
+        while self.ll.evc_ctrl().read()?.evc_en() == 0b1 {}
+
+        // Sometimes, for unknown reasons, the DW1000 gets stuck in RX mode.
+        // Starting the transmitter won't get it to enter TX mode, which means
+        // all subsequent send operations will fail. Let's disable the
+        // transceiver and force the chip into IDLE mode to make sure that
+        // doesn't happen.
+        self.force_idle()?;
+
         Ok(())
     }
 
-    /// Enables receive interrupts for the events that `wait` checks
-    ///
-    /// Overwrites any interrupt flags that were previously set.
-    pub fn enable_rx_interrupts(&mut self)
-        -> Result<(), Error<SPI, CS>>
+    /// Configures the transmitter for a frame that is already in TX_BUFFER
+    ///
+    /// `offset` is the position of the frame in TX_BUFFER, `len` the length of
+    /// the frame data, without the FCS.
+    fn configure_transmitter(&mut self,
+        offset:       u16,
+        len:          usize,
+        delayed_time: Option<Instant>,
+        config:       &TxConfig,
+    )
+        -> Result<(), Error<SPI, CS>>
+    {
+        delayed_time.map(|time| {
+            self.ll
+                .dx_time()
+                .write(|w|
+                    w.value(time.value())
+                )
+        });
+
+        // Prepare transmitter
+        self.ll
+            .tx_fctrl()
+            .modify(|_, w| {
+                let len = len + FCS_LEN; // data length + two-octet CRC
+                w
+                    .tflen(len as u8 & 0x7f) // lower 7 bits of length
+                    .tfle((len >> 7) as u8)  // non-standard length extension
+                    .txboffs(offset) // position of frame in TX_BUFFER
+                    .txbr(config.bitrate) // configured bitrate
+                    .tr(config.ranging_enable as u8) // configured ranging bit
+                    .txprf(config.pulse_repetition_frequency) // configured PRF
+                    .preamble_length(config.preamble_length)
+            })?;
+
+        // Set the channel and sfd settings
+        self.ll
+            .chan_ctrl()
+            .modify(|_, w| {
+                w
+                    .tx_chan(config.channel)
+                    .rx_chan(config.channel)
+                    .dwsfd((config.sfd_sequence == SfdSequence::Decawave || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                    .rxprf(config.pulse_repetition_frequency)
+                    .tnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                    .rnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                    .tx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
+                    .rx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
+            })?;
+
+        match config.sfd_sequence {
+            SfdSequence::IEEE => {}, // IEEE has predefined sfd lengths and the register has no effect.
+            SfdSequence::Decawave => self.ll.sfd_length().write(|w| w.value(8))?, // This isn't entirely necessary as the Decawave8 settings in chan_ctrl already force it to 8
+            SfdSequence::DecawaveAlt => self.ll.sfd_length().write(|w| w.value(16))?, // Set to 16
+            SfdSequence::User => {}, // Users are responsible for setting the lengths themselves
+        }
+
+        // Tune for the correct channel
+        self.ll.rf_txctrl().write(|w| w.value(config.channel.get_recommended_rf_txctrl()))?;
+        self.ll.tc_pgdelay().write(|w| w.value(config.channel.get_recommended_tc_pgdelay()))?;
+        self.ll.fs_pllcfg().write(|w| w.value(config.channel.get_recommended_fs_pllcfg()))?;
+        self.ll.fs_plltune().write(|w| w.value(config.channel.get_recommended_fs_plltune()))?;
+
+        // Set the transmit power
+        let tx_power = config.tx_power.unwrap_or_else(||
+            config.channel.get_recommended_tx_power(
+                config.pulse_repetition_frequency,
+                true,
+            )
+        );
+        self.ll.tx_power().write(|w| w.value(tx_power.to_register_value()))?;
+        self.ll.sys_cfg().modify(|_, w| w.dis_stxp(!tx_power.is_smart() as u8))?;
+
+        // If we're requesting an acknowledgement, the receiver needs to be
+        // enabled right after the transmission, and must give up after the
+        // configured timeout.
+        if config.ack_request {
+            self.ll.ack_resp_t().modify(|_, w| w.w4r_tim(0))?;
+            self.ll.rx_fwto().write(|w| w.value(config.ack_timeout))?;
+        }
+        self.ll
+            .sys_cfg()
+            .modify(|_, w|
+                w
+                    .rxwtoe(config.ack_request as u8) // frame wait timeout
+                    .ffaa(0b1) // receive acknowledgement frames
+                    .phr_mode(phr_mode(config.long_frames))
+            )?;
+
+        Ok(())
+    }
+
+    /// Starts a transmission that has been prepared by `prepare_send`
+    ///
+    /// If `wait_for_response` is true, the receiver is enabled automatically
+    /// after the transmission.
+    fn start_tx(&mut self, delayed: bool, wait_for_response: bool)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll
+            .sys_ctrl()
+            .modify(|_, w|
+                if delayed { w.txdlys(0b1) } else { w }
+                    .wait4resp(wait_for_response as u8)
+                    .txstrt(0b1)
+            )?;
+
+        Ok(())
+    }
+
+    /// Enables the receiver, which must have been configured before
+    fn start_rx(&mut self, delayed_time: Option<Instant>)
+        -> Result<(), Error<SPI, CS>>
+    {
+        if let Some(time) = delayed_time {
+            self.ll.dx_time().write(|w| w.value(time.value()))?;
+        }
+
+        self.ll
+            .sys_ctrl()
+            .modify(|_, w|
+                if delayed_time.is_some() { w.rxdlye(0b1) } else { w }
+                    .rxenab(0b1)
+            )?;
+
+        Ok(())
+    }
+
+    /// Configures the receiver, without enabling it
+    fn configure_receiver(&mut self, config: RxConfig)
+        -> Result<(), Error<SPI, CS>>
+    {
+        // A timeout of zero would disable the timeout in hardware, which is not
+        // what the user asked for.
+        if config.frame_wait_timeout == Some(0)
+            || config.preamble_detection_timeout == Some(0)
+        {
+            return Err(Error::InvalidConfiguration);
+        }
+
+        // An on time of zero disables SNIFF mode, and the field only has four
+        // bits.
+        if let Some(sniff_mode) = config.sniff_mode {
+            if sniff_mode.on_time == 0 || sniff_mode.on_time > 15 {
+                return Err(Error::InvalidConfiguration);
+            }
+        }
+
+        // For unknown reasons, the DW1000 gets stuck in RX mode without ever
+        // receiving anything, after receiving one good frame. Reset the
+        // receiver to make sure its in a valid state before attempting to
+        // receive anything.
+        self.ll
+            .pmsc_ctrl0()
+            .modify(|_, w|
+                w.softreset(ll::SoftReset::Receiver)
+            )?;
+        self.ll
+            .pmsc_ctrl0()
+            .modify(|_, w|
+                w.softreset(ll::SoftReset::Clear)
+            )?;
+
+        // We're already resetting the receiver in the previous step, and that's
+        // good enough to make my example program that's both sending and
+        // receiving work very reliably over many hours (that's not to say it
+        // becomes unreliable after those hours, that's just when my test
+        // stopped). However, I've seen problems with an example program that
+        // only received, never sent, data. That got itself into some weird
+        // state where it couldn't receive anymore.
+        // I suspect that's because that example didn't have the following line
+        // of code, while the send/receive example had that line of code, being
+        // called from `send`.
+        // While I haven't, as of this writing, run any hours-long tests to
+        // confirm this does indeed fix the receive-only example, it seems
+        // (based on my eyeball-only measurements) that the RX/TX example is
+        // dropping fewer frames now.
+        self.force_idle()?;
+
+        // Errors from a previous receive operation, like timeouts, must not be
+        // reported for this one.
+        self.reset_rx_flags()?;
+
+        if config.frame_filtering {
+            self.ll
+                .sys_cfg()
+                .modify(|_, w|
+                    w
+                        .ffen(0b1) // enable frame filtering
+                        .ffab(0b1) // receive beacon frames
+                        .ffad(0b1) // receive data frames
+                        .ffaa(0b1) // receive acknowledgement frames
+                        .ffam(0b1) // receive MAC command frames
+                )?;
+        }
+        else {
+            self.ll
+                .sys_cfg()
+                .modify(|_, w| w.ffen(0b0))?; // disable frame filtering
+        }
+
+        // Automatic acknowledgement requires frame filtering.
+        self.ll
+            .sys_cfg()
+            .modify(|_, w|
+                w
+                    .autoack((config.auto_ack && config.frame_filtering) as u8)
+                    .rxwtoe(config.frame_wait_timeout.is_some() as u8)
+                    .dis_drxb((!config.double_buffered) as u8)
+                    .rxautr(config.continuous as u8)
+                    .phr_mode(phr_mode(config.long_frames))
+            )?;
+
+        // In double-buffered mode, the host side buffer must be the one the
+        // receiver writes to first. The pointers can only be changed by
+        // toggling the host side pointer. See user manual, section 4.3.
+        if config.double_buffered {
+            let sys_status = self.ll.sys_status().read()?;
+            if sys_status.icrbp() != sys_status.hsrbp() {
+                self.ll.sys_ctrl().modify(|_, w| w.hrbpt(0b1))?;
+            }
+        }
+
+        // Configure timeouts. A preamble detection timeout of zero disables
+        // it.
+        if let Some(frame_wait_timeout) = config.frame_wait_timeout {
+            self.ll.rx_fwto().write(|w| w.value(frame_wait_timeout))?;
+        }
+        self.ll
+            .drx_pretoc()
+            .write(|w| w.count(config.preamble_detection_timeout.unwrap_or(0)))?;
+        self.ll
+            .ack_resp_t()
+            .modify(|_, w| w.ack_tim(config.bitrate.get_recommended_ack_tim()))?;
+
+        // Configure SNIFF mode. PLL2 sequencing allows the receiver to be
+        // switched off during the off phase. See user manual, section 4.5.
+        let (sniff_ont, sniff_offt) = config.sniff_mode
+            .map(|sniff_mode| (sniff_mode.on_time, sniff_mode.off_time))
+            .unwrap_or((0, 0));
+        self.ll
+            .rx_sniff()
+            .write(|w|
+                w
+                    .sniff_ont(sniff_ont)
+                    .sniff_offt(sniff_offt)
+            )?;
+        self.ll
+            .pmsc_ctrl0()
+            .modify(|_, w| w.pll2_seq_en(config.sniff_mode.is_some() as u8))?;
+
+        // Set PLLLDT bit in EC_CTRL. According to the documentation of the
+        // CLKPLL_LL bit in SYS_STATUS, this bit needs to be set to ensure the
+        // reliable operation of the CLKPLL_LL bit. Since I've seen that bit
+        // being set, I want to make sure I'm not just seeing crap.
+        self.ll
+            .ec_ctrl()
+            .modify(|_, w|
+                w.pllldt(0b1)
+            )?;
+
+        // Now that PLLLDT is set, clear all bits in SYS_STATUS that depend on
+        // it for reliable operation. After that is done, these bits should work
+        // reliably.
+        self.ll
+            .sys_status()
+            .write(|w|
+                w
+                    .cplock(0b1)
+                    .clkpll_ll(0b1)
+            )?;
+
+        // Apply the config
+        self.ll.chan_ctrl().modify(|_, w| {
+            w
+                .tx_chan(config.channel)
+                .rx_chan(config.channel)
+                .dwsfd((config.sfd_sequence == SfdSequence::Decawave || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                .rxprf(config.pulse_repetition_frequency)
+                .tnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                .rnssfd((config.sfd_sequence == SfdSequence::User || config.sfd_sequence == SfdSequence::DecawaveAlt) as u8)
+                .tx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
+                .rx_pcode(config.channel.get_recommended_preamble_code(config.pulse_repetition_frequency))
+        })?;
+
+        match config.sfd_sequence {
+            SfdSequence::IEEE => {}, // IEEE has predefined sfd lengths and the register has no effect.
+            SfdSequence::Decawave => self.ll.sfd_length().write(|w| w.value(8))?, // This isn't entirely necessary as the Decawave8 settings in chan_ctrl already force it to 8
+            SfdSequence::DecawaveAlt => self.ll.sfd_length().write(|w| w.value(16))?, // Set to 16
+            SfdSequence::User => {}, // Users are responsible for setting the lengths themselves
+        }
+
+        // Set general tuning
+        self.ll.drx_tune0b().write(|w| w.value(config.bitrate.get_recommended_drx_tune0b(config.sfd_sequence)))?;
+        self.ll.drx_tune1a().write(|w| w.value(config.pulse_repetition_frequency.get_recommended_drx_tune1a()))?;
+        let drx_tune1b = config.expected_preamble_length.get_recommended_drx_tune1b(config.bitrate)?;
+        self.ll.drx_tune1b().write(|w| w.value(drx_tune1b))?;
+        let drx_tune2 = config.pulse_repetition_frequency.get_recommended_drx_tune2(config.expected_preamble_length.get_recommended_pac_size())?;
+        self.ll.drx_tune2().write(|w| w.value(drx_tune2))?;
+        self.ll.drx_tune4h().write(|w| w.value(config.expected_preamble_length.get_recommended_dxr_tune4h()))?;
+
+        // Set channel tuning
+        self.ll.rf_rxctrlh().write(|w| w.value(config.channel.get_recommended_rf_rxctrlh()))?;
+        self.ll.fs_pllcfg().write(|w| w.value(config.channel.get_recommended_fs_pllcfg()))?;
+        self.ll.fs_plltune().write(|w| w.value(config.channel.get_recommended_fs_plltune()))?;
+
+        // Set the rx bitrate
+        self.ll.sys_cfg().modify(|_, w| w.rxm110k((config.bitrate == BitRate::Kbps110) as u8))?;
+
+        Ok(())
+    }
+}
+
+impl<SPI, CS> DW1000<SPI, CS, Sending>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Wait for the transmission to finish
+    ///
+    /// This method returns an `nb::Result` to indicate whether the transmission
+    /// has finished, or whether it is still ongoing. You can use this to busily
+    /// wait for the transmission to finish, for example using `nb`'s `block!`
+    /// macro, or you can use it in tandem with [`DW1000::enable_tx_interrupts`]
+    /// and the DW1000 IRQ output to wait in a more energy-efficient manner.
+    ///
+    /// Handling the DW1000's IRQ output line is out of the scope of this
+    /// driver, but please note that if you're using the DWM1001 module or
+    /// DWM1001-Dev board, that the `dwm1001` crate has explicit support for
+    /// this.
+    pub fn wait(&mut self)
+        -> nb::Result<(), Error<SPI, CS>>
+    {
+        self.wait_tx()?;
+
+        // The acknowledgement timeout starts at the end of the transmission.
+        // Remember when that was, so `wait_ack` can enforce it.
+        if !self.state.finished && self.state.ack_seq.is_some() {
+            self.state.tx_done = Some(
+                self.tx_end().map_err(nb::Error::Other)?
+            );
+        }
+        self.state.finished = true;
+
+        Ok(())
+    }
+
+    /// Wait for the acknowledgement of the sent frame
+    ///
+    /// Can only be used, if the frame was sent with
+    /// [`TxConfig::ack_request`] enabled. Waits for the transmission to
+    /// finish first, so there's no need to call [`wait`] before this.
+    ///
+    /// Returns `Ok(())`, once an acknowledgement with the sequence number of
+    /// the sent frame has arrived. Returns [`Error::AckTimeout`], if none
+    /// arrived within [`TxConfig::ack_timeout`]. Other frames that arrive in
+    /// the meantime are ignored.
+    ///
+    /// As with [`wait`], this method can be used together with interrupts.
+    /// Enable them using [`DW1000::enable_rx_interrupts`].
+    ///
+    /// [`TxConfig::ack_request`]: ../configs/struct.TxConfig.html#structfield.ack_request
+    /// [`TxConfig::ack_timeout`]: ../configs/struct.TxConfig.html#structfield.ack_timeout
+    /// [`wait`]: #method.wait
+    /// [`Error::AckTimeout`]: enum.Error.html#variant.AckTimeout
+    pub fn wait_ack(&mut self)
+        -> nb::Result<(), Error<SPI, CS>>
+    {
+        let seq = match self.state.ack_seq {
+            Some(seq) => seq,
+            None      => return Err(nb::Error::Other(Error::NoAckRequested)),
+        };
+
+        if !self.state.finished {
+            self.wait()?;
+        }
+
+        let sys_status = self.ll
+            .sys_status()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+        if sys_status.rxrfto() == 0b1 {
+            self.reset_rx_flags()
+                .map_err(nb::Error::Other)?;
+            self.state.ack_seq = None;
+            return Err(nb::Error::Other(Error::AckTimeout));
+        }
+
+        let rx_error = sys_status.rxfce() == 0b1
+            || sys_status.rxphe() == 0b1
+            || sys_status.rxrfsl() == 0b1
+            || sys_status.rxsfdto() == 0b1;
+        if sys_status.rxdfr() == 0b0 && !rx_error {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        if sys_status.rxdfr() == 0b1 && !rx_error {
+            let rx_finfo = self.ll
+                .rx_finfo()
+                .read()
+                .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+            let len = rx_finfo.rxflen() as usize
+                | (rx_finfo.rxfle() as usize) << 7;
+
+            // Anything that isn't the length of an acknowledgement can't be
+            // one, so there's no need to read it.
+            if len == ACK_FRAME_LEN {
+                let mut ack = [0; ACK_FRAME_LEN];
+                self.ll
+                    .rx_buffer()
+                    .read_at(0, &mut ack)
+                    .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+                if let Ok(frame) = mac::Frame::decode(&ack, true) {
+                    if frame.header.frame_type == mac::FrameType::Acknowledgement
+                        && frame.header.seq == seq
+                    {
+                        self.reset_rx_flags()
+                            .map_err(nb::Error::Other)?;
+                        self.state.ack_seq = None;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        // Whatever we received, it wasn't our acknowledgement. The receiver
+        // has been disabled, so re-enable it and keep waiting. Enabling the
+        // receiver restarts the frame wait timeout, so only give it the time
+        // that's left. Otherwise unrelated frames would extend the timeout
+        // indefinitely.
+        self.reset_rx_flags()
+            .map_err(nb::Error::Other)?;
+
+        let remaining = self.remaining_ack_timeout()
+            .map_err(nb::Error::Other)?;
+        if remaining == 0 {
+            self.state.ack_seq = None;
+            return Err(nb::Error::Other(Error::AckTimeout));
+        }
+
+        self.ll
+            .rx_fwto()
+            .write(|w| w.value(remaining))
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+        self.ll
+            .sys_ctrl()
+            .modify(|_, w| w.rxenab(0b1))
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+        Err(nb::Error::WouldBlock)
+    }
+
+    /// Returns the time at which the transmitted frame ended
+    ///
+    /// This is when the receiver was enabled to wait for the acknowledgement,
+    /// which starts the frame wait timeout. Only valid once the transmission
+    /// has finished.
+    fn tx_end(&mut self) -> Result<Instant, Error<SPI, CS>> {
+        let tx_time  = self.ll.tx_time().read()?;
+        let tx_fctrl = self.ll.tx_fctrl().read()?;
+
+        let len = tx_fctrl.tflen() as usize | (tx_fctrl.tfle() as usize) << 7;
+        let bitrate = tx_fctrl.txbr().unwrap_or(BitRate::Kbps6800);
+
+        // The raw time stamp is the RMARKER, without the antenna delay. Can't
+        // panic, as the value is a 40-bit register field.
+        let rmarker = Instant::new(tx_time.tx_rawst()).unwrap();
+
+        Ok(rmarker + phr_and_data_duration(len, bitrate))
+    }
+
+    /// Returns how much of the acknowledgement timeout is left
+    ///
+    /// The result is in units of RX_FWTO. Zero means the timeout has expired.
+    fn remaining_ack_timeout(&mut self) -> Result<u16, Error<SPI, CS>> {
+        let tx_done = match self.state.tx_done {
+            Some(tx_done) => tx_done,
+            None          => return Ok(self.state.ack_timeout),
+        };
+
+        // One unit of RX_FWTO (512 / 499.2 MHz) is 2^16 units of system time.
+        let elapsed = self.sys_time()?.duration_since(tx_done).value() >> 16;
+
+        Ok((self.state.ack_timeout as u64).saturating_sub(elapsed) as u16)
+    }
+
+    /// Finishes sending and returns to the `Ready` state
+    ///
+    /// If the send operation has finished, as indicated by `wait`, this is a
+    /// no-op. If the send operation is still ongoing, it will be aborted. This
+    /// includes waiting for an acknowledgement that hasn't arrived yet.
+    #[allow(clippy::type_complexity)]
+    pub fn finish_sending(mut self)
+        -> Result<DW1000<SPI, CS, Ready>, (Self, Error<SPI, CS>)>
+    {
+        if self.state.ack_seq.is_some() {
+            match self.force_idle() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+            match self.reset_rx_flags() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+        }
+        if !self.state.finished {
+            // Can't use `map_err` and `?` here, as the compiler will complain
+            // about `self` moving into the closure.
+            match self.force_idle() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+            match self.reset_tx_flags() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+        }
+
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Ready,
+        })
+    }
+}
+
+impl<SPI, CS> DW1000<SPI, CS, Receiving>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Wait for receive operation to finish
+    ///
+    /// This method returns an `nb::Result` to indicate whether the transmission
+    /// has finished, or whether it is still ongoing. You can use this to busily
+    /// wait for the transmission to finish, for example using `nb`'s `block!`
+    /// macro, or you can use it in tandem with [`DW1000::enable_rx_interrupts`]
+    /// and the DW1000 IRQ output to wait in a more energy-efficient manner.
+    ///
+    /// Handling the DW1000's IRQ output line is out of the scope of this
+    /// driver, but please note that if you're using the DWM1001 module or
+    /// DWM1001-Dev board, that the `dwm1001` crate has explicit support for
+    /// this.
+    ///
+    /// If [`RxConfig::double_buffered`] or [`RxConfig::continuous`] is set,
+    /// the receiver keeps running after a frame has been received, and this
+    /// method can be called again to receive the next frame. In continuous
+    /// mode, errors that only affect a single frame ([`Error::Fcs`],
+    /// [`Error::Phy`], [`Error::ReedSolomon`], [`Error::SfdTimeout`]) are
+    /// reported, but the next call waits for the next frame.
+    ///
+    /// [`RxConfig::double_buffered`]: ../configs/struct.RxConfig.html#structfield.double_buffered
+    /// [`RxConfig::continuous`]: ../configs/struct.RxConfig.html#structfield.continuous
+    /// [`Error::Fcs`]: enum.Error.html#variant.Fcs
+    /// [`Error::Phy`]: enum.Error.html#variant.Phy
+    /// [`Error::ReedSolomon`]: enum.Error.html#variant.ReedSolomon
+    /// [`Error::SfdTimeout`]: enum.Error.html#variant.SfdTimeout
+    pub fn wait<'b>(&mut self, buffer: &'b mut [u8])
+        -> nb::Result<Message<'b>, Error<SPI, CS>>
+    {
+        self.wait_with(buffer, Self::wait_rx)
+    }
+
+    /// Wait for receive operation to finish, without decoding the frame
+    ///
+    /// Works like [`wait`], except that the received data is returned as-is,
+    /// instead of being decoded as an IEEE 802.15.4 MAC frame. This is useful
+    /// for interoperating with devices that don't use IEEE 802.15.4 MAC
+    /// framing, for example in combination with [`DW1000::send_raw`]. Frame
+    /// filtering only works with MAC frames, so make sure
+    /// [`RxConfig::frame_filtering`] is disabled.
+    ///
+    /// [`wait`]: #method.wait
+    /// [`DW1000::send_raw`]: #method.send_raw
+    /// [`RxConfig::frame_filtering`]: ../configs/struct.RxConfig.html#structfield.frame_filtering
+    pub fn wait_raw<'b>(&mut self, buffer: &'b mut [u8])
+        -> nb::Result<RawMessage<'b>, Error<SPI, CS>>
+    {
+        self.wait_with(buffer, Self::wait_rx_raw)
+    }
+
+    /// Waits for a frame using `read`, then keeps the receiver going
+    #[allow(clippy::type_complexity)]
+    fn wait_with<'b, T>(&mut self,
+        buffer: &'b mut [u8],
+        read:   fn(&mut Self, &'b mut [u8]) -> nb::Result<T, Error<SPI, CS>>,
+    )
+        -> nb::Result<T, Error<SPI, CS>>
+    {
+        // If this is a delayed receive operation, the Half Period Delay
+        // Warning indicates that the receiver was supposed to be enabled in
+        // the past. The DW1000 would still enable it, once the system time
+        // wraps around, so we need to cancel the operation.
+        let sys_status = self.ll
+            .sys_status()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+        if sys_status.hpdwarn() == 0b1 {
+            self.force_idle()
+                .map_err(nb::Error::Other)?;
+            return Err(nb::Error::Other(Error::DelayedReceiveTooLate));
+        }
+
+        let result = read(self, buffer);
+
+        let frame_read = match &result {
+            Ok(_) => true,
+            Err(nb::Error::Other(Error::BufferTooSmall { .. })) => true,
+            Err(nb::Error::Other(Error::Frame(_))) => true,
+            Err(nb::Error::Other(Error::Fcs))
+            | Err(nb::Error::Other(Error::Phy))
+            | Err(nb::Error::Other(Error::ReedSolomon))
+            | Err(nb::Error::Other(Error::SfdTimeout))
+            | Err(nb::Error::Other(Error::FrameFilteringRejection)) => {
+                // The receiver has already re-enabled itself (RXAUTR, or
+                // automatically after a frame filtering rejection). Clear the
+                // error, so the next call waits for the next frame.
+                if self.state.continuous {
+                    self.reset_rx_flags().map_err(nb::Error::Other)?;
+                }
+                false
+            }
+            Err(_) => false,
+        };
+
+        if frame_read {
+            if self.state.double_buffered {
+                // The frame has been copied out of the host side buffer. Hand
+                // that buffer back to the receiver and make the next one
+                // visible.
+                self.ll
+                    .sys_ctrl()
+                    .modify(|_, w| w.hrbpt(0b1))
+                    .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+            }
+            else if self.state.continuous {
+                // The receiver only re-enables itself after errors, not after
+                // a good frame.
+                self.ll
+                    .sys_ctrl()
+                    .modify(|_, w| w.rxenab(0b1))
+                    .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+            }
+        }
+
+        result
+    }
+
+    /// Reads part of the channel impulse response of the last received frame
+    ///
+    /// Fills `samples` with complex samples (real and imaginary part) of the
+    /// channel impulse response, as recorded in the accumulator memory,
+    /// starting at sample `first_sample`. The channel impulse response consists
+    /// of 992 samples at 16 MHz PRF and 1016 samples at 64 MHz PRF. The first
+    /// path is located at [`RxQuality::fp_index`].
+    ///
+    /// Call this method after [`wait`] has returned a frame, before starting
+    /// the next receive operation.
+    ///
+    /// Returns [`Error::CirOutOfRange`], if the requested samples don't fit
+    /// into the accumulator memory.
+    ///
+    /// [`RxQuality::fp_index`]: struct.RxQuality.html#structfield.fp_index
+    /// [`wait`]: #method.wait
+    /// [`Error::CirOutOfRange`]: enum.Error.html#variant.CirOutOfRange
+    pub fn read_cir(&mut self, first_sample: u16, samples: &mut [(i16, i16)])
+        -> Result<(), Error<SPI, CS>>
+    {
+        let end = first_sample as usize + samples.len();
+        if end > <ll::ACC_MEM as ll::Register>::LEN / CIR_SAMPLE_LEN {
+            return Err(Error::CirOutOfRange);
+        }
+
+        // The accumulator memory can only be read with its clocks forced on.
+        // See user manual, sections 7.2.50.1 and 7.2.26.
+        let pmsc_ctrl0 = self.ll.pmsc_ctrl0().read()?;
+        self.ll.pmsc_ctrl0().modify(|_, w|
+            w
+                .rxclks(ll::SysClks::Pll)
+                .face(0b1)
+                .amce(0b1)
+        )?;
+
+        let result = self.read_acc_mem(first_sample, samples);
+
+        // Restore the clock configuration, regardless of whether reading
+        // succeeded.
+        self.ll.pmsc_ctrl0().modify(|_, w|
+            w
+                .rxclks_raw(pmsc_ctrl0.rxclks_raw())
+                .face(pmsc_ctrl0.face())
+                .amce(pmsc_ctrl0.amce())
+        )?;
+
+        result
+    }
+
+    fn read_acc_mem(&mut self, first_sample: u16, samples: &mut [(i16, i16)])
+        -> Result<(), Error<SPI, CS>>
+    {
+        // The accumulator memory is way too large to read into a buffer on the
+        // stack, so we read it in chunks.
+        let mut buffer = [0; 32 * CIR_SAMPLE_LEN];
+        let mut offset = first_sample as usize * CIR_SAMPLE_LEN;
+
+        for chunk in samples.chunks_mut(buffer.len() / CIR_SAMPLE_LEN) {
+            let bytes = &mut buffer[..chunk.len() * CIR_SAMPLE_LEN];
+            self.ll.acc_mem().read_at(offset as u16, bytes)?;
+
+            for (sample, bytes) in
+                chunk.iter_mut().zip(bytes.chunks(CIR_SAMPLE_LEN))
+            {
+                *sample = (
+                    i16::from_le_bytes([bytes[0], bytes[1]]),
+                    i16::from_le_bytes([bytes[2], bytes[3]]),
+                );
+            }
+
+            offset += bytes.len();
+        }
+
+        Ok(())
+    }
+
+    /// Reads the carrier integrator value of the last received frame
+    ///
+    /// The carrier recovery integrator reflects the frequency offset between
+    /// the remote transmitter's clock and the local clock. It can be used to
+    /// correct for that offset, for example when computing the distance using
+    /// [`ranging::compute_distance_mm_single_sided`].
+    ///
+    /// Call this method after [`wait`] has returned a frame, before starting
+    /// the next receive operation.
+    ///
+    /// [`ranging::compute_distance_mm_single_sided`]: ../ranging/fn.compute_distance_mm_single_sided.html
+    /// [`wait`]: #method.wait
+    pub fn read_carrier_integrator(&mut self) -> Result<i32, Error<SPI, CS>> {
+        self.read_drx_car_int()
+    }
+
+    /// Measures the clock offset to the sender of the last received frame
+    ///
+    /// `rx_config` is the configuration the frame was received with. See
+    /// [`ClockOffset`] for details.
+    ///
+    /// Call this method after [`wait`] has returned a frame, before starting
+    /// the next receive operation.
+    ///
+    /// [`ClockOffset`]: struct.ClockOffset.html
+    /// [`wait`]: #method.wait
+    pub fn read_clock_offset(&mut self, rx_config: RxConfig)
+        -> Result<ClockOffset, Error<SPI, CS>>
+    {
+        self.measure_clock_offset(rx_config)
+    }
+
+    /// Finishes receiving and returns to the `Ready` state
+    ///
+    /// If the receive operation has finished, as indicated by `wait`, this is a
+    /// no-op. If the receive operation is still ongoing, it will be aborted.
+    #[allow(clippy::type_complexity)]
+    pub fn finish_receiving(mut self)
+        -> Result<DW1000<SPI, CS, Ready>, (Self, Error<SPI, CS>)>
+    {
+        if !self.state.finished {
+            // Can't use `map_err` and `?` here, as the compiler will complain
+            // about `self` moving into the closure.
+            match self.force_idle() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+        }
+
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Ready,
+        })
+    }
+}
+
+impl<SPI, CS> DW1000<SPI, CS, Exchanging>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Wait for the transmission to finish
+    ///
+    /// Works like [`DW1000::wait`] in the `Sending` state. Once this method
+    /// has returned `Ok`, the receiver is (or will soon be) enabled.
+    ///
+    /// [`DW1000::wait`]: #method.wait
+    pub fn wait_sent(&mut self)
+        -> nb::Result<(), Error<SPI, CS>>
+    {
+        if !self.state.sent {
+            self.wait_tx()?;
+            self.state.sent = true;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the response to arrive
+    ///
+    /// Works like [`DW1000::wait`] in the `Receiving` state. Waits for the
+    /// transmission to finish first, so there's no need to call
+    /// [`wait_sent`] before this, unless you're interested in when exactly
+    /// the transmission finished.
+    ///
+    /// [`DW1000::wait`]: #method.wait-1
+    /// [`wait_sent`]: #method.wait_sent
+    pub fn wait_response<'b>(&mut self, buffer: &'b mut [u8])
+        -> nb::Result<Message<'b>, Error<SPI, CS>>
+    {
+        self.wait_sent()?;
+
+        let message = self.wait_rx(buffer)?;
+        self.state.finished = true;
+
+        Ok(message)
+    }
+
+    /// Reads the carrier integrator value of the response
+    ///
+    /// See [`DW1000::read_carrier_integrator`] in the `Receiving` state. Call
+    /// this method after [`wait_response`] has returned a frame.
+    ///
+    /// [`DW1000::read_carrier_integrator`]: #method.read_carrier_integrator
+    /// [`wait_response`]: #method.wait_response
+    pub fn read_carrier_integrator(&mut self) -> Result<i32, Error<SPI, CS>> {
+        self.read_drx_car_int()
+    }
+
+    /// Measures the clock offset to the sender of the response
+    ///
+    /// See [`DW1000::read_clock_offset`] in the `Receiving` state. Call this
+    /// method after [`wait_response`] has returned a frame.
+    ///
+    /// [`DW1000::read_clock_offset`]: #method.read_clock_offset
+    /// [`wait_response`]: #method.wait_response
+    pub fn read_clock_offset(&mut self, rx_config: RxConfig)
+        -> Result<ClockOffset, Error<SPI, CS>>
+    {
+        self.measure_clock_offset(rx_config)
+    }
+
+    /// Finishes the exchange and returns to the `Ready` state
+    ///
+    /// If the response has been received, as indicated by `wait_response`,
+    /// this is a no-op. Otherwise, the ongoing transmission or receive
+    /// operation is aborted.
+    #[allow(clippy::type_complexity)]
+    pub fn finish_exchange(mut self)
+        -> Result<DW1000<SPI, CS, Ready>, (Self, Error<SPI, CS>)>
+    {
+        if !self.state.finished {
+            // Can't use `map_err` and `?` here, as the compiler will complain
+            // about `self` moving into the closure.
+            match self.force_idle() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+            match self.reset_tx_flags() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+            match self.reset_rx_flags() {
+                Ok(())     => (),
+                Err(error) => return Err((self, error)),
+            }
+        }
+
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Ready,
+        })
+    }
+}
+
+impl<SPI, CS> DW1000<SPI, CS, Sleeping>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Wake the DW1000 up by holding chip select low
+    ///
+    /// Requires [`SleepConfig::wake_on_spi`] to have been set. Once the DW1000
+    /// is awake, the LDE microcode is loaded and the configuration that
+    /// doesn't survive sleep is restored.
+    ///
+    /// Returns [`Error::WakeUpFailed`], if the DW1000 doesn't respond after
+    /// waking up.
+    ///
+    /// [`SleepConfig::wake_on_spi`]: ../configs/struct.SleepConfig.html#structfield.wake_on_spi
+    /// [`Error::WakeUpFailed`]: enum.Error.html#variant.WakeUpFailed
+    pub fn wake_up<D>(mut self, delay: &mut D)
+        -> Result<DW1000<SPI, CS, Ready>, Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        self.ll.wake_up(delay)?;
+        self.finish_wake_up(delay)
+    }
+
+    /// Wake the DW1000 up using the WAKEUP pin
+    ///
+    /// Requires [`SleepConfig::wake_on_pin`] to have been set. Drives `pin`,
+    /// which must be connected to the DW1000's WAKEUP pin, high for 500 µs.
+    /// Otherwise works like [`wake_up`].
+    ///
+    /// Returns [`Error::WakeUpPin`], if `pin` can't be set.
+    ///
+    /// [`SleepConfig::wake_on_pin`]: ../configs/struct.SleepConfig.html#structfield.wake_on_pin
+    /// [`wake_up`]: #method.wake_up
+    /// [`Error::WakeUpPin`]: enum.Error.html#variant.WakeUpPin
+    pub fn wake_up_with_pin<W, D>(self, pin: &mut W, delay: &mut D)
+        -> Result<DW1000<SPI, CS, Ready>, Error<SPI, CS>>
+        where
+            W: OutputPin,
+            D: DelayUs<u16>,
+    {
+        pin.set_high().map_err(|_| Error::WakeUpPin)?;
+        delay.delay_us(500);
+        pin.set_low().map_err(|_| Error::WakeUpPin)?;
+
+        self.finish_wake_up(delay)
+    }
+
+    fn finish_wake_up<D>(mut self, delay: &mut D)
+        -> Result<DW1000<SPI, CS, Ready>, Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        // The crystal oscillator and PLL need to start up, before the DW1000
+        // reaches the IDLE state. See DW1000 data sheet, section 5.3.1.
+        delay.delay_us(WAKE_UP_TIME_US);
+
+        if self.ll.dev_id().read()?.ridtag() != 0xDECA {
+            return Err(Error::WakeUpFailed);
+        }
+
+        if self.state.preserve_config {
+            let rx_antenna_delay = self.state.rx_antenna_delay;
+
+            self.load_lde_microcode()?;
+            self.ll
+                .lde_rxantd()
+                .write(|w| w.value(rx_antenna_delay))?;
+        }
+        else {
+            self.configure_defaults()?;
+        }
+
+        Ok(DW1000 {
+            ll:    self.ll,
+            seq:   self.seq,
+            state: Ready,
+        })
+    }
+}
+
+impl<SPI, CS, State> DW1000<SPI, CS, State>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Returns the TX antenna delay
+    pub fn get_tx_antenna_delay(&mut self)
+        -> Result<Duration, Error<SPI, CS>>
+    {
+        let tx_antenna_delay = self.ll.tx_antd().read()?.value();
+
+        // Since `tx_antenna_delay` is `u16`, the following will never panic.
+        let tx_antenna_delay = Duration::new(tx_antenna_delay.into()).unwrap();
+
+        Ok(tx_antenna_delay)
+    }
+
+    /// Returns the network id and address used for sending and receiving
+    pub fn get_address(&mut self)
+        -> Result<mac::Address, Error<SPI, CS>>
     {
-        self.ll()
-            .sys_mask()
-            .modify(|_, w|
-                w
-                    .mrxdfr(0b1)
-                    .mrxfce(0b1)
-                    .mrxphe(0b1)
-                    .mrxrfsl(0b1)
-                    .mrxrfto(0b1)
-                    .mrxovrr(0b1)
-                    .mrxpto(0b1)
-                    .mrxsfdto(0b1)
-                    .maffrej(0b1)
-                    .mldedone(0b1)
-            )?;
+        let panadr = self.ll.panadr().read()?;
 
-        Ok(())
+        Ok(mac::Address::Short(
+            mac::PanId(panadr.pan_id()),
+            mac::ShortAddress(panadr.short_addr()),
+        ))
     }
 
-    /// Disables all interrupts
-    pub fn disable_interrupts(&mut self)
-        -> Result<(), Error<SPI, CS>>
+    /// Returns the network id and extended address
+    pub fn get_extended_address(&mut self)
+        -> Result<mac::Address, Error<SPI, CS>>
     {
-        self.ll.sys_mask().write(|w| w)?;
-        Ok(())
-    }
+        let pan_id = self.ll.panadr().read()?.pan_id();
+        let eui    = self.ll.eui().read()?.value();
 
-    /// Configures the gpio pins to operate as LED output.
-    ///
-    /// - Note: This means that the function of the gpio pins change
-    /// - Note: Both the kilohertz and debounce clock will be turned on or off
-    /// ---
-    /// - RXOKLED will change GPIO0
-    /// - SFDLED will change GPIO1
-    /// - RXLED will change GPIO2
-    /// - TXLED will change GPIO3
-    ///
-    /// blink_time is in units of 14 ms
-    pub fn configure_leds(
-        &mut self,
-        enable_rx_ok: bool,
-        enable_sfd: bool,
-        enable_rx: bool,
-        enable_tx: bool,
-        blink_time: u8)
-        -> Result<(), Error<SPI, CS>> {
-        // Turn on the timer that will control the blinking (The debounce clock)
-        self.ll.pmsc_ctrl0().modify(|_, w| {
-            w
-                .gpdce((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
-                .khzclken((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
-        })?;
+        Ok(mac::Address::Extended(
+            mac::PanId(pan_id),
+            mac::ExtendedAddress(eui),
+        ))
+    }
 
-        // Turn on the led blinking
-        self.ll.pmsc_ledc().modify(|_, w| {
-           w
-               .blnken((enable_rx_ok || enable_sfd || enable_rx || enable_tx) as u8)
-               .blink_tim(blink_time)
-        })?;
+    /// Returns the crystal trim value
+    pub fn get_xtal_trim(&mut self) -> Result<u8, Error<SPI, CS>> {
+        Ok(self.ll.fs_xtalt().read()?.xtalt())
+    }
 
-        // Set the proper gpio mode
-        self.ll.gpio_mode().modify(|_, w| {
-            w
-                .msgp0(enable_rx_ok as u8)
-                .msgp1(enable_sfd as u8)
-                .msgp2(enable_rx as u8)
-                .msgp3(enable_tx as u8)
-        })?;
+    /// Returns the current system time
+    pub fn sys_time(&mut self) -> Result<Instant, Error<SPI, CS>> {
+        let sys_time = self.ll.sys_time().read()?.value();
 
-        Ok(())
+        // Since hardware timestamps fit within 40 bits, the following should
+        // never panic.
+        Ok(Instant::new(sys_time).unwrap())
     }
-}
 
-impl<SPI, CS> DW1000<SPI, CS, Sending>
-    where
-        SPI: spi::Transfer<u8> + spi::Write<u8>,
-        CS:  OutputPin,
-{
-    /// Wait for the transmission to finish
+    /// Provides direct access to the register-level API
     ///
-    /// This method returns an `nb::Result` to indicate whether the transmission
-    /// has finished, or whether it is still ongoing. You can use this to busily
-    /// wait for the transmission to finish, for example using `nb`'s `block!`
-    /// macro, or you can use it in tandem with [`DW1000::enable_tx_interrupts`]
-    /// and the DW1000 IRQ output to wait in a more energy-efficient manner.
+    /// Be aware that by using the register-level API, you can invalidate
+    /// various assumptions that the high-level API makes about the operation of
+    /// the DW1000. Don't use the register-level and high-level APIs in tandem,
+    /// unless you know what you're doing.
+    pub fn ll(&mut self) -> &mut ll::DW1000<SPI, CS> {
+        &mut self.ll
+    }
+
+    /// Checks whether the current transmission has finished
     ///
-    /// Handling the DW1000's IRQ output line is out of the scope of this
-    /// driver, but please note that if you're using the DWM1001 module or
-    /// DWM1001-Dev board, that the `dwm1001` crate has explicit support for
-    /// this.
-    pub fn wait(&mut self)
+    /// Resets the TX status flags, once it has.
+    fn wait_tx(&mut self)
         -> nb::Result<(), Error<SPI, CS>>
     {
         // Check Half Period Warning Counter. If this is a delayed transmission,
@@ -600,74 +1925,32 @@ impl<SPI, CS> DW1000<SPI, CS, Sending>
         }
 
         // Frame sent
-        self.reset_flags()
-            .map_err(|error| nb::Error::Other(error))?;
-        self.state.finished = true;
+        self.reset_tx_flags()
+            .map_err(nb::Error::Other)?;
 
         Ok(())
     }
 
-    /// Finishes sending and returns to the `Ready` state
+    /// Checks whether a frame has been received
     ///
-    /// If the send operation has finished, as indicated by `wait`, this is a
-    /// no-op. If the send operation is still ongoing, it will be aborted.
-    pub fn finish_sending(mut self)
-        -> Result<DW1000<SPI, CS, Ready>, (Self, Error<SPI, CS>)>
+    /// Returns the frame, once it has.
+    fn wait_rx<'b>(&mut self, buffer: &'b mut [u8])
+        -> nb::Result<Message<'b>, Error<SPI, CS>>
     {
-        if !self.state.finished {
-            // Can't use `map_err` and `?` here, as the compiler will complain
-            // about `self` moving into the closure.
-            match self.force_idle() {
-                Ok(())     => (),
-                Err(error) => return Err((self, error)),
-            }
-            match self.reset_flags() {
-                Ok(())     => (),
-                Err(error) => return Err((self, error)),
-            }
-        }
-
-        Ok(DW1000 {
-            ll:    self.ll,
-            seq:   self.seq,
-            state: Ready,
-        })
-    }
+        let message = self.wait_rx_raw(buffer)?;
 
-    fn reset_flags(&mut self) -> Result<(), Error<SPI, CS>> {
-        self.ll
-            .sys_status()
-            .write(|w|
-                w
-                    .txfrb(0b1) // Transmit Frame Begins
-                    .txprs(0b1) // Transmit Preamble Sent
-                    .txphs(0b1) // Transmit PHY Header Sent
-                    .txfrs(0b1) // Transmit Frame Sent
-            )?;
+        let frame = mac::Frame::decode(message.data, true)
+            .map_err(|error| nb::Error::Other(Error::Frame(error)))?;
 
-        Ok(())
+        Ok(Message {
+            rx_time:    message.rx_time,
+            frame,
+            rx_quality: message.rx_quality,
+        })
     }
-}
 
-impl<SPI, CS> DW1000<SPI, CS, Receiving>
-    where
-        SPI: spi::Transfer<u8> + spi::Write<u8>,
-        CS:  OutputPin,
-{
-    /// Wait for receive operation to finish
-    ///
-    /// This method returns an `nb::Result` to indicate whether the transmission
-    /// has finished, or whether it is still ongoing. You can use this to busily
-    /// wait for the transmission to finish, for example using `nb`'s `block!`
-    /// macro, or you can use it in tandem with [`DW1000::enable_rx_interrupts`]
-    /// and the DW1000 IRQ output to wait in a more energy-efficient manner.
-    ///
-    /// Handling the DW1000's IRQ output line is out of the scope of this
-    /// driver, but please note that if you're using the DWM1001 module or
-    /// DWM1001-Dev board, that the `dwm1001` crate has explicit support for
-    /// this.
-    pub fn wait<'b>(&mut self, buffer: &'b mut [u8])
-        -> nb::Result<Message<'b>, Error<SPI, CS>>
+    fn wait_rx_raw<'b>(&mut self, buffer: &'b mut [u8])
+        -> nb::Result<RawMessage<'b>, Error<SPI, CS>>
     {
         // ATTENTION:
         // If you're changing anything about which SYS_STATUS flags are being
@@ -725,13 +2008,16 @@ impl<SPI, CS> DW1000<SPI, CS, Receiving>
         let rx_time = self.ll()
             .rx_time()
             .read()
-            .map_err(|error| nb::Error::Other(Error::Spi(error)))?
-            .rx_stamp();
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+        let rx_fqual = self.ll()
+            .rx_fqual()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
 
         // `rx_time` comes directly from the register, which should always
         // contain a 40-bit timestamp. Unless the hardware or its documentation
         // are buggy, the following should never panic.
-        let rx_time = Instant::new(rx_time).unwrap();
+        let rx_stamp = Instant::new(rx_time.rx_stamp()).unwrap();
 
         // Reset status bits. This is not strictly necessary, but it helps, if
         // you have to inspect SYS_STATUS manually during debugging.
@@ -749,114 +2035,211 @@ impl<SPI, CS> DW1000<SPI, CS, Receiving>
                     .rxfce(0b1)   // Receiver FCS Error
                     .rxrfsl(0b1)  // Receiver Reed Solomon Frame Sync Loss
                     .rxrfto(0b1)  // Receiver Frame Wait Timeout
-                    .ldeerr(0b1)  // Leading Edge Detection Processing Error
+                    .ldeerr(0b1)  // Leading Edge Detection Processing Error
+                    .rxovrr(0b1)  // Receiver Overrun
+                    .rxpto(0b1)   // Preamble Detection Timeout
+                    .rxsfdto(0b1) // Receiver SFD Timeout
+                    .rxrscs(0b1)  // Receiver Reed-Solomon Correction Status
+                    .rxprej(0b1)  // Receiver Preamble Rejection
+                    .affrej(0b1)  // Automatic Frame Filtering Rejection
+            )
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+        // Read received frame
+        let rx_finfo = self.ll()
+            .rx_finfo()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+        let len = rx_finfo.rxflen() as usize
+            | (rx_finfo.rxfle() as usize) << 7;
+
+        if buffer.len() < len {
+            return Err(nb::Error::Other(
+                Error::BufferTooSmall { required_len: len }
+            ))
+        }
+
+        self.ll()
+            .rx_buffer()
+            .read_at(0, &mut buffer[..len])
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+
+        let pulse_repetition_frequency = rx_finfo.rxprfr()
+            .unwrap_or(PulseRepetitionFrequency::Mhz16);
+        let bitrate = rx_finfo.rxbr()
+            .unwrap_or(BitRate::Kbps6800);
+
+        let rxpacc_nosat = self.ll()
+            .rxpacc_nosat()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?
+            .value();
+        let chan_ctrl = self.ll()
+            .chan_ctrl()
+            .read()
+            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+        let sfd_sequence = match (chan_ctrl.dwsfd(), chan_ctrl.rnssfd()) {
+            (0b0, 0b0) => SfdSequence::IEEE,
+            (0b1, 0b0) => SfdSequence::Decawave,
+            (0b1, 0b1) => SfdSequence::DecawaveAlt,
+            _          => SfdSequence::User,
+        };
+
+        Ok(RawMessage {
+            rx_time: rx_stamp,
+            data:    &buffer[..len],
+            rx_quality: RxQuality {
+                std_noise: rx_fqual.std_noise(),
+                fp_index:  rx_time.fp_index(),
+                fp_ampl1:  rx_time.fp_ampl1(),
+                fp_ampl2:  rx_fqual.fp_ampl2(),
+                fp_ampl3:  rx_fqual.fp_ampl3(),
+                cir_pwr:   rx_fqual.cir_pwr(),
+                rxpacc:    rx_finfo.rxpacc(),
+                rxpacc_nosat,
+                pulse_repetition_frequency,
+                bitrate,
+                sfd_sequence,
+            },
+        })
+    }
+
+    fn read_drx_car_int(&mut self) -> Result<i32, Error<SPI, CS>> {
+        let value = self.ll.drx_car_int().read()?.value();
+
+        // The value is a 21-bit two's complement number. Shift it to the top of
+        // the `i32`, then back, to extend the sign.
+        Ok(((value << 11) as i32) >> 11)
+    }
+
+    fn measure_clock_offset(&mut self, rx_config: RxConfig)
+        -> Result<ClockOffset, Error<SPI, CS>>
+    {
+        let carrier_integrator = self.read_drx_car_int()?;
+        let xtal_trim          = self.get_xtal_trim()?;
+
+        Ok(ClockOffset::new(carrier_integrator, rx_config, xtal_trim))
+    }
+
+    fn write_xtal_trim(&mut self, trim: u8) -> Result<(), Error<SPI, CS>> {
+        self.ll.fs_xtalt().write(|w|
+            w
+                .xtalt(trim)
+                .xtalt_res(0b011)
+        )?;
+
+        Ok(())
+    }
+
+    fn reset_tx_flags(&mut self) -> Result<(), Error<SPI, CS>> {
+        self.ll
+            .sys_status()
+            .write(|w|
+                w
+                    .txfrb(0b1) // Transmit Frame Begins
+                    .txprs(0b1) // Transmit Preamble Sent
+                    .txphs(0b1) // Transmit PHY Header Sent
+                    .txfrs(0b1) // Transmit Frame Sent
+            )?;
+
+        Ok(())
+    }
+
+    fn reset_rx_flags(&mut self) -> Result<(), Error<SPI, CS>> {
+        self.ll
+            .sys_status()
+            .write(|w|
+                w
+                    .rxprd(0b1)   // Receiver Preamble Detected
+                    .rxsfdd(0b1)  // Receiver SFD Detected
+                    .ldedone(0b1) // LDE Processing Done
+                    .rxphd(0b1)   // Receiver PHY Header Detected
+                    .rxphe(0b1)   // Receiver PHY Header Error
+                    .rxdfr(0b1)   // Receiver Data Frame Ready
+                    .rxfcg(0b1)   // Receiver FCS Good
+                    .rxfce(0b1)   // Receiver FCS Error
+                    .rxrfsl(0b1)  // Receiver Reed Solomon Frame Sync Loss
+                    .rxrfto(0b1)  // Receiver Frame Wait Timeout
                     .rxovrr(0b1)  // Receiver Overrun
                     .rxpto(0b1)   // Preamble Detection Timeout
                     .rxsfdto(0b1) // Receiver SFD Timeout
-                    .rxrscs(0b1)  // Receiver Reed-Solomon Correction Status
-                    .rxprej(0b1)  // Receiver Preamble Rejection
-            )
-            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
-
-        // Read received frame
-        let rx_finfo = self.ll()
-            .rx_finfo()
-            .read()
-            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
-        let rx_buffer = self.ll()
-            .rx_buffer()
-            .read()
-            .map_err(|error| nb::Error::Other(Error::Spi(error)))?;
+                    .affrej(0b1)  // Automatic Frame Filtering Rejection
+                    .aat(0b1)     // Automatic Acknowledge Trigger
+                    .hpdwarn(0b1) // Half Period Delay Warning
+            )?;
 
-        let len = rx_finfo.rxflen() as usize;
+        Ok(())
+    }
 
-        if buffer.len() < len {
-            return Err(nb::Error::Other(
-                Error::BufferTooSmall { required_len: len }
-            ))
-        }
+    /// Applies the configuration recommended by the user manual
+    ///
+    /// See [`DW1000::init`] for details.
+    ///
+    /// [`DW1000::init`]: #method.init
+    fn configure_defaults(&mut self) -> Result<(), Error<SPI, CS>> {
+        // Set AGC_TUNE1. See user manual, section 2.5.5.1.
+        self.ll.agc_tune1().write(|w| w.value(0x8870))?;
 
-        buffer[..len].copy_from_slice(&rx_buffer.data()[..len]);
+        // Set AGC_TUNE2. See user manual, section 2.5.5.2.
+        self.ll.agc_tune2().write(|w| w.value(0x2502A907))?;
 
-        let frame = mac::Frame::decode(&buffer[..len], true)
-            .map_err(|error| nb::Error::Other(Error::Frame(error)))?;
+        // Set DRX_TUNE2. See user manual, section 2.5.5.3.
+        self.ll.drx_tune2().write(|w| w.value(0x311A002D))?;
 
-        Ok(Message {
-            rx_time,
-            frame,
-        })
-    }
+        // Set NTM. See user manual, section 2.5.5.4. This improves performance
+        // in line-of-sight conditions, but might not be the best choice if non-
+        // line-of-sight performance is important.
+        self.ll.lde_cfg1().modify(|_, w| w.ntm(0xD))?;
 
-    /// Finishes receiving and returns to the `Ready` state
-    ///
-    /// If the receive operation has finished, as indicated by `wait`, this is a
-    /// no-op. If the receive operation is still ongoing, it will be aborted.
-    pub fn finish_receiving(mut self)
-        -> Result<DW1000<SPI, CS, Ready>, (Self, Error<SPI, CS>)>
-    {
-        if !self.state.finished {
-            // Can't use `map_err` and `?` here, as the compiler will complain
-            // about `self` moving into the closure.
-            match self.force_idle() {
-                Ok(())     => (),
-                Err(error) => return Err((self, error)),
-            }
-        }
+        // Set LDE_CFG2. See user manual, section 2.5.5.5.
+        self.ll.lde_cfg2().write(|w| w.value(0x1607))?;
 
-        Ok(DW1000 {
-            ll:    self.ll,
-            seq:   self.seq,
-            state: Ready,
-        })
-    }
-}
+        // Set TX_POWER. See user manual, section 2.5.5.6. This is overwritten
+        // by every send operation, according to its configuration.
+        let tx_power = UwbChannel::default()
+            .get_recommended_tx_power(Default::default(), true);
+        self.ll.tx_power().write(|w| w.value(tx_power.to_register_value()))?;
 
-impl<SPI, CS, State> DW1000<SPI, CS, State>
-    where
-        SPI: spi::Transfer<u8> + spi::Write<u8>,
-        CS:  OutputPin,
-{
-    /// Returns the TX antenna delay
-    pub fn get_tx_antenna_delay(&mut self)
-        -> Result<Duration, Error<SPI, CS>>
-    {
-        let tx_antenna_delay = self.ll.tx_antd().read()?.value();
+        // Set RF_TXCTRL. See user manual, section 2.5.5.7.
+        self.ll.rf_txctrl().modify(|_, w|
+            w
+                .txmtune(0b1111)
+                .txmq(0b111)
+        )?;
 
-        // Since `tx_antenna_delay` is `u16`, the following will never panic.
-        let tx_antenna_delay = Duration::new(tx_antenna_delay.into()).unwrap();
+        // Set TC_PGDELAY. See user manual, section 2.5.5.8.
+        self.ll.tc_pgdelay().write(|w| w.value(0xC0))?;
 
-        Ok(tx_antenna_delay)
-    }
+        // Set FS_PLLTUNE. See user manual, section 2.5.5.9.
+        self.ll.fs_plltune().write(|w| w.value(0xBE))?;
 
-    /// Returns the network id and address used for sending and receiving
-    pub fn get_address(&mut self)
-        -> Result<mac::Address, Error<SPI, CS>>
-    {
-        let panadr = self.ll.panadr().read()?;
+        // Set LDELOAD. See user manual, section 2.5.5.10.
+        self.load_lde_microcode()?;
 
-        Ok(mac::Address::Short(
-            mac::PanId(panadr.pan_id()),
-            mac::ShortAddress(panadr.short_addr()),
-        ))
-    }
+        // Set LDOTUNE. See user manual, section 2.5.5.11.
+        Otp::new(&mut self.ll).load_ldotune()?;
 
-    /// Returns the current system time
-    pub fn sys_time(&mut self) -> Result<Instant, Error<SPI, CS>> {
-        let sys_time = self.ll.sys_time().read()?.value();
+        // Set FS_XTALT from the factory calibration. See user manual, section
+        // 8.1.
+        let xtal_trim = Otp::new(&mut self.ll).xtal_trim()?
+            .unwrap_or(XTAL_TRIM_DEFAULT);
+        self.write_xtal_trim(xtal_trim)?;
 
-        // Since hardware timestamps fit within 40 bits, the following should
-        // never panic.
-        Ok(Instant::new(sys_time).unwrap())
+        Ok(())
     }
 
-    /// Provides direct access to the register-level API
+    /// Loads the LDE microcode from ROM into RAM
     ///
-    /// Be aware that by using the register-level API, you can invalidate
-    /// various assumptions that the high-level API makes about the operation of
-    /// the DW1000. Don't use the register-level and high-level APIs in tandem,
-    /// unless you know what you're doing.
-    pub fn ll(&mut self) -> &mut ll::DW1000<SPI, CS> {
-        &mut self.ll
+    /// See user manual, section 2.5.5.10. The LDE microcode is lost during
+    /// sleep, and needs to be loaded again afterwards.
+    fn load_lde_microcode(&mut self) -> Result<(), Error<SPI, CS>> {
+        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(ll::SysClks::Xti))?;
+        self.ll.otp_ctrl().modify(|_, w| w.ldeload(0b1))?;
+        while self.ll.otp_ctrl().read()?.ldeload() == 0b1 {}
+        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(ll::SysClks::Auto))?;
+
+        Ok(())
     }
 
     /// Force the DW1000 into IDLE mode
@@ -940,6 +2323,12 @@ pub enum Error<SPI, CS>
     /// wrong.
     DelayedSendTooLate,
 
+    /// The receiver could not be enabled at the requested time
+    ///
+    /// The requested time had already passed. The receive operation has been
+    /// cancelled, and the receiver won't be enabled.
+    DelayedReceiveTooLate,
+
     /// Transmitter could not power up in time for delayed send
     ///
     /// The frame was still transmitted, but the first bytes of the preamble
@@ -951,6 +2340,41 @@ pub enum Error<SPI, CS>
 
     /// The configuration was not valid. Some combinations of settings are not allowed.
     InvalidConfiguration,
+
+    /// The requested samples are outside of the channel impulse response
+    CirOutOfRange,
+
+    /// No acknowledgement arrived in time
+    AckTimeout,
+
+    /// Waited for an acknowledgement, but the frame didn't request one
+    NoAckRequested,
+
+    /// The DW1000 didn't respond after waking up
+    WakeUpFailed,
+
+    /// The OTP address is outside of the OTP memory
+    OtpAddressOutOfRange,
+
+    /// The OTP word can't be changed to the requested value
+    ///
+    /// Bits that have been programmed to 1 can't be changed back to 0.
+    OtpAlreadyProgrammed,
+
+    /// The OTP programming voltage could not be applied
+    OtpVpp,
+
+    /// The OTP word does not have the requested value after programming
+    OtpProgrammingFailed,
+
+    /// The WAKEUP pin could not be set
+    WakeUpPin,
+
+    /// The data doesn't fit into a frame
+    FrameTooLong,
+
+    /// The data doesn't fit into TX_BUFFER, or the preloaded frame
+    TxBufferOutOfRange,
 }
 
 impl<SPI, CS> From<ll::Error<SPI, CS>> for Error<SPI, CS>
@@ -1013,12 +2437,36 @@ impl<SPI, CS> fmt::Debug for Error<SPI, CS>
                 write!(f, "Frame({:?})", error),
             Error::DelayedSendTooLate =>
                 write!(f, "DelayedSendTooLate"),
+            Error::DelayedReceiveTooLate =>
+                write!(f, "DelayedReceiveTooLate"),
             Error::DelayedSendPowerUpWarning =>
                 write!(f, "DelayedSendPowerUpWarning"),
             Error::Ssmarshal(error) =>
                 write!(f, "Ssmarshal({:?})", error),
             Error::InvalidConfiguration =>
                 write!(f, "InvalidConfiguration"),
+            Error::CirOutOfRange =>
+                write!(f, "CirOutOfRange"),
+            Error::AckTimeout =>
+                write!(f, "AckTimeout"),
+            Error::NoAckRequested =>
+                write!(f, "NoAckRequested"),
+            Error::WakeUpFailed =>
+                write!(f, "WakeUpFailed"),
+            Error::WakeUpPin =>
+                write!(f, "WakeUpPin"),
+            Error::FrameTooLong =>
+                write!(f, "FrameTooLong"),
+            Error::TxBufferOutOfRange =>
+                write!(f, "TxBufferOutOfRange"),
+            Error::OtpAddressOutOfRange =>
+                write!(f, "OtpAddressOutOfRange"),
+            Error::OtpAlreadyProgrammed =>
+                write!(f, "OtpAlreadyProgrammed"),
+            Error::OtpVpp =>
+                write!(f, "OtpVpp"),
+            Error::OtpProgrammingFailed =>
+                write!(f, "OtpProgrammingFailed"),
         }
     }
 }
@@ -1035,12 +2483,31 @@ pub struct Ready;
 /// Indicates that the `DW1000` instance is currently sending
 #[derive(Debug)]
 pub struct Sending {
-    finished: bool,
+    finished:    bool,
+    ack_seq:     Option<u8>,
+    ack_timeout: u16,
+    tx_done:     Option<Instant>,
 }
 
 /// Indicates that the `DW1000` instance is currently receiving
 #[derive(Debug)]
 pub struct Receiving {
+    finished:        bool,
+    double_buffered: bool,
+    continuous:      bool,
+}
+
+/// Indicates that the `DW1000` instance is asleep
+#[derive(Debug)]
+pub struct Sleeping {
+    preserve_config:  bool,
+    rx_antenna_delay: u16,
+}
+
+/// Indicates that the `DW1000` instance is sending, then receiving a response
+#[derive(Debug)]
+pub struct Exchanging {
+    sent:     bool,
     finished: bool,
 }
 
@@ -1056,4 +2523,395 @@ pub struct Message<'l> {
 
     /// The MAC frame
     pub frame: mac::Frame<'l>,
+
+    /// Diagnostic information about the quality of the received signal
+    pub rx_quality: RxQuality,
+}
+
+
+/// A frame that has been written to TX_BUFFER, to be sent later
+///
+/// Returned by [`DW1000::preload`] and [`DW1000::preload_raw`]. Pass it to
+/// [`DW1000::send_preloaded`] to send the frame.
+///
+/// [`DW1000::preload`]: struct.DW1000.html#method.preload
+/// [`DW1000::preload_raw`]: struct.DW1000.html#method.preload_raw
+/// [`DW1000::send_preloaded`]: struct.DW1000.html#method.send_preloaded
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PreloadedFrame {
+    offset:         u16,
+    len:            u16,
+    payload_offset: u16,
+    seq:            Option<u8>,
+}
+
+impl PreloadedFrame {
+    /// The position of the frame in TX_BUFFER
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// The length of the frame, without the FCS
+    pub fn frame_len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// The length of the payload
+    ///
+    /// This is the part of the frame that can be changed using
+    /// [`DW1000::patch_preloaded`]. For raw frames, it is the whole frame.
+    ///
+    /// [`DW1000::patch_preloaded`]: struct.DW1000.html#method.patch_preloaded
+    pub fn payload_len(&self) -> usize {
+        (self.len - self.payload_offset) as usize
+    }
+
+    /// The frame's sequence number, unless it is a raw frame
+    pub fn seq(&self) -> Option<u8> {
+        self.seq
+    }
+}
+
+
+/// An incoming frame, as received by [`Receiving::wait_raw`]
+///
+/// [`Receiving::wait_raw`]: struct.DW1000.html#method.wait_raw
+#[derive(Debug)]
+pub struct RawMessage<'l> {
+    /// The time the frame was received
+    ///
+    /// This time is based on the local system time, as defined in the SYS_TIME
+    /// register.
+    pub rx_time: Instant,
+
+    /// The received PSDU
+    ///
+    /// Includes the two-octet FCS at the end, which has already been checked
+    /// by the DW1000.
+    pub data: &'l [u8],
+
+    /// Diagnostic information about the quality of the received signal
+    pub rx_quality: RxQuality,
+}
+
+
+/// Diagnostic information about the quality of a received frame
+///
+/// Contains the raw values the DW1000 reports for each received frame, and
+/// methods to estimate the signal power from them, as described in the user
+/// manual, section 4.7.
+///
+/// If the receive power is significantly higher than the first path power
+/// (6 dB or more, according to the user manual), the channel is likely to be
+/// non-line-of-sight, and range measurements will be less accurate.
+#[derive(Clone, Copy, Debug)]
+pub struct RxQuality {
+    /// Standard deviation of the noise level (STD_NOISE in RX_FQUAL)
+    pub std_noise: u16,
+
+    /// First path index (FP_INDEX in RX_TIME)
+    ///
+    /// This is the position of the first path in the channel impulse response,
+    /// as a fixed-point number with 6 fractional bits. See
+    /// [`DW1000::read_cir`].
+    ///
+    /// [`DW1000::read_cir`]: struct.DW1000.html#method.read_cir
+    pub fp_index: u16,
+
+    /// First path amplitude point 1 (FP_AMPL1 in RX_TIME)
+    pub fp_ampl1: u16,
+
+    /// First path amplitude point 2 (FP_AMPL2 in RX_FQUAL)
+    pub fp_ampl2: u16,
+
+    /// First path amplitude point 3 (FP_AMPL3 in RX_FQUAL)
+    pub fp_ampl3: u16,
+
+    /// Channel impulse response power (CIR_PWR in RX_FQUAL)
+    pub cir_pwr: u16,
+
+    /// Preamble accumulation count (RXPACC in RX_FINFO)
+    pub rxpacc: u16,
+
+    /// Unsaturated preamble accumulation count (RXPACC_NOSAT)
+    pub rxpacc_nosat: u16,
+
+    /// The pulse repetition frequency the frame was received with
+    pub pulse_repetition_frequency: PulseRepetitionFrequency,
+
+    /// The bitrate the frame was received with
+    pub bitrate: BitRate,
+
+    /// The SFD sequence the receiver was configured for
+    pub sfd_sequence: SfdSequence,
+}
+
+impl RxQuality {
+    /// Estimates the power of the first path, in dBm
+    ///
+    /// Implements the formula from the user manual, section 4.7.1.
+    pub fn first_path_power_dbm(&self) -> f32 {
+        let f1 = self.fp_ampl1 as f32;
+        let f2 = self.fp_ampl2 as f32;
+        let f3 = self.fp_ampl3 as f32;
+        let n  = self.preamble_count();
+
+        10.0 * log10((f1 * f1 + f2 * f2 + f3 * f3) / (n * n))
+            - self.correction_factor()
+    }
+
+    /// Estimates the receive power, in dBm
+    ///
+    /// Implements the formula from the user manual, section 4.7.2.
+    pub fn rx_power_dbm(&self) -> f32 {
+        let c = self.cir_pwr as f32;
+        let n = self.preamble_count();
+
+        10.0 * log10(c * (1 << 17) as f32 / (n * n))
+            - self.correction_factor()
+    }
+
+    /// The preamble accumulation count `N` used by the power formulas
+    ///
+    /// If RXPACC hasn't saturated, it includes some SFD symbols, which need to
+    /// be subtracted, according to the user manual, section 4.7.1, table 18.
+    fn preamble_count(&self) -> f32 {
+        if self.rxpacc != self.rxpacc_nosat {
+            return self.rxpacc as f32;
+        }
+
+        // The table only lists values for the standard and the Decawave SFD.
+        // Other non-standard SFDs are treated like the Decawave one.
+        let correction = match (self.sfd_sequence, self.bitrate) {
+            (SfdSequence::IEEE, BitRate::Kbps110) => 64,
+            (SfdSequence::IEEE, _)                => 5,
+            (_, BitRate::Kbps110)                 => 82,
+            (_, BitRate::Kbps850)                 => 18,
+            (_, BitRate::Kbps6800)                => 10,
+        };
+
+        self.rxpacc.saturating_sub(correction) as f32
+    }
+
+    /// The constant `A` from the formulas in the user manual, section 4.7
+    fn correction_factor(&self) -> f32 {
+        match self.pulse_repetition_frequency {
+            PulseRepetitionFrequency::Mhz16 => 113.77,
+            PulseRepetitionFrequency::Mhz64 => 121.74,
+        }
+    }
+}
+
+
+/// A temperature and voltage measurement
+///
+/// Returned by [`DW1000::read_temperature_and_voltage`]. Contains both the raw
+/// SAR ADC readings and the converted values.
+///
+/// [`DW1000::read_temperature_and_voltage`]: struct.DW1000.html#method.read_temperature_and_voltage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SarReading {
+    /// The chip temperature, in °C
+    pub temperature: f32,
+
+    /// The supply voltage, in V
+    pub voltage: f32,
+
+    /// The raw temperature reading (SAR_LTEMP in TC_SARL)
+    pub raw_temperature: u8,
+
+    /// The raw voltage reading (SAR_LVBAT in TC_SARL)
+    pub raw_voltage: u8,
+}
+
+impl SarReading {
+    /// Converts raw readings, using the calibration values from OTP memory
+    ///
+    /// `temperature_cal` is the reading at 23 °C, `voltage_cal` the reading
+    /// at 3.3 V. The formulas are taken from the user manual, section 6.4.
+    pub fn new(
+        raw_temperature: u8,
+        raw_voltage:     u8,
+        temperature_cal: u8,
+        voltage_cal:     u8,
+    )
+        -> Self
+    {
+        let temperature =
+            (raw_temperature as f32 - temperature_cal as f32) * 1.14 + 23.0;
+        let voltage =
+            (raw_voltage as f32 - voltage_cal as f32) / 173.0 + 3.3;
+
+        SarReading {
+            temperature,
+            voltage,
+            raw_temperature,
+            raw_voltage,
+        }
+    }
+}
+
+
+/// The clock offset to a remote node
+///
+/// Returned by [`DW1000::read_clock_offset`]. The offset is derived from the
+/// carrier recovery integrator, as described in the user manual, section
+/// 7.2.40.11. Contains a crystal trim value that would bring the local clock
+/// closer to the remote one, which can be passed to
+/// [`DW1000::set_xtal_trim`]. If the remote node is known to be well
+/// calibrated, repeating this a few times calibrates the local node.
+///
+/// [`DW1000::read_clock_offset`]: struct.DW1000.html#method.read_clock_offset
+/// [`DW1000::set_xtal_trim`]: struct.DW1000.html#method.set_xtal_trim
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockOffset {
+    /// How much faster the remote clock runs than the local one, in ppm
+    ///
+    /// Negative values mean the remote clock runs slower.
+    pub ppm: f32,
+
+    /// The carrier integrator value the offset was computed from
+    pub carrier_integrator: i32,
+
+    /// The crystal trim value that would compensate for the offset
+    pub suggested_xtal_trim: u8,
+}
+
+impl ClockOffset {
+    /// Computes the clock offset from a carrier integrator value
+    ///
+    /// `rx_config` is the configuration the frame was received with.
+    /// `xtal_trim` is the crystal trim value that was in use at the time.
+    pub fn new(carrier_integrator: i32, rx_config: RxConfig, xtal_trim: u8)
+        -> Self
+    {
+        // See user manual, section 7.2.40.11. The integrator counts in steps
+        // of `998.4 MHz / 2^n` of carrier offset, and the carrier frequency is
+        // a multiple of `998.4 MHz / 2`, so the 998.4 MHz cancel out.
+        let shift = rx_config.bitrate.get_carrier_integrator_shift();
+        let ratio = 2.0 / rx_config.channel.get_carrier_multiple() as f32
+            / (1u32 << shift) as f32;
+        let ppm = -(carrier_integrator as f32) * ratio * 1e6;
+
+        // A remote clock that runs faster requires a faster local clock, which
+        // means a lower trim value. `core` can't round, hence the manual
+        // rounding to the nearest step.
+        let steps = ppm / XTAL_TRIM_PPM_PER_STEP;
+        let steps = if steps < 0.0 { steps - 0.5 } else { steps + 0.5 } as i32;
+        let suggested_xtal_trim =
+            (xtal_trim as i32 - steps).clamp(0, XTAL_TRIM_MAX as i32) as u8;
+
+        ClockOffset {
+            ppm,
+            carrier_integrator,
+            suggested_xtal_trim,
+        }
+    }
+}
+
+
+/// Makes sure a frame fits into TX_BUFFER, as configured
+///
+/// `len` is the length of the frame data, without the FCS.
+fn check_frame_len<SPI, CS>(len: usize, config: &TxConfig)
+    -> Result<(), Error<SPI, CS>>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    let max_len = if config.long_frames {
+        MAX_LONG_FRAME_LEN
+    }
+    else {
+        MAX_FRAME_LEN
+    };
+
+    if len + FCS_LEN > max_len {
+        return Err(Error::FrameTooLong);
+    }
+
+    Ok(())
+}
+
+/// Computes the time it takes to transmit the PHR and data of a frame
+///
+/// This is the time from the RMARKER to the end of the frame. `len` is the
+/// length of the frame data, including the FCS.
+fn phr_and_data_duration(len: usize, bitrate: BitRate) -> Duration {
+    // The PHR is always 21 bits long, and is sent at 850 kbps, unless the
+    // data is sent at 110 kbps.
+    let (phr_kbps, data_kbps) = match bitrate {
+        BitRate::Kbps110  => (110, 110),
+        BitRate::Kbps850  => (850, 850),
+        BitRate::Kbps6800 => (850, 6800),
+    };
+
+    // Reed-Solomon encoding adds 48 parity bits to every 330 data bits.
+    let data_bits = len as u64 * 8 * 378 / 330;
+    let nanos = 21 * 1_000_000 / phr_kbps
+        + data_bits * 1_000_000 / data_kbps;
+
+    // A frame takes less than 100 ms, so this can't truncate.
+    Duration::from_nanos(nanos as u32)
+}
+
+/// Makes sure a frame fits into TX_BUFFER at the given offset
+///
+/// `len` is the length of the frame data, without the FCS, which the DW1000
+/// appends in TX_BUFFER.
+fn check_tx_buffer_range<SPI, CS>(offset: u16, len: usize)
+    -> Result<(), Error<SPI, CS>>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    if offset as usize + len + FCS_LEN > <ll::TX_BUFFER as ll::Register>::LEN {
+        return Err(Error::TxBufferOutOfRange);
+    }
+
+    Ok(())
+}
+
+/// Returns the value of SYS_CFG.PHR_MODE for the given configuration
+fn phr_mode(long_frames: bool) -> ll::PhrMode {
+    if long_frames { ll::PhrMode::Long } else { ll::PhrMode::Standard }
+}
+
+
+/// Computes the base-10 logarithm
+///
+/// `core` doesn't provide this, so here's an approximation that is accurate to
+/// about 1e-5, which is plenty for power estimates. Returns negative infinity
+/// for zero, and NaN for negative numbers and NaN.
+fn log10(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    if x.is_infinite() {
+        return x;
+    }
+
+    // Split `x` into `mantissa * 2^exponent`, with `mantissa` in [1, 2).
+    // Subnormal numbers are scaled up first.
+    let (x, offset) = if x.is_normal() {
+        (x, 0)
+    }
+    else {
+        (x * (1u64 << 32) as f32, 32)
+    };
+    let bits     = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 - offset;
+    let mantissa = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+
+    // ln(m) = 2 * atanh(s), with s = (m - 1) / (m + 1) <= 1/3
+    let s  = (mantissa - 1.0) / (mantissa + 1.0);
+    let s2 = s * s;
+    let ln_mantissa = 2.0 * s
+        * (1.0 + s2 * (1.0 / 3.0 + s2 * (1.0 / 5.0 + s2 * (1.0 / 7.0 + s2 / 9.0))));
+
+    (exponent as f32 * core::f32::consts::LN_2 + ln_mantissa)
+        * core::f32::consts::LOG10_E
 }
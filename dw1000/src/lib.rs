@@ -27,27 +27,43 @@
 #![deny(missing_docs)]
 
 
+#[cfg(feature = "sim")]
+extern crate std;
+
+
 pub mod ll;
 pub mod hl;
+pub mod otp;
 pub mod ranging;
 pub mod time;
 pub mod configs;
 
+#[cfg(feature = "sim")]
+pub mod sim;
+
 
 #[doc(no_inline)]
 pub use ieee802154::mac;
 
 pub use crate::hl::{
+    ClockOffset,
     DW1000,
     Error,
+    Exchanging,
     Message,
+    PreloadedFrame,
+    RawMessage,
     Ready,
     Receiving,
+    RxQuality,
+    SarReading,
     Sending,
+    Sleeping,
     Uninitialized,
 };
 
 pub use crate::configs::{
     TxConfig,
-    RxConfig
+    RxConfig,
+    SleepConfig,
 };
@@ -7,6 +7,10 @@
 //! If you're using the low-level interface because the high-level interface
 //! doesn't cover your use case, please consider [filing an issue].
 //!
+//! Fields with enumerated values are accessed through enums, like
+//! [`SysClks`]. For those fields, raw accessors with a `_raw` suffix are
+//! available too.
+//!
 //! **NOTE**: Many field access methods accept types that have a larger number
 //! of bits than the field actually consists of. If you use such a method to
 //! pass a value that is too large to be written to the field, it will be
@@ -22,10 +26,20 @@ use core::{
 };
 
 use embedded_hal::{
-    blocking::spi,
+    blocking::{
+        delay::DelayUs,
+        spi,
+    },
     digital::v2::OutputPin,
 };
 
+use crate::configs::{
+    BitRate,
+    PreambleLength,
+    PulseRepetitionFrequency,
+    UwbChannel,
+};
+
 
 /// Entry point to the DW1000 driver's low-level API
 ///
@@ -50,6 +64,30 @@ impl<SPI, CS> DW1000<SPI, CS> {
     }
 }
 
+impl<SPI, CS> DW1000<SPI, CS>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Wake the DW1000 from sleep by holding chip select low
+    ///
+    /// Chip select is held low for 500 µs, which is long enough for the
+    /// DW1000 to wake up, if waking up via SPI is enabled in AON_CFG0. See
+    /// user manual, section 2.4.3. The DW1000 needs more time after this,
+    /// before it can be used.
+    pub fn wake_up<D>(&mut self, delay: &mut D) -> Result<(), Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        self.chip_select.set_low()
+            .map_err(|err| Error::ChipSelect(err))?;
+        delay.delay_us(500);
+        self.chip_select.set_high()
+            .map_err(|err| Error::ChipSelect(err))?;
+
+        Ok(())
+    }
+}
+
 
 /// Provides access to a register
 ///
@@ -69,9 +107,9 @@ impl<'s, R, SPI, CS> RegAccessor<'s, R, SPI, CS>
             R: Register + Readable,
     {
         let mut r      = R::read();
-        let mut buffer = R::buffer(&mut r);
+        let buffer     = R::buffer(&mut r);
 
-        init_header::<R>(false, &mut buffer);
+        init_header::<R>(false, buffer);
 
         self.0.chip_select.set_low()
             .map_err(|err| Error::ChipSelect(err))?;
@@ -134,6 +172,69 @@ impl<'s, R, SPI, CS> RegAccessor<'s, R, SPI, CS>
 
         Ok(())
     }
+
+    /// Read part of the register
+    ///
+    /// Fills `buffer` with the contents of the register, starting at `offset`.
+    /// Only as many bytes as fit into `buffer` are transferred. If the DW1000
+    /// sends dummy bytes before the actual data, those are discarded.
+    ///
+    /// Panics, if the access would extend beyond the end of the register.
+    pub fn read_at(&mut self, offset: u16, buffer: &mut [u8])
+        -> Result<(), Error<SPI, CS>>
+        where
+            R: ReadableBuffer,
+    {
+        assert!(offset as usize + buffer.len() <= R::LEN);
+
+        let mut header = [0; 3];
+        let header_len =
+            init_header_at(false, R::ID, R::SUB_ID + offset, &mut header);
+
+        self.0.chip_select.set_low()
+            .map_err(|err| Error::ChipSelect(err))?;
+        <SPI as spi::Write<u8>>::write(&mut self.0.spi, &header[..header_len])
+            .map_err(|err| Error::Write(err))?;
+        for _ in 0 .. R::DUMMY_LEN {
+            self.0.spi.transfer(&mut [0])
+                .map_err(|err| Error::Transfer(err))?;
+        }
+        self.0.spi.transfer(buffer)
+            .map_err(|err| Error::Transfer(err))?;
+        self.0.chip_select.set_high()
+            .map_err(|err| Error::ChipSelect(err))?;
+
+        Ok(())
+    }
+
+    /// Write part of the register
+    ///
+    /// Writes `data` to the register, starting at `offset`. The rest of the
+    /// register is left as it is.
+    ///
+    /// Panics, if the access would extend beyond the end of the register.
+    pub fn write_at(&mut self, offset: u16, data: &[u8])
+        -> Result<(), Error<SPI, CS>>
+        where
+            R: WritableBuffer,
+    {
+        assert!(offset as usize + data.len() <= R::LEN);
+
+        let mut header = [0; 3];
+        let header_len =
+            init_header_at(true, R::ID, R::SUB_ID + offset, &mut header);
+
+        self.0.chip_select.set_low()
+            .map_err(|err| Error::ChipSelect(err))?;
+        <SPI as spi::Write<u8>>::write(&mut self.0.spi, &header[..header_len])
+            .map_err(|err| Error::Write(err))?;
+        <SPI as spi::Write<u8>>::write(&mut self.0.spi, data)
+            .map_err(|err| Error::Write(err))?;
+        self.0.chip_select.set_high()
+            .map_err(|err| Error::ChipSelect(err))?;
+
+        Ok(())
+    }
 }
 
 
@@ -179,28 +280,39 @@ impl<SPI, CS> fmt::Debug for Error<SPI, CS>
 /// the header directly into the provided buffer. Returns the length of the
 /// header that was written.
 fn init_header<R: Register>(write: bool, buffer: &mut [u8]) -> usize {
-    let sub_id = R::SUB_ID > 0;
+    init_header_at(write, R::ID, R::SUB_ID, buffer)
+}
+
+/// Initializes the SPI message header for a given register ID and sub-index
+///
+/// Like `init_header`, but takes the register ID and sub-index as arguments.
+/// This is required when accessing parts of a register at runtime-defined
+/// offsets.
+fn init_header_at(write: bool, id: u8, sub_id: u16, buffer: &mut [u8])
+    -> usize
+{
+    let has_sub_id = sub_id > 0;
 
     buffer[0] =
-        (((write as u8)  << 7) & 0x80) |
-        (((sub_id as u8) << 6) & 0x40) |
-        (R::ID                 & 0x3f);
+        (((write as u8)      << 7) & 0x80) |
+        (((has_sub_id as u8) << 6) & 0x40) |
+        (id                        & 0x3f);
 
-    if !sub_id {
+    if !has_sub_id {
         return 1;
     }
 
-    let ext_addr = R::SUB_ID > 127;
+    let ext_addr = sub_id > 127;
 
     buffer[1] =
         (((ext_addr as u8) << 7) & 0x80) |
-        (R::SUB_ID as u8         & 0x7f); // lower 7 bits (of 15)
+        (sub_id as u8            & 0x7f); // lower 7 bits (of 15)
 
     if !ext_addr {
         return 2;
     }
 
-    buffer[2] = ((R::SUB_ID & 0x7f80) >> 7) as u8; // higher 8 bits (of 15)
+    buffer[2] = ((sub_id & 0x7f80) >> 7) as u8; // higher 8 bits (of 15)
 
     3
 }
@@ -257,6 +369,43 @@ pub trait Writable {
     fn buffer(w: &mut Self::Write) -> &mut [u8];
 }
 
+/// Marker trait for buffer-like registers that can be read partially
+///
+/// This is a mostly internal crate that should not be implemented or used
+/// directly by users of this crate. It is exposed through the public API
+/// though, so it can't be made private.
+pub trait ReadableBuffer: Register {
+    /// The number of dummy bytes the DW1000 sends before the actual data
+    const DUMMY_LEN: usize = 0;
+}
+
+/// Marker trait for buffer-like registers that can be written partially
+///
+/// This is a mostly internal crate that should not be implemented or used
+/// directly by users of this crate. It is exposed through the public API
+/// though, so it can't be made private.
+pub trait WritableBuffer: Register {}
+
+/// Static description of a register
+///
+/// Only used by the simulated DW1000, which needs to know which parts of the
+/// register file can be written by the host.
+#[cfg(feature = "sim")]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RegisterInfo {
+    /// The register index
+    pub id: u8,
+
+    /// The register's sub-index
+    pub sub_id: u16,
+
+    /// The length of the register
+    pub len: usize,
+
+    /// Whether the host can write to the register
+    pub writable: bool,
+}
+
 /// Generates register implementations
 macro_rules! impl_register {
     (
@@ -271,7 +420,7 @@ macro_rules! impl_register {
                 $field:ident,
                 $first_bit:expr,
                 $last_bit:expr,
-                $ty:ty;
+                $ty:ty $(as $enum:ty, $raw:ident)?;
                 #[$field_doc:meta]
             )*
             }
@@ -306,6 +455,10 @@ macro_rules! impl_register {
             pub mod $name_lower {
                 use core::fmt;
 
+                // Makes the field enums available
+                #[allow(unused_imports)]
+                use super::*;
+
 
                 const HEADER_LEN: usize = super::$name::HEADER_LEN;
 
@@ -315,101 +468,8 @@ macro_rules! impl_register {
 
                 impl R {
                     $(
-                        #[$field_doc]
-                        pub fn $field(&self) -> $ty {
-                            use core::mem::size_of;
-                            use crate::ll::FromBytes;
-
-                            // The index (in the register data) of the first
-                            // byte that contains a part of this field.
-                            const START: usize = $first_bit / 8;
-
-                            // The index (in the register data) of the byte
-                            // after the last byte that contains a part of this
-                            // field.
-                            const END: usize = $last_bit  / 8 + 1;
-
-                            // The numer of bytes in the register data that
-                            // contain part of this field.
-                            const LEN: usize = END - START;
-
-                            // Get all bytes that contain our field. The field
-                            // might fill out these bytes completely, or only
-                            // some bits in them.
-                            let mut bytes = [0; LEN];
-                            bytes[..LEN].copy_from_slice(
-                                &self.0[START+HEADER_LEN .. END+HEADER_LEN]
-                            );
-
-                            // Before we can convert the field into a number and
-                            // return it, we need to shift it, to make sure
-                            // there are no other bits to the right of it. Let's
-                            // start by determining the offset of the field
-                            // within a byte.
-                            const OFFSET_IN_BYTE: usize = $first_bit % 8;
-
-                            if OFFSET_IN_BYTE > 0 {
-                                // Shift the first byte. We always have at least
-                                // one byte here, so this always works.
-                                bytes[0] >>= OFFSET_IN_BYTE;
-
-                                // If there are more bytes, let's shift those
-                                // too.
-                                // We need to allow exceeding bitshifts in this
-                                // loop, as we run into that if `OFFSET_IN_BYTE`
-                                // equals `0`. Please note that we never
-                                // actually encounter that at runtime, due to
-                                // the if condition above.
-                                let mut i = 1;
-                                #[allow(exceeding_bitshifts)]
-                                #[allow(arithmetic_overflow)]
-                                while i < LEN {
-                                    bytes[i - 1] |=
-                                        bytes[i] << 8 - OFFSET_IN_BYTE;
-                                    bytes[i] >>= OFFSET_IN_BYTE;
-                                    i += 1;
-                                }
-                            }
-
-                            // If the field didn't completely fill out its last
-                            // byte, we might have bits from unrelated fields
-                            // there. Let's erase those before doing the final
-                            // conversion into the field's data type.
-                            const SIZE_IN_BITS: usize =
-                                $last_bit - $first_bit + 1;
-                            const BITS_ABOVE_FIELD: usize =
-                                8 - (SIZE_IN_BITS % 8);
-                            const SIZE_IN_BYTES: usize =
-                                (SIZE_IN_BITS - 1) / 8 + 1;
-                            const LAST_INDEX: usize =
-                                SIZE_IN_BYTES - 1;
-                            if BITS_ABOVE_FIELD < 8 {
-                                // Need to allow exceeding bitshifts to make the
-                                // compiler happy. They're never actually
-                                // encountered at runtime, due to the if
-                                // condition.
-                                #[allow(exceeding_bitshifts)]
-                                #[allow(arithmetic_overflow)]
-                                {
-                                    bytes[LAST_INDEX] <<= BITS_ABOVE_FIELD;
-                                    bytes[LAST_INDEX] >>= BITS_ABOVE_FIELD;
-                                }
-                            }
-
-                            // Now all that's left is to convert the bytes into
-                            // the field's type. Please note that methods for
-                            // converting numbers to/from bytes are coming to
-                            // stable Rust, so we might be able to remove our
-                            // custom infrastructure here. Tracking issue:
-                            // https://github.com/rust-lang/rust/issues/52963
-                            let bytes = if bytes.len() > size_of::<$ty>() {
-                                &bytes[..size_of::<$ty>()]
-                            }
-                            else {
-                                &bytes
-                            };
-                            <$ty as FromBytes>::from_bytes(bytes)
-                        }
+                        impl_field!(@R, $field, $first_bit, $last_bit, $ty,
+                            [$($enum, $raw)?], #[$field_doc]);
                     )*
                 }
 
@@ -430,119 +490,8 @@ macro_rules! impl_register {
 
                 impl W {
                     $(
-                        #[$field_doc]
-                        pub fn $field(&mut self, value: $ty) -> &mut Self {
-                            use crate::ll::ToBytes;
-
-                            // Convert value into bytes
-                            let source = <$ty as ToBytes>::to_bytes(value);
-
-                            // Now, let's figure out where the bytes are located
-                            // within the register array.
-                            const START:          usize = $first_bit / 8;
-                            const END:            usize = $last_bit  / 8 + 1;
-                            const OFFSET_IN_BYTE: usize = $first_bit % 8;
-
-                            // Also figure out the length of the value in bits.
-                            // That's going to come in handy.
-                            const LEN: usize = $last_bit - $first_bit + 1;
-
-
-                            // We need to track how many bits are left in the
-                            // value overall, and in the value's current byte.
-                            let mut bits_left         = LEN;
-                            let mut bits_left_in_byte = 8;
-
-                            // We also need to track how many bits have already
-                            // been written to the current target byte.
-                            let mut bits_written_to_byte = 0;
-
-                            // Now we can take the bytes from the value, shift
-                            // them, mask them, and write them into the target
-                            // array.
-                            let mut source_i  = 0;
-                            let mut target_i  = START;
-                            while target_i < END {
-                                // Values don't always end at byte boundaries,
-                                // so we need to mask the bytes when writing to
-                                // the slice.
-                                // Let's start out assuming we can write to the
-                                // whole byte of the slice. This will be true
-                                // for the middle bytes of our value.
-                                let mut mask = 0xff;
-
-                                // Let's keep track of the offset we're using to
-                                // write to this byte. We're going to need it.
-                                let mut offset_in_this_byte = 0;
-
-                                // If this is the first byte we're writing to
-                                // the slice, we need to remove the lower bits
-                                // of the mask.
-                                if target_i == START {
-                                    mask <<= OFFSET_IN_BYTE;
-                                    offset_in_this_byte = OFFSET_IN_BYTE;
-                                }
-
-                                // If this is the last byte we're writing to the
-                                // slice, we need to remove the higher bits of
-                                // the mask. Please note that we could be
-                                // writing to _both_ the first and the last
-                                // byte.
-                                if target_i == END - 1 {
-                                    let shift =
-                                        8 - bits_left - offset_in_this_byte;
-                                    mask <<= shift;
-                                    mask >>= shift;
-                                }
-
-                                mask <<= bits_written_to_byte;
-
-                                // Read the value from `source`
-                                let value = source[source_i]
-                                    >> 8 - bits_left_in_byte
-                                    << offset_in_this_byte
-                                    << bits_written_to_byte;
-
-                                // Zero the target bits in the slice, then write
-                                // the value.
-                                self.0[HEADER_LEN + target_i] &= !mask;
-                                self.0[HEADER_LEN + target_i] |= value & mask;
-
-                                // The number of bits that were expected to be
-                                // written to the target byte.
-                                let bits_needed = mask.count_ones() as usize;
-
-                                // The number of bits we actually wrote to the
-                                // target byte.
-                                let bits_used = bits_needed.min(
-                                    bits_left_in_byte - offset_in_this_byte
-                                );
-
-                                bits_left -= bits_used;
-                                bits_written_to_byte += bits_used;
-
-                                // Did we use up all the bits in the source
-                                // byte? If so, we can move on to the next one.
-                                if bits_left_in_byte > bits_used {
-                                    bits_left_in_byte -= bits_used;
-                                }
-                                else {
-                                    bits_left_in_byte =
-                                        8 - (bits_used - bits_left_in_byte);
-
-                                    source_i += 1;
-                                }
-
-                                // Did we write all the bits in the target byte?
-                                // If so, we can move on to the next one.
-                                if bits_used == bits_needed {
-                                    target_i += 1;
-                                    bits_written_to_byte = 0;
-                                }
-                            }
-
-                            self
-                        }
+                        impl_field!(@W, $field, $first_bit, $last_bit, $ty,
+                            [$($enum, $raw)?], #[$field_doc]);
                     )*
                 }
             }
@@ -554,14 +503,282 @@ macro_rules! impl_register {
         impl<SPI, CS> DW1000<SPI, CS> {
             $(
                 #[$doc]
-                pub fn $name_lower(&mut self) -> RegAccessor<$name, SPI, CS> {
+                pub fn $name_lower(&mut self) -> RegAccessor<'_, $name, SPI, CS> {
                     RegAccessor(self, PhantomData)
                 }
             )*
         }
+
+
+        /// Describes all registers declared in this invocation
+        ///
+        /// Used by the simulated DW1000 to enforce read-only registers.
+        #[cfg(feature = "sim")]
+        pub(crate) const REGISTERS: &[RegisterInfo] = &[
+            $(
+                RegisterInfo {
+                    id:       $id,
+                    sub_id:   $sub_id,
+                    len:      $len,
+                    writable: impl_rw!(@writable, $rw),
+                },
+            )*
+        ];
     }
 }
 
+// Helper macro, used internally by `impl_register!` to generate field
+// accessors
+macro_rules! impl_field {
+    (@R, $field:ident, $first_bit:expr, $last_bit:expr, $ty:ty, [],
+        $(#[$field_doc:meta])+
+    ) => {
+        $(#[$field_doc])+
+        pub fn $field(&self) -> $ty {
+            use core::mem::size_of;
+            use crate::ll::FromBytes;
+
+            // The index (in the register data) of the first
+            // byte that contains a part of this field.
+            const START: usize = $first_bit / 8;
+
+            // The index (in the register data) of the byte
+            // after the last byte that contains a part of this
+            // field.
+            const END: usize = $last_bit  / 8 + 1;
+
+            // The numer of bytes in the register data that
+            // contain part of this field.
+            const LEN: usize = END - START;
+
+            // Get all bytes that contain our field. The field
+            // might fill out these bytes completely, or only
+            // some bits in them.
+            let mut bytes = [0; LEN];
+            bytes[..LEN].copy_from_slice(
+                &self.0[START+HEADER_LEN .. END+HEADER_LEN]
+            );
+
+            // Before we can convert the field into a number and
+            // return it, we need to shift it, to make sure
+            // there are no other bits to the right of it. Let's
+            // start by determining the offset of the field
+            // within a byte.
+            const OFFSET_IN_BYTE: usize = $first_bit % 8;
+
+            if OFFSET_IN_BYTE > 0 {
+                // Shift the first byte. We always have at least
+                // one byte here, so this always works.
+                bytes[0] >>= OFFSET_IN_BYTE;
+
+                // If there are more bytes, let's shift those
+                // too.
+                // We need to allow exceeding bitshifts in this
+                // loop, as we run into that if `OFFSET_IN_BYTE`
+                // equals `0`. Please note that we never
+                // actually encounter that at runtime, due to
+                // the if condition above.
+                let mut i = 1;
+                #[allow(arithmetic_overflow)]
+                while i < LEN {
+                    bytes[i - 1] |=
+                        bytes[i] << 8 - OFFSET_IN_BYTE;
+                    bytes[i] >>= OFFSET_IN_BYTE;
+                    i += 1;
+                }
+            }
+
+            // If the field didn't completely fill out its last
+            // byte, we might have bits from unrelated fields
+            // there. Let's erase those before doing the final
+            // conversion into the field's data type.
+            const SIZE_IN_BITS: usize =
+                $last_bit - $first_bit + 1;
+            const BITS_ABOVE_FIELD: usize =
+                8 - (SIZE_IN_BITS % 8);
+            const SIZE_IN_BYTES: usize =
+                (SIZE_IN_BITS - 1) / 8 + 1;
+            const LAST_INDEX: usize =
+                SIZE_IN_BYTES - 1;
+            if BITS_ABOVE_FIELD < 8 {
+                // Need to allow exceeding bitshifts to make the
+                // compiler happy. They're never actually
+                // encountered at runtime, due to the if
+                // condition.
+                #[allow(arithmetic_overflow)]
+                {
+                    bytes[LAST_INDEX] <<= BITS_ABOVE_FIELD;
+                    bytes[LAST_INDEX] >>= BITS_ABOVE_FIELD;
+                }
+            }
+
+            // Now all that's left is to convert the bytes into
+            // the field's type. Please note that methods for
+            // converting numbers to/from bytes are coming to
+            // stable Rust, so we might be able to remove our
+            // custom infrastructure here. Tracking issue:
+            // https://github.com/rust-lang/rust/issues/52963
+            let bytes = if bytes.len() > size_of::<$ty>() {
+                &bytes[..size_of::<$ty>()]
+            }
+            else {
+                &bytes
+            };
+            <$ty as FromBytes>::from_bytes(bytes)
+        }
+    };
+    (@R, $field:ident, $first_bit:expr, $last_bit:expr, $ty:ty,
+        [$enum:ty, $raw:ident], #[$field_doc:meta]
+    ) => {
+        impl_field!(@R, $raw, $first_bit, $last_bit, $ty, [],
+            #[$field_doc]
+            #[doc = ""]
+            #[doc = "Accesses the raw value, bypassing the field's enum type."]
+        );
+
+        #[$field_doc]
+        ///
+        /// Returns `None`, if the field contains a value that doesn't
+        /// correspond to any variant of the field's type. Use the raw
+        /// accessor to read such values.
+        pub fn $field(&self) -> Option<$enum> {
+            <$enum as crate::ll::FieldEnum>::from_raw(self.$raw())
+        }
+    };
+
+    (@W, $field:ident, $first_bit:expr, $last_bit:expr, $ty:ty, [],
+        $(#[$field_doc:meta])+
+    ) => {
+        $(#[$field_doc])+
+        pub fn $field(&mut self, value: $ty) -> &mut Self {
+            use crate::ll::ToBytes;
+
+            // Convert value into bytes
+            let source = <$ty as ToBytes>::to_bytes(value);
+
+            // Now, let's figure out where the bytes are located
+            // within the register array.
+            const START:          usize = $first_bit / 8;
+            const END:            usize = $last_bit  / 8 + 1;
+            const OFFSET_IN_BYTE: usize = $first_bit % 8;
+
+            // Also figure out the length of the value in bits.
+            // That's going to come in handy.
+            const LEN: usize = $last_bit - $first_bit + 1;
+
+
+            // We need to track how many bits are left in the
+            // value overall, and in the value's current byte.
+            let mut bits_left         = LEN;
+            let mut bits_left_in_byte = 8;
+
+            // We also need to track how many bits have already
+            // been written to the current target byte.
+            let mut bits_written_to_byte = 0;
+
+            // Now we can take the bytes from the value, shift
+            // them, mask them, and write them into the target
+            // array.
+            let mut source_i  = 0;
+            let mut target_i  = START;
+            while target_i < END {
+                // Values don't always end at byte boundaries,
+                // so we need to mask the bytes when writing to
+                // the slice.
+                // Let's start out assuming we can write to the
+                // whole byte of the slice. This will be true
+                // for the middle bytes of our value.
+                let mut mask = 0xff;
+
+                // Let's keep track of the offset we're using to
+                // write to this byte. We're going to need it.
+                let mut offset_in_this_byte = 0;
+
+                // If this is the first byte we're writing to
+                // the slice, we need to remove the lower bits
+                // of the mask.
+                if target_i == START {
+                    mask <<= OFFSET_IN_BYTE;
+                    offset_in_this_byte = OFFSET_IN_BYTE;
+                }
+
+                // If this is the last byte we're writing to the
+                // slice, we need to remove the higher bits of
+                // the mask. Please note that we could be
+                // writing to _both_ the first and the last
+                // byte.
+                if target_i == END - 1 {
+                    let shift =
+                        8 - bits_left - offset_in_this_byte;
+                    mask <<= shift;
+                    mask >>= shift;
+                }
+
+                mask <<= bits_written_to_byte;
+
+                // Read the value from `source`
+                let value = source[source_i]
+                    >> 8 - bits_left_in_byte
+                    << offset_in_this_byte
+                    << bits_written_to_byte;
+
+                // Zero the target bits in the slice, then write
+                // the value.
+                self.0[HEADER_LEN + target_i] &= !mask;
+                self.0[HEADER_LEN + target_i] |= value & mask;
+
+                // The number of bits that were expected to be
+                // written to the target byte.
+                let bits_needed = mask.count_ones() as usize;
+
+                // The number of bits we actually wrote to the
+                // target byte.
+                let bits_used = bits_needed.min(
+                    bits_left_in_byte - offset_in_this_byte
+                );
+
+                bits_left -= bits_used;
+                bits_written_to_byte += bits_used;
+
+                // Did we use up all the bits in the source
+                // byte? If so, we can move on to the next one.
+                if bits_left_in_byte > bits_used {
+                    bits_left_in_byte -= bits_used;
+                }
+                else {
+                    bits_left_in_byte =
+                        8 - (bits_used - bits_left_in_byte);
+
+                    source_i += 1;
+                }
+
+                // Did we write all the bits in the target byte?
+                // If so, we can move on to the next one.
+                if bits_used == bits_needed {
+                    target_i += 1;
+                    bits_written_to_byte = 0;
+                }
+            }
+
+            self
+        }
+    };
+    (@W, $field:ident, $first_bit:expr, $last_bit:expr, $ty:ty,
+        [$enum:ty, $raw:ident], #[$field_doc:meta]
+    ) => {
+        impl_field!(@W, $raw, $first_bit, $last_bit, $ty, [],
+            #[$field_doc]
+            #[doc = ""]
+            #[doc = "Accesses the raw value, bypassing the field's enum type."]
+        );
+
+        #[$field_doc]
+        pub fn $field(&mut self, value: $enum) -> &mut Self {
+            self.$raw(<$enum as crate::ll::FieldEnum>::to_raw(value))
+        }
+    };
+}
+
 // Helper macro, used internally by `impl_register!`
 macro_rules! impl_rw {
     (RO, $name:ident, $name_lower:ident, $len:expr) => {
@@ -585,6 +802,9 @@ macro_rules! impl_rw {
             }
         }
     };
+    (@writable, RO) => { false };
+    (@writable, RW) => { true };
+
     (@W, $name:ident, $name_lower:ident, $len:expr) => {
         impl Writable for $name {
             type Write = $name_lower::W;
@@ -610,6 +830,10 @@ macro_rules! impl_rw {
 //
 // Each field follows the following syntax:
 // <name>, <first-bit-index>, <last-bit-index>, <type>; /// <doc>
+//
+// Fields whose values are enumerated can name an enum that implements
+// `FieldEnum`, as well as a name for the raw accessors:
+// <name>, <first-bit-index>, <last-bit-index>, <type> as <enum>, <raw-name>; /// <doc>
 impl_register! {
     0x00, 0x00, 4, RO, DEV_ID(dev_id) { /// Device identifier
         rev,     0,  3, u8;  /// Revision
@@ -641,7 +865,7 @@ impl_register! {
         dis_phe,    13, 13, u8; /// Disable Receiver Abort on PHR Error
         dis_rsde,   14, 14, u8; /// Disable Receiver Abort on RSD Error
         fcs_init2f, 15, 15, u8; /// FCS Seed Selection
-        phr_mode,   16, 17, u8; /// PHR Mode
+        phr_mode,   16, 17, u8 as PhrMode, phr_mode_raw; /// PHR Mode
         dis_stxp,   18, 18, u8; /// Disable Smart TX Power Control
         rxm110k,    22, 22, u8; /// Receiver Mode 110kpbs Data Rate
         rxwtoe,     28, 28, u8; /// Receiver Wait Timeout Enable
@@ -655,17 +879,20 @@ impl_register! {
     0x08, 0x00, 5, RW, TX_FCTRL(tx_fctrl) { /// TX Frame Control
         tflen,     0,  6, u8;  /// TX Frame Length
         tfle,      7,  9, u8;  /// TX Frame Length Extension
-        txbr,     13, 14, u8;  /// TX Bit Rate
+        txbr,     13, 14, u8 as BitRate, txbr_raw; /// TX Bit Rate
         tr,       15, 15, u8;  /// TX Ranging Enable
-        txprf,    16, 17, u8;  /// TX Pulse Repetition Frequency
-        txpsr,    18, 19, u8;  /// TX Preamble Symbol Repetitions
-        pe,       20, 21, u8;  /// Preamble Extension
+        txprf,    16, 17, u8 as PulseRepetitionFrequency, txprf_raw; /// TX Pulse Repetition Frequency
+        txpsr,    18, 19, u8;  /// TX Preamble Symbol Repetitions (see `preamble_length`)
+        pe,       20, 21, u8;  /// Preamble Extension (see `preamble_length`)
         txboffs,  22, 31, u16; /// TX Buffer Index Offset
         ifsdelay, 32, 39, u8;  /// Inter-Frame Spacing
     }
     0x0A, 0x00, 5, RW, DX_TIME(dx_time) { /// Delayed Send or Receive Time
         value, 0, 39, u64; /// Delayed Send or Receive Time
     }
+    0x0C, 0x00, 2, RW, RX_FWTO(rx_fwto) { /// Receive Frame Wait Timeout Period
+        value, 0, 15, u16; /// Receive Frame Wait Timeout Period
+    }
     0x0D, 0x00, 4, RW, SYS_CTRL(sys_ctrl) { /// System Control Register
         sfcst,      0,  0, u8; /// Suppress Auto-FCS Transmission
         txstrt,     1,  1, u8; /// Transmit Start
@@ -747,10 +974,17 @@ impl_register! {
         rxflen,  0,  6, u8; /// Receive Frame Length
         rxfle,   7,  9, u8; /// Receive Frame Length Extension
         rxnspl, 11, 12, u8; /// Receive Non-Standard Preamble Length
-        rxbr,   13, 14, u8; /// Receive Bit Rate Report
+        rxbr,   13, 14, u8 as BitRate, rxbr_raw; /// Receive Bit Rate Report
         rng,    15, 15, u8; /// Receiver Ranging
-        rxprfr, 16, 17, u8; /// RX Pulse Repetition Rate Report
+        rxprfr, 16, 17, u8 as PulseRepetitionFrequency, rxprfr_raw; /// RX Pulse Repetition Rate Report
         rxpsr,  18, 19, u8; /// RX Preamble Repetition
+        rxpacc, 20, 31, u16; /// Preamble Accumulation Count
+    }
+    0x12, 0x00, 8, RO, RX_FQUAL(rx_fqual) { /// Rx Frame Quality Information
+        std_noise,  0, 15, u16; /// Standard Deviation of Noise
+        fp_ampl2,  16, 31, u16; /// First Path Amplitude point 2
+        fp_ampl3,  32, 47, u16; /// First Path Amplitude point 3
+        cir_pwr,   48, 63, u16; /// Channel Impulse Response Power
     }
     0x15, 0x00, 14, RO, RX_TIME(rx_time) { /// Receive Time Stamp
         rx_stamp,  0,  39, u64; /// Fully adjusted time stamp
@@ -770,18 +1004,26 @@ impl_register! {
         rx_state,    8, 12, u8; /// Current Receive State Machine value
         pmsc_state, 16, 23, u8; /// Current PMSC State Machine value
     }
+    0x1A, 0x00, 4, RW, ACK_RESP_T(ack_resp_t) { /// Acknowledgement Time and Response Time
+        w4r_tim,  0, 19, u32; /// Wait-for-Response Turn-Around Time
+        ack_tim, 24, 31, u8;  /// Auto-Acknowledgement Turn-Around Time
+    }
+    0x1D, 0x00, 4, RW, RX_SNIFF(rx_sniff) { /// Sniff Mode
+        sniff_ont,  0,  3, u8; /// SNIFF Mode ON time, in units of PAC
+        sniff_offt, 8, 15, u8; /// SNIFF Mode OFF time, in units of approximately 1 µs
+    }
     0x1E, 0x00, 4, RW, TX_POWER(tx_power) { /// TX Power Control
         // The TX_POWER register has multiple sets of fields defined, depending
         // on the smart TX power control setting. I don't know how to model
         // this, so I've opted to provide just a single `value` field for
-        // maximum flexibility.
+        // maximum flexibility. `configs::TxPower` provides a typed view.
         value, 0, 31, u32; /// TX Power Control value
     }
     0x1F, 0x00, 4, RW, CHAN_CTRL(chan_ctrl) { /// Channel Control Register
-        tx_chan, 0, 3, u8; /// Selects the transmit channel.
-        rx_chan, 4, 7, u8; /// Selects the receive channel.
+        tx_chan, 0, 3, u8 as UwbChannel, tx_chan_raw; /// Selects the transmit channel.
+        rx_chan, 4, 7, u8 as UwbChannel, rx_chan_raw; /// Selects the receive channel.
         dwsfd, 17, 17, u8; /// Enables the non-standard Decawave proprietary SFD sequence.
-        rxprf, 18, 19, u8; /// Selects the PRF used in the receiver.
+        rxprf, 18, 19, u8 as PulseRepetitionFrequency, rxprf_raw; /// Selects the PRF used in the receiver.
         tnssfd, 20, 20, u8; /// This bit enables the use of a user specified (non-standard) SFDin the transmitter.
         rnssfd, 21, 21, u8; /// This bit enables the use of a user specified (non-standard) SFDin the receiver.
         tx_pcode, 22, 26, u8; /// This field selects the preamble code used in the transmitter.
@@ -952,8 +1194,8 @@ impl_register! {
     0x27, 0x26, 2, RW, DRX_TUNE4H(drx_tune4h) { /// Digital Tuning Register 4h
         value, 0, 15, u16; /// DRX_TUNE4H tuning value
     }
-    0x27, 0x28, 2, RO, DRX_CAR_INT(dxr_car_int) { /// Carrier Recovery Integrator Register
-        value, 0, 15, u16; /// value
+    0x27, 0x28, 3, RO, DRX_CAR_INT(drx_car_int) { /// Carrier Recovery Integrator Register
+        value, 0, 20, u32; /// 21-bit signed value
     }
     0x27, 0x2C, 2, RO, RXPACC_NOSAT(rxpacc_nosat) { /// Digital debug register. Unsaturated accumulated preamble symbols.
         value, 0, 15, u16; /// value
@@ -966,9 +1208,26 @@ impl_register! {
         txmq,    9, 11, u8; /// Transmit mixer Q-factor tuning register
         value, 0, 23, u32; /// The entire register
     }
+    0x28, 0x11, 1, RW, RF_SENSOR_BIAS(rf_sensor_bias) { /// Temperature and voltage sensor bias (undocumented, see user manual, section 6.4)
+        value, 0, 7, u8; /// Temperature and voltage sensor bias
+    }
+    0x28, 0x12, 1, RW, RF_SAR_CFG(rf_sar_cfg) { /// SAR ADC bias and output configuration (undocumented, see user manual, section 6.4)
+        value, 0, 7, u8; /// SAR ADC bias and output configuration
+    }
     0x28, 0x30, 5, RW, LDOTUNE(ldotune) { /// LDO voltage tuning parameter
         value, 0, 39, u64; /// Internal LDO voltage tuning parameter
     }
+    0x2A, 0x00, 1, RW, TC_SARC(tc_sarc) { /// Transmitter Calibration - SAR control
+        sar_ctrl, 0, 0, u8; /// Writing 1 sets SAR enable and writing 0 clears the enable
+    }
+    0x2A, 0x03, 3, RO, TC_SARL(tc_sarl) { /// Transmitter Calibration - Latest SAR readings
+        sar_lvbat,  0,  7, u8; /// Latest SAR reading for Voltage level
+        sar_ltemp,  8, 15, u8; /// Latest SAR reading for Temperature level
+    }
+    0x2A, 0x06, 2, RO, TC_SARW(tc_sarw) { /// Transmitter Calibration - SAR readings at last Wake-Up
+        sar_wvbat, 0,  7, u8; /// SAR reading of Voltage level taken at last wake up event
+        sar_wtemp, 8, 15, u8; /// SAR reading of Temperature level taken at last wake up event
+    }
     0x2A, 0x0B, 1, RW, TC_PGDELAY(tc_pgdelay) { /// Pulse Generator Delay
         value, 0, 7, u8; /// Transmitter Calibration - Pulse Generator Delay
     }
@@ -978,6 +1237,50 @@ impl_register! {
     0x2B, 0x0B, 1, RW, FS_PLLTUNE(fs_plltune) { /// Frequency synth - PLL Tuning
         value, 0, 7, u8; /// Frequency synthesiser - PLL Tuning
     }
+    0x2B, 0x0E, 1, RW, FS_XTALT(fs_xtalt) { /// Frequency synth - Crystal trim
+        xtalt,     0, 4, u8; /// Crystal Trim
+        xtalt_res, 5, 7, u8; /// Must be set to 0b011
+    }
+    0x2C, 0x00, 2, RW, AON_WCFG(aon_wcfg) { /// AON Wakeup Configuration Register
+        onw_radc,    0,  0, u8; /// On Wake-up Run the (temperature and voltage) Analog-to-Digital Convertors
+        onw_rx,      1,  1, u8; /// On Wake-up turn on the Receiver
+        onw_leui,    3,  3, u8; /// On Wake-up load the EUI from OTP memory into Register file: 0x01 – Extended Unique Identifier
+        onw_ldc,     6,  6, u8; /// On Wake-up load configurations from the AON memory into the host interface register set
+        onw_l64p,    7,  7, u8; /// On Wake-up load the Length64 receiver operating parameter set
+        pres_sleep,  8,  8, u8; /// Preserve Sleep
+        onw_llde,   11, 11, u8; /// On Wake-up load the LDE microcode
+        onw_lld0,   12, 12, u8; /// On Wake-up load the LDOTUNE value from OTP
+    }
+    0x2C, 0x02, 1, RW, AON_CTRL(aon_ctrl) { /// AON Control Register
+        restore,   0, 0, u8; /// Copy the user configurations from the AON memory to the host interface register set
+        save,      1, 1, u8; /// Copy the user configurations from the host interface register set into the AON memory
+        upl_cfg,   2, 2, u8; /// Upload the AON block configurations to the AON
+        dca_read,  3, 3, u8; /// Direct AON memory access read
+        dca_enab,  7, 7, u8; /// Direct AON memory access enable bit
+    }
+    0x2C, 0x03, 1, RO, AON_RDAT(aon_rdat) { /// AON Direct Access Read Data Result
+        value, 0, 7, u8; /// AON Direct Access Read Data Result
+    }
+    0x2C, 0x04, 1, RW, AON_ADDR(aon_addr) { /// AON Direct Access Address
+        value, 0, 7, u8; /// AON Direct Access Address
+    }
+    0x2C, 0x06, 4, RW, AON_CFG0(aon_cfg0) { /// AON Configuration Register 0
+        sleep_en,   0,  0, u8;  /// Sleep enable configuration bit
+        wake_pin,   1,  1, u8;  /// Wake using WAKEUP pin
+        wake_spi,   2,  2, u8;  /// Wake using SPI access
+        wake_cnt,   3,  3, u8;  /// Wake when sleep counter elapses
+        lpdiv_en,   4,  4, u8;  /// Low power divider enable configuration
+        lpclkdiva,  5, 15, u16; /// Divider count for dividing the raw DW1000 XTAL oscillator frequency
+        sleep_tim, 16, 31, u16; /// Sleep time
+    }
+    0x2C, 0x0A, 2, RW, AON_CFG1(aon_cfg1) { /// AON Configuration Register 1
+        sleep_ce, 0, 0, u8; /// Sleep counter enable
+        smxx,     1, 1, u8; /// Must be set to 0
+        lposc_c,  2, 2, u8; /// Low power oscillator calibration enable
+    }
+    0x2D, 0x00, 4, RW, OTP_WDAT(otp_wdat) { /// OTP Write Data
+        value, 0, 31, u32; /// OTP Write Data
+    }
     0x2D, 0x04, 2, RW, OTP_ADDR(otp_addr) { /// OTP Address
         value, 0, 10, u16; /// OTP Address
     }
@@ -989,9 +1292,18 @@ impl_register! {
         otpmr,    7, 10, u8; /// OTP mode register
         ldeload, 15, 15, u8; /// Force load of LDE microcode
     }
+    0x2D, 0x08, 2, RW, OTP_STAT(otp_stat) { /// OTP Status
+        otpprgd, 0, 0, u8; /// OTP Programming Done
+        otpvpok, 1, 1, u8; /// OTP Programming Voltage OK
+    }
     0x2D, 0x0A, 4, RO, OTP_RDAT(otp_rdat) { /// OTP Read Data
         value, 0, 31, u32; /// OTP Read Data
     }
+    0x2D, 0x12, 1, RW, OTP_SF(otp_sf) { /// OTP Special Function
+        ops_kick, 0, 0, u8; /// Load the operating parameter set selected by OPS_SEL
+        ldo_kick, 1, 1, u8; /// Load the LDOTUNE_CAL parameter from OTP
+        ops_sel,  5, 6, u8; /// Operating parameter set selection
+    }
     0x2E, 0x0806, 1, RW, LDE_CFG1(lde_cfg1) { /// LDE Configuration Register 1
         ntm,   0, 4, u8; /// Noise Threshold Multiplier
         pmult, 5, 7, u8; /// Peak Multiplier
@@ -1013,18 +1325,19 @@ impl_register! {
         value, 0, 11, u16; /// TX Power-Up Warning Event Counter
     }
     0x36, 0x00, 4, RW, PMSC_CTRL0(pmsc_ctrl0) { /// PMSC Control Register 0
-        sysclks,    0,  1, u8; /// System Clock Selection
-        rxclks,     2,  3, u8; /// Receiver Clock Selection
-        txclks,     4,  5, u8; /// Transmitter Clock Selection
-        face,       6,  6, u8; /// Force Accumulator Clock Enable
-        adcce,     10, 10, u8; /// ADC Clock Enable
-        amce,      15, 15, u8; /// Accumulator Memory Clock Enable
-        gpce,      16, 16, u8; /// GPIO Clock Enable
-        gprn,      17, 17, u8; /// GPIO Reset (Not), active low
-        gpdce,     18, 18, u8; /// GPIO De-bounce Clock Enable
-        gpdrn,     19, 19, u8; /// GPIO De-bounce Reset (Not), active low
-        khzclken,  23, 23, u8; /// Kilohertz Clock Enable
-        softreset, 28, 31, u8; /// Soft Reset
+        sysclks,      0,  1, u8 as SysClks, sysclks_raw; /// System Clock Selection
+        rxclks,       2,  3, u8 as SysClks, rxclks_raw; /// Receiver Clock Selection
+        txclks,       4,  5, u8 as SysClks, txclks_raw; /// Transmitter Clock Selection
+        face,         6,  6, u8; /// Force Accumulator Clock Enable
+        adcce,       10, 10, u8; /// ADC Clock Enable
+        amce,        15, 15, u8; /// Accumulator Memory Clock Enable
+        gpce,        16, 16, u8; /// GPIO Clock Enable
+        gprn,        17, 17, u8; /// GPIO Reset (Not), active low
+        gpdce,       18, 18, u8; /// GPIO De-bounce Clock Enable
+        gpdrn,       19, 19, u8; /// GPIO De-bounce Reset (Not), active low
+        khzclken,    23, 23, u8; /// Kilohertz Clock Enable
+        pll2_seq_en, 24, 24, u8; /// Enable PLL2 on/off sequencing by SNIFF mode
+        softreset,   28, 31, u8 as SoftReset, softreset_raw; /// Soft Reset
     }
     0x36, 0x04, 4, RW, PMSC_CTRL1(pmsc_ctrl1) { /// PMSC Control Register 1
         arx2init,   1,  1, u8; /// Automatic transition from receive to init
@@ -1047,116 +1360,60 @@ impl_register! {
 
 /// Transmit Data Buffer
 ///
-/// Currently only the first 127 bytes of the buffer are supported, which is
-/// enough to support standard Standard IEEE 802.15.4 UWB frames.
+/// The buffer is 1024 bytes long, which is enough for the longest frames the
+/// DW1000 supports in its non-standard long frame mode (see user manual,
+/// section 3.4). It can only be written partially, using
+/// [`RegAccessor::write_at`], so only the part that's actually needed is
+/// transferred.
+///
+/// [`RegAccessor::write_at`]: struct.RegAccessor.html#method.write_at
 #[allow(non_camel_case_types)]
 pub struct TX_BUFFER;
 
 impl Register for TX_BUFFER {
     const ID:     u8    = 0x09;
     const SUB_ID: u16   = 0x00;
-    const LEN:    usize = 127;
+    const LEN:    usize = 1024;
 }
 
-impl Writable for TX_BUFFER {
-    type Write = tx_buffer::W;
-
-    fn write() -> Self::Write {
-        tx_buffer::W([0; 127 + 1])
-    }
-
-    fn buffer(w: &mut Self::Write) -> &mut [u8] {
-        &mut w.0
-    }
-}
+impl WritableBuffer for TX_BUFFER {}
 
 impl<SPI, CS> DW1000<SPI, CS> {
     /// Transmit Data Buffer
-    pub fn tx_buffer(&mut self) -> RegAccessor<TX_BUFFER, SPI, CS> {
+    pub fn tx_buffer(&mut self) -> RegAccessor<'_, TX_BUFFER, SPI, CS> {
         RegAccessor(self, PhantomData)
     }
 }
 
 
-/// Transmit Data Buffer
-pub mod tx_buffer {
-    /// Used to write to the register
-    pub struct W(pub(crate) [u8; 127 + 1]);
-
-    impl W {
-        /// Provides write access to the buffer contents
-        pub fn data(&mut self) -> &mut [u8] {
-            &mut self.0[1..]
-        }
-    }
-}
-
-
 /// Receive Data Buffer
 ///
-/// Currently only the first 127 bytes of the buffer are supported, which is
-/// enough to support standard Standard IEEE 802.15.4 UWB frames.
+/// The buffer is 1024 bytes long, which is enough for the longest frames the
+/// DW1000 supports in its non-standard long frame mode (see user manual,
+/// section 3.4). It can only be read partially, using
+/// [`RegAccessor::read_at`], so only the part that's actually needed is
+/// transferred.
+///
+/// [`RegAccessor::read_at`]: struct.RegAccessor.html#method.read_at
 #[allow(non_camel_case_types)]
 pub struct RX_BUFFER;
 
 impl Register for RX_BUFFER {
     const ID:     u8    = 0x11;
     const SUB_ID: u16   = 0x00;
-    const LEN:    usize = 127;
+    const LEN:    usize = 1024;
 }
 
-impl Readable for RX_BUFFER {
-    type Read = rx_buffer::R;
-
-    fn read() -> Self::Read {
-        rx_buffer::R([0; 127 + 1])
-    }
-
-    fn buffer(w: &mut Self::Read) -> &mut [u8] {
-        &mut w.0
-    }
-}
+impl ReadableBuffer for RX_BUFFER {}
 
 impl<SPI, CS> DW1000<SPI, CS> {
     /// Receive Data Buffer
-    pub fn rx_buffer(&mut self) -> RegAccessor<RX_BUFFER, SPI, CS> {
+    pub fn rx_buffer(&mut self) -> RegAccessor<'_, RX_BUFFER, SPI, CS> {
         RegAccessor(self, PhantomData)
     }
 }
 
 
-/// Receive Data Buffer
-pub mod rx_buffer {
-    use core::fmt;
-
-
-    const HEADER_LEN: usize = 1;
-    const LEN:        usize = 127;
-
-
-    /// Used to read from the register
-    pub struct R(pub(crate) [u8; HEADER_LEN + LEN]);
-
-    impl R {
-        /// Provides read access to the buffer contents
-        pub fn data(&self) -> &[u8] {
-            &self.0[HEADER_LEN .. HEADER_LEN + LEN]
-        }
-    }
-
-    impl fmt::Debug for R {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "0x")?;
-            for i in (0 .. LEN).rev() {
-                write!(f, "{:02x}", self.0[HEADER_LEN + i])?;
-            }
-
-            Ok(())
-        }
-    }
-}
-
-
 /// Internal trait used by `impl_registers!`
 trait FromBytes {
     fn from_bytes(bytes: &[u8]) -> Self;
@@ -1211,3 +1468,187 @@ impl_bytes! {
     u32,
     u64,
 }
+
+
+/// Implemented by enums that represent the values of a register field
+///
+/// This is a mostly internal trait that should not be implemented or used
+/// directly by users of this crate. It is exposed through the public API
+/// though, so it can't be made private.
+pub trait FieldEnum: Sized {
+    /// The raw type of the field
+    type Raw;
+
+    /// Converts a raw field value into the enum
+    ///
+    /// Returns `None`, if the value doesn't correspond to any variant.
+    fn from_raw(raw: Self::Raw) -> Option<Self>;
+
+    /// Converts the enum into a raw field value
+    fn to_raw(self) -> Self::Raw;
+}
+
+/// Internal macro used to implement `FieldEnum`
+///
+/// The enums must be `Copy` and have explicit discriminants that match the
+/// field values.
+macro_rules! impl_field_enum {
+    ($($ty:ty: $raw:ty { $($variant:ident,)* })*) => {
+        $(
+            impl FieldEnum for $ty {
+                type Raw = $raw;
+
+                fn from_raw(raw: Self::Raw) -> Option<Self> {
+                    $(
+                        if raw == <$ty>::$variant as $raw {
+                            return Some(<$ty>::$variant);
+                        }
+                    )*
+
+                    None
+                }
+
+                fn to_raw(self) -> Self::Raw {
+                    self as $raw
+                }
+            }
+        )*
+    }
+}
+
+impl_field_enum! {
+    SysClks: u8 {
+        Auto,
+        Xti,
+        Pll,
+    }
+    PhrMode: u8 {
+        Standard,
+        Long,
+    }
+    BitRate: u8 {
+        Kbps110,
+        Kbps850,
+        Kbps6800,
+    }
+    PulseRepetitionFrequency: u8 {
+        Mhz16,
+        Mhz64,
+    }
+    PreambleLength: u8 {
+        Symbols64,
+        Symbols128,
+        Symbols256,
+        Symbols512,
+        Symbols1024,
+        Symbols1536,
+        Symbols2048,
+        Symbols4096,
+    }
+    UwbChannel: u8 {
+        Channel1,
+        Channel2,
+        Channel3,
+        Channel4,
+        Channel5,
+        Channel7,
+    }
+    SoftReset: u8 {
+        All,
+        Receiver,
+        Clear,
+    }
+}
+
+
+/// Clock selection (PMSC_CTRL0.SYSCLKS, RXCLKS and TXCLKS)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SysClks {
+    /// Automatic clock selection
+    Auto = 0b00,
+
+    /// Force the 19.2 MHz XTI clock
+    Xti = 0b01,
+
+    /// Force the 125 MHz PLL clock
+    Pll = 0b10,
+}
+
+/// Soft reset (PMSC_CTRL0.SOFTRESET)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SoftReset {
+    /// Reset everything, except for the host interface
+    All = 0b0000,
+
+    /// Reset the receiver
+    Receiver = 0b1110,
+
+    /// Clear the reset
+    Clear = 0b1111,
+}
+
+/// PHR mode (SYS_CFG.PHR_MODE)
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PhrMode {
+    /// Standard frames of up to 127 bytes
+    Standard = 0b00,
+
+    /// Proprietary long frames of up to 1023 bytes
+    Long = 0b11,
+}
+
+
+// The preamble length is split across TXPSR and PE. `PreambleLength` has
+// TXPSR in its upper two bits, the register has it in the lower ones, so it
+// can't be declared as a single field.
+
+impl tx_fctrl::R {
+    /// TX Preamble Length, as selected by TXPSR and PE
+    pub fn preamble_length(&self) -> Option<PreambleLength> {
+        PreambleLength::from_raw(self.txpsr() << 2 | self.pe())
+    }
+}
+
+impl tx_fctrl::W {
+    /// TX Preamble Length, as selected by TXPSR and PE
+    pub fn preamble_length(&mut self, value: PreambleLength) -> &mut Self {
+        let raw = value.to_raw();
+        self
+            .txpsr(raw >> 2)
+            .pe(raw & 0b11)
+    }
+}
+
+
+/// Accumulator CIR memory
+///
+/// Contains the channel impulse response (CIR) of the last received frame, as
+/// complex samples. Each sample consists of a 16-bit real part, followed by a
+/// 16-bit imaginary part.
+///
+/// This register is too large to be read at once. Use
+/// [`RegAccessor::read_at`] to read parts of it. Reading it only works, if its
+/// clocks are enabled (FACE and AMCE in PMSC_CTRL0).
+///
+/// [`RegAccessor::read_at`]: struct.RegAccessor.html#method.read_at
+#[allow(non_camel_case_types)]
+pub struct ACC_MEM;
+
+impl Register for ACC_MEM {
+    const ID:     u8    = 0x25;
+    const SUB_ID: u16   = 0x00;
+    const LEN:    usize = 4064;
+}
+
+impl<SPI, CS> DW1000<SPI, CS> {
+    /// Accumulator CIR memory
+    pub fn acc_mem(&mut self) -> RegAccessor<'_, ACC_MEM, SPI, CS> {
+        RegAccessor(self, PhantomData)
+    }
+}
+
+impl ReadableBuffer for ACC_MEM {
+    // When reading from this register, the DW1000 sends a dummy byte before
+    // the actual data. See user manual, section 7.2.26.
+    const DUMMY_LEN: usize = 1;
+}
@@ -0,0 +1,419 @@
+//! Access to the DW1000's one-time programmable (OTP) memory
+//!
+//! The OTP memory holds calibration values that are programmed during
+//! production, like the EUI, the LDO tuning value and the SAR ADC calibration.
+//! Some of it is left for the user to program. The layout is described in the
+//! user manual, section 6.3.
+//!
+//! Get access to the OTP memory via [`DW1000::otp`]. Reading is always safe.
+//! Programming can't be undone, as bits can only ever be changed from 0 to 1,
+//! and requires an external programming voltage (VPP) to be applied. See
+//! [`Otp::write_word`] for details.
+//!
+//! [`DW1000::otp`]: ../hl/struct.DW1000.html#method.otp
+//! [`Otp::write_word`]: struct.Otp.html#method.write_word
+
+
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi,
+    },
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    ll,
+    Error,
+};
+
+
+/// The number of 32-bit words in OTP memory
+pub const WORDS: u16 = 0x800;
+
+/// Address of the 64-bit EUI (two words, lower word first)
+pub const EUI: u16 = 0x000;
+
+/// Address of the alternative 64-bit EUI (two words, lower word first)
+pub const ALTERNATIVE_EUI: u16 = 0x002;
+
+/// Address of the 40-bit LDOTUNE calibration value (two words)
+pub const LDOTUNE: u16 = 0x004;
+
+/// Address of the chip ID
+pub const CHIP_ID: u16 = 0x006;
+
+/// Address of the lot ID
+pub const LOT_ID: u16 = 0x007;
+
+/// Address of the voltage calibration values
+pub const VOLTAGE_CALIBRATION: u16 = 0x008;
+
+/// Address of the temperature calibration value
+pub const TEMPERATURE_CALIBRATION: u16 = 0x009;
+
+/// Address of the crystal trim value
+pub const XTAL_TRIM: u16 = 0x01E;
+
+/// How often OTP_STAT is polled before programming is considered failed
+const MAX_POLLS: usize = 1000;
+
+/// How often programming a word is attempted, before giving up
+const MAX_PROGRAMMING_ATTEMPTS: usize = 5;
+
+// Bits of the OTPMR field in OTP_CTRL
+const MR_AUX_UPDATE: u8 = 0b0001;
+const MR_MODE_SEL:   u8 = 0b0010;
+const MR_MRA_SEL:    u8 = 0b0100;
+const MR_MRB_SEL:    u8 = 0b1000;
+
+/// Values of the OTP mode registers (MR, MRA, MRB)
+///
+/// These values aren't documented in the user manual. They are taken from
+/// `_dwt_otpsetmrregs` in Decawave's driver.
+#[derive(Clone, Copy)]
+struct Mode {
+    mr:  u16,
+    mra: u16,
+    mrb: u16,
+}
+
+impl Mode {
+    const READ:    Self = Mode { mr: 0x0000, mra: 0x0000, mrb: 0x0000 };
+    const PROGRAM: Self = Mode { mr: 0x1024, mra: 0x9220, mrb: 0x000e };
+    const VERIFY:  Self = Mode { mr: 0x0000, mra: 0x0000, mrb: 0x0003 };
+}
+
+
+/// Provides access to the OTP memory
+///
+/// Created by [`DW1000::otp`].
+///
+/// [`DW1000::otp`]: ../hl/struct.DW1000.html#method.otp
+pub struct Otp<'r, SPI, CS> {
+    ll: &'r mut ll::DW1000<SPI, CS>,
+}
+
+impl<'r, SPI, CS> Otp<'r, SPI, CS>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    /// Create an instance that uses the given register-level interface
+    pub fn new(ll: &'r mut ll::DW1000<SPI, CS>) -> Self {
+        Otp { ll }
+    }
+
+    /// Reads a 32-bit word
+    ///
+    /// Returns [`Error::OtpAddressOutOfRange`], if `address` is outside of the
+    /// OTP memory. See user manual, section 6.3.3.
+    ///
+    /// [`Error::OtpAddressOutOfRange`]: ../hl/enum.Error.html#variant.OtpAddressOutOfRange
+    pub fn read_word(&mut self, address: u16) -> Result<u32, Error<SPI, CS>> {
+        if address >= WORDS {
+            return Err(Error::OtpAddressOutOfRange);
+        }
+
+        self.ll.otp_addr().write(|w| w.value(address))?;
+        self.ll.otp_ctrl().modify(|_, w|
+            w
+                .otprden(0b1)
+                .otpread(0b1)
+        )?;
+        while self.ll.otp_ctrl().read()?.otpread() == 0b1 {}
+
+        // OTPREAD clears itself, but OTPRDEN doesn't. Decawave's driver
+        // clears it after every read.
+        self.ll.otp_ctrl().modify(|_, w| w.otprden(0b0))?;
+
+        Ok(self.ll.otp_rdat().read()?.value())
+    }
+
+    /// Programs a 32-bit word
+    ///
+    /// Programming is permanent. A bit that has been programmed to 1 can never
+    /// be changed back to 0. If `value` would require that, this method
+    /// returns [`Error::OtpAlreadyProgrammed`] without touching the memory. If
+    /// the word already has the requested value, nothing is done.
+    ///
+    /// `vpp` must switch the programming voltage, as described in the DW1000
+    /// data sheet. It is driven high for the duration of the programming
+    /// procedure, and low afterwards, even if programming fails. If the DW1000
+    /// doesn't detect the programming voltage, or `vpp` fails to switch, this
+    /// method returns [`Error::OtpVpp`]. Errors from the programming procedure
+    /// take precedence over a failure to switch `vpp` off again.
+    ///
+    /// Programming follows the user manual, section 6.3.2, and Decawave's
+    /// driver: the system clock is switched to XTI, the OTP mode registers are
+    /// set up for programming, and the word is programmed, up to 5 times,
+    /// until it reads back correctly. It is then read again in verify mode.
+    /// If it doesn't have the requested value, this method returns
+    /// [`Error::OtpProgrammingFailed`]. `delay` is used to wait for the mode
+    /// registers to settle.
+    ///
+    /// [`Error::OtpAlreadyProgrammed`]: ../hl/enum.Error.html#variant.OtpAlreadyProgrammed
+    /// [`Error::OtpVpp`]: ../hl/enum.Error.html#variant.OtpVpp
+    /// [`Error::OtpProgrammingFailed`]: ../hl/enum.Error.html#variant.OtpProgrammingFailed
+    pub fn write_word<VPP, D>(&mut self,
+        address: u16,
+        value:   u32,
+        vpp:     &mut VPP,
+        delay:   &mut D,
+    )
+        -> Result<(), Error<SPI, CS>>
+        where
+            VPP: OutputPin,
+            D:   DelayUs<u16>,
+    {
+        let current = self.read_word(address)?;
+        if current == value {
+            return Ok(());
+        }
+        if current & !value != 0 {
+            return Err(Error::OtpAlreadyProgrammed);
+        }
+
+        vpp.set_high().map_err(|_| Error::OtpVpp)?;
+        let result = self.program(address, value, delay);
+
+        // Always try to switch off the programming voltage, but report the
+        // more relevant error, if programming failed too.
+        let vpp_result = vpp.set_low().map_err(|_| Error::OtpVpp);
+        result?;
+        vpp_result
+    }
+
+    /// Loads the LDOTUNE calibration value from OTP into the LDOTUNE register
+    ///
+    /// Uses the LDO_KICK bit in OTP_SF, as described in the user manual,
+    /// section 2.5.5.11. Does nothing, if the value hasn't been programmed.
+    pub fn load_ldotune(&mut self) -> Result<(), Error<SPI, CS>> {
+        if self.read_word(LDOTUNE)? != 0 {
+            self.ll.otp_sf().write(|w| w.ldo_kick(0b1))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the 64-bit EUI
+    pub fn eui(&mut self) -> Result<u64, Error<SPI, CS>> {
+        self.read_u64(EUI)
+    }
+
+    /// Reads the alternative 64-bit EUI
+    ///
+    /// This is left for the user to program.
+    pub fn alternative_eui(&mut self) -> Result<u64, Error<SPI, CS>> {
+        self.read_u64(ALTERNATIVE_EUI)
+    }
+
+    /// Reads the 40-bit LDOTUNE calibration value
+    ///
+    /// Returns `None`, if it hasn't been programmed.
+    pub fn ldotune(&mut self) -> Result<Option<u64>, Error<SPI, CS>> {
+        let low = self.read_word(LDOTUNE)?;
+        if low == 0 {
+            return Ok(None);
+        }
+        let high = self.read_word(LDOTUNE + 1)? & 0xff;
+
+        Ok(Some(low as u64 | (high as u64) << 32))
+    }
+
+    /// Reads the chip ID
+    pub fn chip_id(&mut self) -> Result<u32, Error<SPI, CS>> {
+        self.read_word(CHIP_ID)
+    }
+
+    /// Reads the lot ID
+    pub fn lot_id(&mut self) -> Result<u32, Error<SPI, CS>> {
+        self.read_word(LOT_ID)
+    }
+
+    /// Reads the SAR ADC voltage calibration values
+    ///
+    /// These are the readings at 3.3 V and 3.7 V.
+    pub fn voltage_calibration(&mut self)
+        -> Result<VoltageCalibration, Error<SPI, CS>>
+    {
+        let word = self.read_word(VOLTAGE_CALIBRATION)?;
+
+        Ok(VoltageCalibration {
+            at_3_3_v: word as u8,
+            at_3_7_v: (word >> 8) as u8,
+        })
+    }
+
+    /// Reads the SAR ADC temperature calibration value
+    ///
+    /// This is the reading at 23 °C.
+    pub fn temperature_calibration(&mut self) -> Result<u8, Error<SPI, CS>> {
+        Ok(self.read_word(TEMPERATURE_CALIBRATION)? as u8)
+    }
+
+    /// Reads the crystal trim value
+    ///
+    /// Returns `None`, if it hasn't been programmed.
+    pub fn xtal_trim(&mut self) -> Result<Option<u8>, Error<SPI, CS>> {
+        let trim = self.read_word(XTAL_TRIM)? as u8 & 0x1f;
+
+        if trim == 0 {
+            return Ok(None);
+        }
+        Ok(Some(trim))
+    }
+
+    fn read_u64(&mut self, address: u16) -> Result<u64, Error<SPI, CS>> {
+        let low  = self.read_word(address)?;
+        let high = self.read_word(address + 1)?;
+
+        Ok(low as u64 | (high as u64) << 32)
+    }
+
+    /// Programs and verifies a word, like `dwt_otpwriteandverify`
+    fn program<D>(&mut self, address: u16, value: u32, delay: &mut D)
+        -> Result<(), Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        if !self.poll(|otp_stat| otp_stat.otpvpok() == 0b1)? {
+            return Err(Error::OtpVpp);
+        }
+
+        // OTP access requires the system clock to run from the crystal.
+        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(ll::SysClks::Xti))?;
+
+        let result = self.program_and_verify(address, value, delay);
+
+        // Leave the mode registers in read mode and restore the system clock,
+        // even if programming failed.
+        self.set_mode(Mode::READ, delay)?;
+        self.ll.pmsc_ctrl0().modify(|_, w| w.sysclks(ll::SysClks::Auto))?;
+
+        result
+    }
+
+    fn program_and_verify<D>(&mut self,
+        address: u16,
+        value:   u32,
+        delay:   &mut D,
+    )
+        -> Result<(), Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        self.set_mode(Mode::PROGRAM, delay)?;
+
+        for _ in 0 .. MAX_PROGRAMMING_ATTEMPTS {
+            self.program_word(address, value, delay)?;
+            if self.read_word(address)? == value {
+                break;
+            }
+        }
+
+        self.set_mode(Mode::VERIFY, delay)?;
+        if self.read_word(address)? != value {
+            return Err(Error::OtpProgrammingFailed);
+        }
+
+        Ok(())
+    }
+
+    /// Runs the programming sequence from the user manual, section 6.3.2
+    ///
+    /// Requires the mode registers to be set up for programming.
+    fn program_word<D>(&mut self, address: u16, value: u32, delay: &mut D)
+        -> Result<(), Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        self.ll.otp_wdat().write(|w| w.value(value))?;
+        self.ll.otp_addr().write(|w| w.value(address))?;
+        self.ll.otp_ctrl().write(|w| w.otpprog(0b1))?;
+        self.ll.otp_ctrl().write(|w| w)?;
+
+        for _ in 0 .. MAX_POLLS {
+            if self.ll.otp_stat().read()?.otpprgd() == 0b1 {
+                return Ok(());
+            }
+            delay.delay_us(1000);
+        }
+
+        Err(Error::OtpProgrammingFailed)
+    }
+
+    /// Writes the OTP mode registers, like `_dwt_otpsetmrregs`
+    fn set_mode<D>(&mut self, mode: Mode, delay: &mut D)
+        -> Result<(), Error<SPI, CS>>
+        where D: DelayUs<u16>
+    {
+        self.ll.otp_ctrl().write(|w| w)?;
+        self.write_mode_register(MR_MRA_SEL, mode.mra)?;
+        self.write_mode_register(MR_MRB_SEL, mode.mrb)?;
+
+        // MR itself is written without the AUX update.
+        self.ll.otp_ctrl().write(|w| w.otpmr(MR_MODE_SEL))?;
+        self.ll.otp_wdat().write(|w| w.value(mode.mr as u32))?;
+        self.ll.otp_ctrl().write(|w| w.otpmr(MR_MODE_SEL).otpmrwr(0b1))?;
+        delay.delay_us(10_000);
+        self.ll.otp_ctrl().write(|w| w.otpmrwr(0b1))?;
+
+        // Confirm that the mode registers were written.
+        self.ll.otp_ctrl().write(|w| w.otprden(0b1))?;
+        self.ll.otp_ctrl().write(|w| w.otprden(0b1).otpmr(MR_MRA_SEL))?;
+        self.ll.otp_ctrl().write(|w| w.otprden(0b1).otpmr(MR_MRB_SEL))?;
+        for _ in 0 .. 10 {
+            delay.delay_us(10_000);
+        }
+        self.ll.otp_ctrl().write(|w| w.otprden(0b1))?;
+        self.ll.otp_ctrl().write(|w| w)?;
+        delay.delay_us(10_000);
+
+        Ok(())
+    }
+
+    /// Writes MRA or MRB, selected by `select`
+    fn write_mode_register(&mut self, select: u8, value: u16)
+        -> Result<(), Error<SPI, CS>>
+    {
+        self.ll.otp_ctrl().write(|w| w.otpmr(MR_MODE_SEL | select))?;
+        self.ll.otp_wdat().write(|w| w.value(value as u32))?;
+        self.ll.otp_ctrl().write(|w|
+            w.otpmr(MR_MODE_SEL | select).otpmrwr(0b1)
+        )?;
+        self.ll.otp_ctrl().write(|w| w.otpmr(select).otpmrwr(0b1))?;
+        self.ll.otp_ctrl().write(|w|
+            w.otpmr(select | MR_AUX_UPDATE).otpmrwr(0b1)
+        )?;
+        self.ll.otp_ctrl().write(|w| w.otpmr(select | MR_AUX_UPDATE))?;
+        self.ll.otp_ctrl().write(|w| w.otpmr(select))?;
+
+        Ok(())
+    }
+
+    /// Polls OTP_STAT until `done` returns `true`, or gives up
+    fn poll<F>(&mut self, done: F) -> Result<bool, Error<SPI, CS>>
+        where F: Fn(&ll::otp_stat::R) -> bool
+    {
+        for _ in 0 .. MAX_POLLS {
+            if done(&self.ll.otp_stat().read()?) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+
+/// The SAR ADC voltage calibration values
+///
+/// See [`Otp::voltage_calibration`].
+///
+/// [`Otp::voltage_calibration`]: struct.Otp.html#method.voltage_calibration
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VoltageCalibration {
+    /// The SAR ADC reading at 3.3 V
+    pub at_3_3_v: u8,
+
+    /// The SAR ADC reading at 3.7 V
+    pub at_3_7_v: u8,
+}
@@ -1,8 +1,11 @@
-//! Implementation of double-sided two-way ranging
+//! Implementation of two-way ranging
 //!
 //! This ranging technique is described in the DW1000 user manual, section 12.3.
-//! This module uses three messages for a range measurement, as described in
-//! section 12.3.2.
+//! This module primarily uses three messages for a range measurement, as
+//! described in section 12.3.2 (double-sided two-way ranging). It also
+//! supports single-sided two-way ranging with two messages, as described in
+//! section 12.3.1 (see [`Poll`], [`PollResponse`] and
+//! [`compute_distance_mm_single_sided`]).
 //!
 //! This module defines the messages required, and provides code for sending and
 //! decoding them. It is left to the user to tie all that together, by sending
@@ -31,12 +34,18 @@
 //!
 //! Please note that using the code in this module without further processing of
 //! the result will yield imprecise measurements. To improve the precision of
-//! those measurements, a range bias needs to be applied. Please refer to the
-//! user manual, and [this DWM1001 issue] for more information.
+//! those measurements, a range bias needs to be applied, which depends on the
+//! received signal level. [`correct_range_bias_mm`] does that, based on the
+//! correction curves from Decawave's application note APS011. Please refer to
+//! the user manual, and [this DWM1001 issue] for more information.
 //!
 //! [`Ping`]: struct.Ping.html
 //! [`Request`]: struct.Request.html
 //! [`Response`]: struct.Response.html
+//! [`Poll`]: struct.Poll.html
+//! [`PollResponse`]: struct.PollResponse.html
+//! [`compute_distance_mm_single_sided`]: fn.compute_distance_mm_single_sided.html
+//! [`correct_range_bias_mm`]: fn.correct_range_bias_mm.html
 //! [examples]: https://github.com/braun-robotics/rust-dwm1001/tree/master/examples
 //! [this DWM1001 issue]: https://github.com/braun-robotics/rust-dwm1001/issues/55
 
@@ -56,7 +65,10 @@ use ssmarshal;
 use crate::{hl, mac, time::{
     Duration,
     Instant,
-}, DW1000, Error, Ready, Sending, TxConfig};
+}, configs::{
+    PulseRepetitionFrequency,
+    UwbChannel,
+}, DW1000, Error, Ready, RxConfig, Sending, TxConfig};
 
 
 /// The transmission delay
@@ -166,6 +178,7 @@ impl<T> TxMessage<T> where T: Message {
     ///
     /// Serializes the message payload and uses [`DW1000::send`] internally to
     /// send it.
+    #[allow(clippy::extra_unused_lifetimes)] // part of the public API
     pub fn send<'r, SPI, CS>(&self, dw1000: DW1000<SPI, CS, Ready>)
         -> Result<DW1000<SPI, CS, Sending>, Error<SPI, CS>>
         where
@@ -198,6 +211,24 @@ impl<T> TxMessage<T> where T: Message {
 }
 
 
+/// Returns the time at which a message sent now will leave the DW1000
+///
+/// The DW1000 ignores the lower 9 bits of the delayed transmission time (see
+/// user manual, section 3.3). The time stamps embedded in the messages must
+/// take that into account, or they won't match the actual transmission time.
+fn delayed_tx_time<SPI, CS>(dw1000: &mut DW1000<SPI, CS, Ready>)
+    -> Result<Instant, Error<SPI, CS>>
+    where
+        SPI: spi::Transfer<u8> + spi::Write<u8>,
+        CS:  OutputPin,
+{
+    let tx_time = dw1000.sys_time()? + Duration::from_nanos(TX_DELAY);
+
+    // Can't panic. Clearing bits can't move the value out of range.
+    Ok(Instant::new(tx_time.value() & !0x1ff).unwrap())
+}
+
+
 /// Sent before a message's data to identify the message
 #[derive(Debug, Deserialize, Serialize)]
 #[repr(C)]
@@ -230,7 +261,7 @@ impl Ping {
             SPI: spi::Transfer<u8> + spi::Write<u8>,
             CS:  OutputPin,
     {
-        let tx_time = dw1000.sys_time()? + Duration::from_nanos(TX_DELAY);
+        let tx_time = delayed_tx_time(dw1000)?;
         let ping_tx_time = tx_time + dw1000.get_tx_antenna_delay()?;
 
         let payload = Ping {
@@ -286,7 +317,7 @@ impl Request {
             SPI: spi::Transfer<u8> + spi::Write<u8>,
             CS:  OutputPin,
     {
-        let tx_time = dw1000.sys_time()? + Duration::from_nanos(TX_DELAY);
+        let tx_time = delayed_tx_time(dw1000)?;
         let request_tx_time = tx_time + dw1000.get_tx_antenna_delay()?;
 
         let ping_reply_time = request_tx_time.duration_since(ping.rx_time);
@@ -350,7 +381,7 @@ impl Response {
             SPI: spi::Transfer<u8> + spi::Write<u8>,
             CS:  OutputPin,
     {
-        let tx_time = dw1000.sys_time()? + Duration::from_nanos(TX_DELAY);
+        let tx_time = delayed_tx_time(dw1000)?;
         let response_tx_time = tx_time + dw1000.get_tx_antenna_delay()?;
 
         let ping_round_trip_time =
@@ -379,60 +410,354 @@ impl Message for Response {
 }
 
 
+/// Single-sided ranging poll message
+///
+/// This message is sent to initiate a single-sided range measurement. The node
+/// receiving it replies with a [`PollResponse`]. Single-sided ranging requires
+/// only two messages, which makes it attractive for power-constrained nodes,
+/// but its precision depends on the correction of the clock offset between
+/// the nodes. See [`compute_distance_mm_single_sided`].
+///
+/// [`PollResponse`]: struct.PollResponse.html
+/// [`compute_distance_mm_single_sided`]: fn.compute_distance_mm_single_sided.html
+#[derive(Debug, Deserialize, Serialize)]
+#[repr(C)]
+pub struct Poll {
+    /// When the poll was sent, in local sender time
+    pub poll_tx_time: Instant,
+}
+
+impl Poll {
+    /// Creates a new poll message
+    ///
+    /// Only creates the message, but doesn't yet send it. Sets the transmission
+    /// time to 10 milliseconds in the future. Make sure to send the message
+    /// within that time frame, or the distance measurement will be negatively
+    /// affected.
+    pub fn new<SPI, CS>(
+        dw1000:    &mut DW1000<SPI, CS, Ready>,
+        recipient: mac::Address,
+    )
+        -> Result<TxMessage<Self>, Error<SPI, CS>>
+        where
+            SPI: spi::Transfer<u8> + spi::Write<u8>,
+            CS:  OutputPin,
+    {
+        let tx_time = delayed_tx_time(dw1000)?;
+        let poll_tx_time = tx_time + dw1000.get_tx_antenna_delay()?;
+
+        let payload = Poll {
+            poll_tx_time,
+        };
+
+        Ok(TxMessage {
+            recipient,
+            tx_time,
+            payload,
+        })
+    }
+}
+
+impl Message for Poll {
+    const PRELUDE:     Prelude = Prelude(b"RANGING POLL");
+    const PRELUDE_LEN: usize   = 12;
+}
+
+
+/// Single-sided ranging response message
+///
+/// This message is sent in reply to a [`Poll`]. It contains the responder's
+/// reply time, which the initiator needs to compute the distance.
+///
+/// [`Poll`]: struct.Poll.html
+#[derive(Debug, Deserialize, Serialize)]
+#[repr(C)]
+pub struct PollResponse {
+    /// When the poll was sent, in local time on the initiator
+    pub poll_tx_time: Instant,
+
+    /// The time between the poll being received and the reply being sent
+    pub poll_reply_time: Duration,
+}
+
+impl PollResponse {
+    /// Creates a new poll response message
+    ///
+    /// Only creates the message, but doesn't yet send it. Sets the transmission
+    /// time to 10 milliseconds in the future. Make sure to send the message
+    /// within that time frame, or the distance measurement will be negatively
+    /// affected.
+    pub fn new<SPI, CS>(
+        dw1000: &mut DW1000<SPI, CS, Ready>,
+        poll:   &RxMessage<Poll>,
+    )
+        -> Result<TxMessage<Self>, Error<SPI, CS>>
+        where
+            SPI: spi::Transfer<u8> + spi::Write<u8>,
+            CS:  OutputPin,
+    {
+        let tx_time = delayed_tx_time(dw1000)?;
+        let response_tx_time = tx_time + dw1000.get_tx_antenna_delay()?;
+
+        let payload = PollResponse {
+            poll_tx_time:    poll.payload.poll_tx_time,
+            poll_reply_time: response_tx_time.duration_since(poll.rx_time),
+        };
+
+        Ok(TxMessage {
+            recipient: poll.source,
+            tx_time,
+            payload,
+        })
+    }
+}
+
+impl Message for PollResponse {
+    const PRELUDE:     Prelude = Prelude(b"RANGING POLL RESPONSE");
+    const PRELUDE_LEN: usize   = 21;
+}
+
+
 /// Computes the distance to another node from a ranging response
+///
+/// Returns an error, if the time of flight computed by
+/// [`compute_time_of_flight`] is negative. This can happen at very short
+/// distances, due to measurement noise.
+///
+/// [`compute_time_of_flight`]: fn.compute_time_of_flight.html
 pub fn compute_distance_mm(response: &RxMessage<Response>)
     -> Result<u64, ComputeDistanceError>
 {
-    // To keep variable names to a reasonable length, this function uses `rt` as
-    // a short-hand for "reply time" and `rtt` and a short-hand for "round-trip
-    // time".
-
-    let ping_rt = response.payload.ping_reply_time.value();
-    let ping_rtt = response.payload.ping_round_trip_time.value();
-    let request_rt = response.payload.request_reply_time.value();
-    let request_rtt = response.rx_time
-        .duration_since(response.payload.request_tx_time)
-        .value();
-
-    // Compute time of flight according to the formula given in the DW1000 user
-    // manual, section 12.3.2.
-    let rtt_product = ping_rtt.checked_mul(request_rtt)
-        .ok_or(ComputeDistanceError::RoundTripTimesTooLarge)?;
-    let rt_product = ping_rt.checked_mul(request_rt)
-        .ok_or(ComputeDistanceError::ReplyTimesTooLarge)?;
-    let rt_sum = ping_rt.checked_add(request_rt)
-        .ok_or(ComputeDistanceError::SumTooLarge)?;
-    let rtt_sum = ping_rtt.checked_add(request_rtt)
-        .ok_or(ComputeDistanceError::SumTooLarge)?;
-    let sum = rt_sum.checked_add(rtt_sum)
-        .ok_or(ComputeDistanceError::SumTooLarge)?;
-    let time_of_flight = (rtt_product - rt_product) / sum;
+    let request_round_trip_time = response.rx_time
+        .duration_since(response.payload.request_tx_time);
+
+    let time_of_flight = compute_time_of_flight(
+        response.payload.ping_round_trip_time,
+        response.payload.ping_reply_time,
+        request_round_trip_time,
+        response.payload.request_reply_time,
+    )?;
+    if time_of_flight < 0 {
+        return Err(ComputeDistanceError::NegativeTimeOfFlight);
+    }
+
+    Ok(time_of_flight_to_mm(time_of_flight as u64))
+}
+
+/// Computes the time of flight from the times measured by both nodes
+///
+/// Uses the formula for asymmetric double-sided two-way ranging given in the
+/// user manual, section 12.3.2:
+///
+/// ``` text
+/// ToF = (Ra * Rb - Da * Db) / (Ra + Rb + Da + Db)
+/// ```
+///
+/// `Ra`/`Rb` are the round-trip times measured by the initiator and responder,
+/// `Da`/`Db` are the corresponding reply times of the other node. The formula
+/// doesn't require both reply times to be equal, and works with any valid
+/// 40-bit time stamps, as it is computed using 128-bit arithmetic.
+///
+/// Returns the time of flight in DW1000 time units (1/64 ns). The result can
+/// be slightly negative at very short distances, due to measurement noise.
+/// Returns an error, if the magnitude of the time of flight exceeds half of
+/// either round-trip time, which is physically impossible.
+pub fn compute_time_of_flight(
+    ping_round_trip_time:    Duration,
+    ping_reply_time:         Duration,
+    request_round_trip_time: Duration,
+    request_reply_time:      Duration,
+)
+    -> Result<i64, ComputeDistanceError>
+{
+    // All values are 40-bit numbers, so products of two of them easily fit
+    // into an `i128`.
+    let ra = ping_round_trip_time.value() as i128;
+    let da = ping_reply_time.value() as i128;
+    let rb = request_round_trip_time.value() as i128;
+    let db = request_reply_time.value() as i128;
+
+    let sum = ra + rb + da + db;
+    if sum == 0 {
+        // All time stamps are identical.
+        return Ok(0);
+    }
+
+    let time_of_flight = (ra * rb - da * db) / sum;
+
+    if time_of_flight.abs() * 2 > ra.min(rb) {
+        return Err(ComputeDistanceError::ImplausibleTimeOfFlight);
+    }
+
+    // Can't truncate. We just made sure the value is smaller than a 40-bit
+    // number.
+    Ok(time_of_flight as i64)
+}
+
+/// Computes the distance to another node from a single-sided poll response
+///
+/// `carrier_integrator` is the value returned by
+/// [`DW1000::read_carrier_integrator`] after receiving the response.
+/// `rx_config` is the configuration the response was received with. Both are
+/// required to correct for the offset between the clocks of both nodes, as
+/// described in the user manual, section 12.3.1. Without that correction, a
+/// clock offset of a few ppm would result in errors of several meters, given
+/// the reply times used by this module.
+///
+/// [`DW1000::read_carrier_integrator`]: ../hl/struct.DW1000.html#method.read_carrier_integrator
+pub fn compute_distance_mm_single_sided(
+    response:           &RxMessage<PollResponse>,
+    carrier_integrator: i32,
+    rx_config:          RxConfig,
+)
+    -> Result<u64, ComputeDistanceError>
+{
+    let round_trip_time = response.rx_time
+        .duration_since(response.payload.poll_tx_time)
+        .value() as i128;
+    let reply_time = response.payload.poll_reply_time.value() as i128;
+
+    // The ratio between the remote and local clock frequencies is
+    // `-carrier_integrator * 998.4 MHz / 2^n / f_c` (see user manual, section
+    // 7.2.40.11). All carrier frequencies are multiples of `998.4 MHz / 2`, so
+    // we can do the whole computation in integers.
+    let carrier_multiple = rx_config.channel.get_carrier_multiple() as i128;
+    let shift = rx_config.bitrate.get_carrier_integrator_shift() - 1;
+
+    // Converts the reply time from remote into local clock ticks. The
+    // magnitude of all values involved is small enough that this can't
+    // overflow an `i128`.
+    let correction = reply_time * carrier_integrator as i128
+        / (carrier_multiple << shift);
+    let reply_time = reply_time + correction;
+
+    let time_of_flight = (round_trip_time - reply_time) / 2;
+    if time_of_flight < 0 {
+        return Err(ComputeDistanceError::NegativeTimeOfFlight);
+    }
 
+    Ok(time_of_flight_to_mm(time_of_flight as u64))
+}
+
+/// Converts a time of flight in DW1000 time units into a distance in mm
+fn time_of_flight_to_mm(time_of_flight: u64) -> u64 {
     // Nominally, all time units are based on a 64 Ghz clock, meaning each time
     // unit is 1/64 ns.
 
-    const SPEED_OF_LIGHT: u64 = 299_792_458; // m/s or nm/ns
+    const SPEED_OF_LIGHT: u128 = 299_792_458; // m/s or nm/ns
 
-    let distance_nm_times_64 = SPEED_OF_LIGHT.checked_mul(time_of_flight)
-        .ok_or(ComputeDistanceError::TimeOfFlightTooLarge)?;
+    // The time of flight is at most a 40-bit number, so this can't overflow.
+    let distance_nm_times_64 = SPEED_OF_LIGHT * time_of_flight as u128;
     let distance_mm          = distance_nm_times_64 / 64 / 1_000_000;
 
-    Ok(distance_mm)
+    distance_mm as u64
 }
 
 
-/// Returned from [`compute_distance_mm`] in case of an error
-#[derive(Debug)]
-pub enum ComputeDistanceError {
-    /// Reply times are too large to be multiplied
-    ReplyTimesTooLarge,
+/// Corrects a measured distance for range bias
+///
+/// The distances computed by this module are biased, depending on the level of
+/// the received signal. `rx_power_dbm` is the estimated receive power of the
+/// ranging messages, `channel` and `prf` are the channel and pulse repetition
+/// frequency they were sent with. See [`range_bias_mm`] for details.
+///
+/// Distances are never corrected to below zero.
+///
+/// [`range_bias_mm`]: fn.range_bias_mm.html
+pub fn correct_range_bias_mm(
+    distance_mm:  u64,
+    rx_power_dbm: f32,
+    channel:      UwbChannel,
+    prf:          PulseRepetitionFrequency,
+)
+    -> u64
+{
+    let bias_mm   = range_bias_mm(rx_power_dbm, channel, prf) as i64;
+    let corrected = distance_mm as i64 - bias_mm;
+
+    if corrected < 0 { 0 } else { corrected as u64 }
+}
 
-    /// Round-trip times are too large to be multiplied
-    RoundTripTimesTooLarge,
+/// Returns the range bias for a given receive power
+///
+/// The range bias is the difference between the measured and the actual
+/// distance, in mm. It is interpolated from the correction curves in Decawave's
+/// application note APS011 ("Sources of error in DW1000 based two-way ranging
+/// schemes"), which cover receive powers from -61 dBm to -95 dBm. Receive
+/// powers outside of that range use the nearest value.
+///
+/// Channels 4 and 7 have a bandwidth of 900 MHz, all other channels of
+/// 500 MHz. Both have different curves.
+pub fn range_bias_mm(
+    rx_power_dbm: f32,
+    channel:      UwbChannel,
+    prf:          PulseRepetitionFrequency,
+)
+    -> i32
+{
+    let table = match (channel, prf) {
+        (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz16) |
+        (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz16) =>
+            &RANGE_BIAS_900_MHZ_16_MHZ_PRF,
+        (UwbChannel::Channel4, PulseRepetitionFrequency::Mhz64) |
+        (UwbChannel::Channel7, PulseRepetitionFrequency::Mhz64) =>
+            &RANGE_BIAS_900_MHZ_64_MHZ_PRF,
+        (_, PulseRepetitionFrequency::Mhz16) =>
+            &RANGE_BIAS_500_MHZ_16_MHZ_PRF,
+        (_, PulseRepetitionFrequency::Mhz64) =>
+            &RANGE_BIAS_500_MHZ_64_MHZ_PRF,
+    };
+
+    // Table entries are 2 dB apart, starting at -61 dBm.
+    let last     = table.len() - 1;
+    let position = ((-61.0 - rx_power_dbm) / 2.0).clamp(0.0, last as f32);
+    let index    = position as usize;
+    let fraction = position - index as f32;
+
+    let low  = table[index] as f32;
+    let high = table[(index + 1).min(last)] as f32;
+
+    (low + (high - low) * fraction) as i32
+}
 
-    /// The sum computed as part of the algorithm is too large
-    SumTooLarge,
+/// Range bias in mm for 500 MHz channels at 16 MHz PRF (-61 dBm to -95 dBm)
+const RANGE_BIAS_500_MHZ_16_MHZ_PRF: [i16; 18] = [
+    -198, -187, -179, -163, -143, -127, -109, -84, -59,
+    -31, 0, 36, 65, 84, 97, 106, 110, 112,
+];
+
+/// Range bias in mm for 500 MHz channels at 64 MHz PRF (-61 dBm to -95 dBm)
+const RANGE_BIAS_500_MHZ_64_MHZ_PRF: [i16; 18] = [
+    -110, -105, -100, -93, -82, -69, -51, -27, 0,
+    21, 35, 42, 49, 62, 71, 76, 81, 86,
+];
+
+/// Range bias in mm for 900 MHz channels at 16 MHz PRF (-61 dBm to -95 dBm)
+const RANGE_BIAS_900_MHZ_16_MHZ_PRF: [i16; 18] = [
+    -274, -244, -210, -176, -138, -94, -50, 0, 42,
+    96, 158, 210, 254, 294, 320, 338, 356, 394,
+];
+
+/// Range bias in mm for 900 MHz channels at 64 MHz PRF (-61 dBm to -95 dBm)
+const RANGE_BIAS_900_MHZ_64_MHZ_PRF: [i16; 18] = [
+    -294, -266, -234, -198, -150, -100, -58, 0, 48,
+    90, 126, 152, 174, 196, 232, 244, 264, 284,
+];
+
+
+/// Returned from the functions in this module in case of an error
+#[derive(Debug, Eq, PartialEq)]
+pub enum ComputeDistanceError {
+    /// The computed time of flight is negative
+    ///
+    /// This can happen at very short distances, due to measurement noise, or
+    /// if the time stamps the computation is based on are inconsistent.
+    NegativeTimeOfFlight,
 
-    /// The time of flight is so large, the distance calculation would overflow
-    TimeOfFlightTooLarge,
+    /// The computed time of flight is physically impossible
+    ///
+    /// The time of flight can't be longer than half of either round-trip time.
+    /// This indicates that the time stamps the computation is based on are
+    /// inconsistent.
+    ImplausibleTimeOfFlight,
 }
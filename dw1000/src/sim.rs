@@ -0,0 +1,2140 @@
+//! Simulated DW1000 for host-side testing
+//!
+//! This module provides a software model of the DW1000 that implements the
+//! `embedded-hal` traits the driver requires. It can be passed to
+//! [`DW1000::new`] like a real SPI peripheral and chip select pin, which makes
+//! it possible to test code built on top of this driver without any hardware.
+//!
+//! The model decodes the SPI header format used by the [register-level
+//! interface] and keeps a register file that mirrors the layout described in
+//! the user manual. It models the parts of the DW1000's behavior that the
+//! driver relies on:
+//! - Read-only registers ignore writes, write-only registers read as zero.
+//! - Bits in SYS_STATUS are cleared by writing 1 to them.
+//! - The command bits in SYS_CTRL, OTP_CTRL and EVC_CTRL clear themselves once
+//!   the command has been executed.
+//! - Frames written to TX_BUFFER are transmitted on TXSTRT, both immediately
+//!   and delayed, and time-stamped accordingly.
+//! - Frames can be delivered to the receiver, which fills RX_BUFFER, RX_FINFO
+//!   and RX_TIME, and applies frame filtering. Rejected frames set AFFREJ,
+//!   and the receiver stays enabled.
+//! - RXENAB enables the receiver, both immediately and delayed.
+//! - Frames that request an acknowledgement are acknowledged automatically,
+//!   if AUTOACK is enabled. WAIT4RESP enables the receiver after the
+//!   transmission. The frame wait timeout (RX_FWTO) and the preamble
+//!   detection timeout (DRX_PRETOC) disable it again.
+//! - Received frames leave a channel impulse response in ACC_MEM, consisting of
+//!   a single path. It can only be read while the accumulator clocks are
+//!   enabled, and reads start with a dummy byte.
+//! - In double-buffered mode (DIS_DRXB cleared), frames are received into two
+//!   sets of RX_FINFO, RX_BUFFER, RX_FQUAL and RX_TIME. The receiver stays on
+//!   until both are full, and HRBPT hands the host side buffer back.
+//! - In SNIFF mode (RX_SNIFF and PLL2_SEQ_EN), the receiver only notices
+//!   frames whose preamble spans a complete listening phase.
+//! - Received frames are only time-stamped (LDEDONE), if the LDE microcode has
+//!   been loaded (LDELOAD).
+//! - Saving to the AON memory (AON_CTRL) puts the chip to sleep, if SLEEP_EN
+//!   is set. A sleeping chip ignores SPI traffic, and wakes up when chip
+//!   select or the WAKEUP pin is held for 500 µs. It restores its
+//!   configuration, if ONW_LDC is set, but loses the LDE microcode. The sleep
+//!   counter is not modelled.
+//! - OTP memory can be read (OTPREAD) and programmed (OTPPROG). Programming
+//!   only works while the programming voltage is applied through
+//!   [`Chip::vpp_pin`] and the OTP mode registers (written through OTPMRWR)
+//!   hold the programming values, and can only set bits. LDO_KICK in OTP_SF
+//!   loads LDOTUNE from OTP.
+//! - The SAR ADC (TC_SARC) measures the temperature and voltage set with
+//!   [`Chip::set_temperature`] and [`Chip::set_voltage`], according to the
+//!   calibration values in OTP memory.
+//! - Corrupted frames can be delivered to the receiver, which reports an FCS
+//!   error. RXAUTR keeps the receiver enabled afterwards.
+//! - Frames of up to 1023 bytes can be sent and received in long frame mode
+//!   (PHR_MODE). Outside of it, the length extension (TFLE) isn't sent, and
+//!   the receiver reports a PHY header error for long frames.
+//!
+//! Multiple simulated chips can be connected through an [`Ether`]. A frame
+//! transmitted by one of them is received by all others whose receiver is
+//! enabled, with time stamps that reflect the distance between the nodes,
+//! their clock offsets and their antenna delays (see [`Node`]). This allows
+//! for end-to-end tests of the [`ranging`] module with a known ground truth.
+//!
+//! Time only passes while the driver talks to the chip (every byte transferred
+//! over SPI takes a microsecond), or when the test explicitly calls
+//! [`Chip::advance`]. All chips connected to an ether share the same notion of
+//! time, so time spent talking to one chip also passes for all the others.
+//! This keeps tests deterministic.
+//!
+//! This module requires the standard library and is only available, if the
+//! `sim` feature is enabled.
+//!
+//! # Example
+//!
+//! ``` rust
+//! use dw1000::{
+//!     sim,
+//!     DW1000,
+//!     RxConfig,
+//! };
+//!
+//! let chip   = sim::Chip::new();
+//! let dw1000 = DW1000::new(chip.spi(), chip.chip_select())
+//!     .init()
+//!     .unwrap();
+//!
+//! let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+//! assert!(receiving.wait(&mut [0; 128]).is_err());
+//! ```
+//!
+//! Two nodes, 10 meters apart:
+//!
+//! ``` rust
+//! use dw1000::{
+//!     mac,
+//!     sim,
+//!     DW1000,
+//!     RxConfig,
+//!     TxConfig,
+//! };
+//!
+//! let ether = sim::Ether::new();
+//! let a = ether.add_node(sim::Node::default());
+//! let b = ether.add_node(sim::Node {
+//!     position: [10.0, 0.0, 0.0],
+//!     .. sim::Node::default()
+//! });
+//!
+//! let a = DW1000::new(a.spi(), a.chip_select()).init().unwrap();
+//! let b = DW1000::new(b.spi(), b.chip_select()).init().unwrap();
+//!
+//! let mut receiving = b.receive(RxConfig::default()).unwrap();
+//!
+//! let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+//! let mut sending = a.send(b"ping", destination, None, TxConfig::default())
+//!     .unwrap();
+//! nb::block!(sending.wait()).unwrap();
+//!
+//! let mut buffer = [0; 128];
+//! let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+//! assert_eq!(message.frame.payload, b"ping");
+//! ```
+//!
+//! [`DW1000::new`]: ../hl/struct.DW1000.html#method.new
+//! [register-level interface]: ../ll/index.html
+//! [`ranging`]: ../ranging/index.html
+
+
+use core::convert::Infallible;
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    rc::Rc,
+    vec,
+    vec::Vec,
+};
+
+use embedded_hal::{
+    blocking::{
+        delay::DelayUs,
+        spi,
+    },
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    configs::{
+        BitRate,
+        PreambleLength,
+        PulseRepetitionFrequency,
+    },
+    ll::{
+        self,
+        Readable,
+        Register,
+        Writable,
+    },
+    time::{
+        Duration,
+        Instant,
+        TIME_MAX,
+    },
+};
+
+
+/// The number of time units in a nanosecond
+///
+/// The driver treats DW1000 time units as 1/64 ns (see [`Duration::from_nanos`]),
+/// and so does the simulation.
+const TICKS_PER_NS: u64 = 64;
+
+/// How long it takes to transfer a single byte over SPI
+const SPI_BYTE_TIME: u64 = 1_000 * TICKS_PER_NS;
+
+/// The speed of light, in meters per second
+const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+
+/// The transmit power of all simulated nodes, in dBm
+///
+/// This corresponds to the regulatory limit of -41.3 dBm/MHz over a bandwidth
+/// of 500 MHz.
+const TX_POWER_DBM: f64 = -14.3;
+
+/// The receive power of frames delivered using [`Chip::deliver`], in dBm
+const DELIVERY_POWER_DBM: f64 = -80.0;
+
+/// The time between TXSTRT and the start of the preamble
+const TX_POWER_UP_TIME: u64 = 5_000 * TICKS_PER_NS;
+
+/// How long chip select or the WAKEUP pin must be held to wake the chip up
+const WAKE_UP_HOLD_TIME: u64 = 500_000 * TICKS_PER_NS;
+
+/// The unit of SNIFF_OFFT (128/125 µs)
+const SNIFF_OFF_TIME: u64 = 1_024 * TICKS_PER_NS;
+
+/// The unit of RX_FWTO and W4R_TIM (512 / 499.2 MHz, roughly 1 µs)
+const UWB_MICROSECOND: u64 = 1 << 16;
+
+/// The duration of a preamble symbol, roughly
+const SYMBOL_TIME: u64 = 1_000 * TICKS_PER_NS;
+
+/// The lengths of the register files, indexed by register ID
+///
+/// Taken from the user manual, section 7.1. Reserved register files have a
+/// length of zero. The manual lists OTP_IF (0x2D) as 18 bytes long, but
+/// OTP_SF is at sub-address 0x12, so it's really 19.
+const FILE_LENGTHS: [usize; 64] = [
+    4, 8, 0, 4, 4, 0, 5, 0, 5, 1024, 5, 0, 2, 4, 4, 5, // 0x00 - 0x0F
+    4, 1024, 8, 4, 5, 14, 0, 10, 2, 5, 4, 0, 0, 4, 4, 4, // 0x10 - 0x1F
+    0, 41, 0, 33, 12, 4064, 44, 46, 58, 0, 52, 21, 12, 19, 0x2808, 41, // 0x20 - 0x2F
+    0, 0, 0, 0, 0, 0, 48, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 0x30 - 0x3F
+];
+
+/// The number of 32-bit words in OTP memory
+const OTP_WORDS: usize = 0x800;
+
+/// The values of the OTP mode registers (MR, MRA, MRB) for programming
+const OTP_MODE_PROGRAM: [u16; 3] = [0x1024, 0x9220, 0x000e];
+
+// Register files that are accessed directly, as they aren't declared using
+// `impl_register!`.
+const TX_BUFFER: u8 = 0x09;
+const RX_BUFFER: u8 = 0x11;
+const ACC_MEM:   u8 = 0x25;
+
+/// The position of the first path in the simulated channel impulse response
+const FP_INDEX: usize = 750;
+
+/// The register files that exist once per buffer in double-buffered mode
+///
+/// These are RX_FINFO, RX_BUFFER, RX_FQUAL, and RX_TIME, as listed in the user
+/// manual, section 4.3.
+const RX_SET_FILES: [u8; 4] = [0x10, RX_BUFFER, 0x12, 0x15];
+
+/// The SYS_STATUS byte that exists once per buffer in double-buffered mode
+///
+/// This byte holds the frame status bits, RXPRD to RXFCE.
+const RX_SET_STATUS: usize = 1;
+
+
+/// A simulated DW1000
+///
+/// This is a handle to the simulated chip. It can be cloned cheaply, and all
+/// clones refer to the same chip. Use [`Chip::spi`] and [`Chip::chip_select`]
+/// to get the peripherals that can be passed to [`DW1000::new`].
+///
+/// A chip created with [`Chip::new`] stands on its own. Use
+/// [`Ether::add_node`] to create chips that can talk to each other.
+///
+/// [`DW1000::new`]: ../hl/struct.DW1000.html#method.new
+#[derive(Clone)]
+pub struct Chip {
+    state: Rc<RefCell<State>>,
+    ether: Option<Rc<RefCell<Medium>>>,
+}
+
+impl Chip {
+    /// Creates a new simulated DW1000 in its power-on state
+    pub fn new() -> Self {
+        let clock = Rc::new(Cell::new(0));
+
+        Chip {
+            state: Rc::new(RefCell::new(State::new(clock, Node::default()))),
+            ether: None,
+        }
+    }
+
+    /// Returns the SPI peripheral that is connected to this chip
+    pub fn spi(&self) -> Spi {
+        Spi(self.clone())
+    }
+
+    /// Returns the chip select pin that is connected to this chip
+    pub fn chip_select(&self) -> ChipSelect {
+        ChipSelect(self.clone())
+    }
+
+    /// Returns the pin that is connected to the chip's WAKEUP input
+    pub fn wakeup_pin(&self) -> WakeUpPin {
+        WakeUpPin(self.clone())
+    }
+
+    /// Returns the pin that switches the chip's OTP programming voltage
+    pub fn vpp_pin(&self) -> VppPin {
+        VppPin(self.clone())
+    }
+
+    /// Returns a delay implementation that lets time pass for this chip
+    ///
+    /// Delays work like [`Chip::advance`].
+    pub fn delay(&self) -> Delay {
+        Delay(self.clone())
+    }
+
+    /// Sets the chip temperature, in °C, as measured by the SAR ADC
+    ///
+    /// Defaults to 23 °C.
+    pub fn set_temperature(&self, temperature: f64) {
+        self.state.borrow_mut().temperature = temperature;
+    }
+
+    /// Sets the supply voltage, in V, as measured by the SAR ADC
+    ///
+    /// Defaults to 3.3 V.
+    pub fn set_voltage(&self, voltage: f64) {
+        self.state.borrow_mut().voltage = voltage;
+    }
+
+    /// Indicates whether the chip is in the SLEEP or DEEPSLEEP state
+    pub fn is_asleep(&self) -> bool {
+        self.state.borrow().asleep
+    }
+
+    /// Returns the current system time of the chip
+    pub fn sys_time(&self) -> Instant {
+        self.state.borrow().local_time()
+    }
+
+    /// Lets time pass without any SPI communication
+    ///
+    /// Any transmissions that finish within this time are completed. If the
+    /// chip is connected to an ether, the time passes for all other chips
+    /// too.
+    pub fn advance(&self, duration: Duration) {
+        {
+            let state = self.state.borrow();
+            state.clock.set(state.clock.get() + duration.value());
+        }
+        self.sync();
+    }
+
+    /// Writes a word to the chip's OTP memory
+    ///
+    /// This is meant to set up factory-programmed values before the test
+    /// starts. It bypasses the programming procedure of the real chip.
+    pub fn set_otp(&self, address: u16, value: u32) {
+        self.state.borrow_mut().otp[address as usize] = value;
+    }
+
+    /// Reads a register without affecting the simulation
+    ///
+    /// No time passes, and no side effects are triggered.
+    pub fn peek<R>(&self) -> R::Read
+        where R: Register + Readable
+    {
+        self.state.borrow().get::<R>()
+    }
+
+    /// Delivers a frame to the receiver, as if it arrived right now
+    ///
+    /// `data` is the frame without its FCS. The FCS is computed and appended,
+    /// just like a transmitting DW1000 would do. The frame is treated as if it
+    /// had been sent by a node with the same configuration as this one.
+    ///
+    /// Returns `true`, if the frame was received. Returns `false`, if the
+    /// receiver wasn't enabled, or the frame was rejected by frame filtering.
+    pub fn deliver(&self, data: &[u8]) -> bool {
+        self.sync();
+
+        let mut state = self.state.borrow_mut();
+        let rmarker = state.now();
+        let phy     = state.tx_phy();
+        state.receive(data, rmarker, rmarker, phy, 0, DELIVERY_POWER_DBM)
+    }
+
+    /// Delivers a frame that fails the FCS check to the receiver
+    ///
+    /// Sets the same status bits as a DW1000 that receives a corrupted frame.
+    /// The receiver is disabled afterwards, unless RXAUTR is set.
+    ///
+    /// Returns `true`, if the receiver was enabled.
+    pub fn deliver_corrupted(&self) -> bool {
+        self.sync();
+
+        let mut state = self.state.borrow_mut();
+        let now = state.now();
+        state.receive_corrupted(now)
+    }
+
+    /// Returns all frames transmitted since the last call to this method
+    pub fn take_transmitted(&self) -> Vec<Transmission> {
+        let mut state = self.state.borrow_mut();
+        state.transmitted.drain(..).collect()
+    }
+
+    /// Processes all events that happened up to the current time
+    ///
+    /// Must not be called while the chip's state is borrowed.
+    fn sync(&self) {
+        match &self.ether {
+            Some(ether) => ether.borrow_mut().update(),
+            None        => self.state.borrow_mut().update(),
+        }
+    }
+}
+
+impl Default for Chip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// A frame transmitted by the simulated DW1000
+#[derive(Clone, Debug)]
+pub struct Transmission {
+    /// The frame data, without the FCS
+    pub data: Vec<u8>,
+
+    /// The transmit time stamp, as reported in TX_TIME
+    pub tx_time: Instant,
+}
+
+
+/// A virtual radio medium that connects simulated DW1000s
+///
+/// Every frame transmitted by a chip connected to the ether reaches all other
+/// chips after a time of flight that corresponds to the distance between them.
+/// It is received by those chips whose receiver is enabled before the frame
+/// arrives, and which are configured for the same channel, PRF, preamble code
+/// and bit rate as the transmitter.
+///
+/// All chips share a single clock. Each chip's system time is derived from
+/// that clock, according to the clock offset and drift configured in its
+/// [`Node`].
+#[derive(Clone)]
+pub struct Ether(Rc<RefCell<Medium>>);
+
+impl Ether {
+    /// Creates an ether without any nodes
+    pub fn new() -> Self {
+        Ether(Rc::new(RefCell::new(Medium {
+            clock:     Rc::new(Cell::new(0)),
+            nodes:     Vec::new(),
+            in_flight: Vec::new(),
+        })))
+    }
+
+    /// Adds a node to the ether
+    ///
+    /// Returns the simulated chip of the new node, in its power-on state.
+    pub fn add_node(&self, node: Node) -> Chip {
+        let mut medium = self.0.borrow_mut();
+
+        let state = Rc::new(RefCell::new(State::new(medium.clock.clone(), node)));
+        state.borrow_mut().update_sys_time();
+        medium.nodes.push(state.clone());
+
+        Chip {
+            state,
+            ether: Some(self.0.clone()),
+        }
+    }
+
+    /// Lets time pass for all nodes, without any SPI communication
+    pub fn advance(&self, duration: Duration) {
+        let mut medium = self.0.borrow_mut();
+        medium.clock.set(medium.clock.get() + duration.value());
+        medium.update();
+    }
+}
+
+impl Default for Ether {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// The physical properties of a node connected to an [`Ether`]
+#[derive(Clone, Copy, Debug)]
+pub struct Node {
+    /// The position of the node, in meters
+    pub position: [f64; 3],
+
+    /// The node's system time at the start of the simulation
+    pub clock_offset: Duration,
+
+    /// How much faster the node's clock runs than the ether's, in ppm
+    ///
+    /// Negative values make the clock run slower.
+    pub clock_drift_ppm: f64,
+
+    /// The delay between the TX time stamp and the RMARKER leaving the antenna
+    ///
+    /// In DW1000 time units. This is a property of the hardware. The driver
+    /// compensates for it by configuring TX_ANTD to the same value.
+    pub tx_antenna_delay: u16,
+
+    /// The delay between the RMARKER arriving at the antenna and the RX time
+    /// stamp
+    ///
+    /// In DW1000 time units. This is a property of the hardware. The driver
+    /// compensates for it by configuring LDE_RXANTD to the same value.
+    pub rx_antenna_delay: u16,
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node {
+            position:         [0.0; 3],
+            clock_offset:     Duration::new(0).unwrap(),
+            clock_drift_ppm:  0.0,
+            tx_antenna_delay: 0,
+            rx_antenna_delay: 0,
+        }
+    }
+}
+
+
+/// The SPI peripheral connected to a simulated DW1000
+///
+/// Created by [`Chip::spi`].
+pub struct Spi(Chip);
+
+impl spi::Transfer<u8> for Spi {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8])
+        -> Result<&'w [u8], Self::Error>
+    {
+        let result = self.0.state.borrow_mut().transfer(words);
+        self.0.sync();
+
+        result?;
+        Ok(words)
+    }
+}
+
+impl spi::Write<u8> for Spi {
+    type Error = Error;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Self::Error> {
+        let result = self.0.state.borrow_mut().write(words);
+        self.0.sync();
+
+        result
+    }
+}
+
+
+/// The chip select pin connected to a simulated DW1000
+///
+/// Created by [`Chip::chip_select`].
+pub struct ChipSelect(Chip);
+
+impl OutputPin for ChipSelect {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.state.borrow_mut().select();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.state.borrow_mut().deselect();
+        self.0.sync();
+        Ok(())
+    }
+}
+
+
+/// The pin connected to the WAKEUP input of a simulated DW1000
+///
+/// Created by [`Chip::wakeup_pin`].
+pub struct WakeUpPin(Chip);
+
+impl OutputPin for WakeUpPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.state.borrow_mut().release_wakeup_pin();
+        self.0.sync();
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.0.state.borrow_mut();
+        state.wakeup_pin_since = Some(state.clock.get());
+        Ok(())
+    }
+}
+
+
+/// The pin that switches the OTP programming voltage of a simulated DW1000
+///
+/// Created by [`Chip::vpp_pin`].
+pub struct VppPin(Chip);
+
+impl OutputPin for VppPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.state.borrow_mut().set_vpp(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.state.borrow_mut().set_vpp(true);
+        Ok(())
+    }
+}
+
+
+/// A delay that lets time pass for a simulated DW1000
+///
+/// Created by [`Chip::delay`].
+pub struct Delay(Chip);
+
+impl DelayUs<u16> for Delay {
+    fn delay_us(&mut self, us: u16) {
+        self.0.advance(Duration::from_nanos(us as u32 * 1000));
+    }
+}
+
+impl DelayUs<u32> for Delay {
+    fn delay_us(&mut self, us: u32) {
+        self.0.advance(Duration::new(us as u64 * 1000 * TICKS_PER_NS).unwrap());
+    }
+}
+
+
+/// An error that can occur when communicating with the simulated DW1000
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    /// Data was transferred while chip select was not asserted
+    NotSelected,
+}
+
+
+/// The internal state of a simulated DW1000
+struct State {
+    /// The register files, indexed by register ID
+    files: Vec<Vec<u8>>,
+
+    /// OTP memory
+    otp: Vec<u32>,
+
+    /// The clock that drives the simulation, in DW1000 time units
+    ///
+    /// Shared by all chips connected to the same ether.
+    clock: Rc<Cell<u64>>,
+
+    /// The physical properties of the node this chip belongs to
+    node: Node,
+
+    /// The SPI transaction in progress, if chip select is asserted
+    transaction: Option<Transaction>,
+
+    /// Whether the event counters are enabled
+    counters_enabled: bool,
+
+    /// The current transmission, if any
+    tx: Option<Tx>,
+
+    /// When the receiver was enabled, if it is enabled
+    rx_since: Option<u64>,
+
+    /// The receive buffer that isn't visible to the host
+    ///
+    /// Only used in double-buffered mode. The other buffer lives in the
+    /// regular register files.
+    rx_back: RxSet,
+
+    /// Which receive buffers hold a frame the host hasn't released yet
+    rx_full: [bool; 2],
+
+    /// Whether the receiver is waiting for the host to release a buffer
+    rx_stalled: bool,
+
+    /// Whether the chip is in the SLEEP or DEEPSLEEP state
+    asleep: bool,
+
+    /// Whether the LDE microcode has been loaded since the last reset or sleep
+    lde_loaded: bool,
+
+    /// When chip select was asserted, if it is asserted
+    selected_since: Option<u64>,
+
+    /// When the WAKEUP pin was driven high, if it is high
+    wakeup_pin_since: Option<u64>,
+
+    /// Whether the OTP programming voltage is applied
+    vpp: bool,
+
+    /// The OTP mode registers (MR, MRA and MRB)
+    otp_mode: [u16; 3],
+
+    /// The chip temperature, in °C
+    temperature: f64,
+
+    /// The supply voltage, in V
+    voltage: f64,
+
+    /// Transmitted frames that haven't been collected yet
+    transmitted: Vec<Transmission>,
+}
+
+impl State {
+    fn new(clock: Rc<Cell<u64>>, node: Node) -> Self {
+        let mut state = State {
+            files:            Vec::new(),
+            otp:              vec![0; OTP_WORDS],
+            clock,
+            node,
+            transaction:      None,
+            counters_enabled: false,
+            tx:               None,
+            rx_since:         None,
+            rx_back:          RxSet::new(),
+            rx_full:          [false; 2],
+            rx_stalled:       false,
+            asleep:           false,
+            lde_loaded:       false,
+            selected_since:   None,
+            wakeup_pin_since: None,
+            vpp:              false,
+            otp_mode:         [0; 3],
+            temperature:      23.0,
+            voltage:          3.3,
+            transmitted:      Vec::new(),
+        };
+        state.reset();
+        state
+    }
+
+    /// Puts all registers into their reset state
+    ///
+    /// Reset values are taken from the user manual, section 7.2.
+    fn reset(&mut self) {
+        self.files = FILE_LENGTHS.iter().map(|&len| vec![0; len]).collect();
+        self.tx = None;
+        self.rx_since = None;
+        self.rx_back = RxSet::new();
+        self.rx_full = [false; 2];
+        self.rx_stalled = false;
+        self.asleep = false;
+        self.lde_loaded = false;
+        self.counters_enabled = false;
+        self.otp_mode = [0; 3];
+
+        self.store::<ll::DEV_ID>(0xDECA0130);
+        self.store::<ll::PANADR>(0xFFFFFFFF);
+        self.store::<ll::SYS_CFG>(0x00001200);
+        self.store::<ll::TX_FCTRL>(0x0015400C);
+        self.store::<ll::CHAN_CTRL>(0x00000055);
+        self.store::<ll::PMSC_CTRL0>(0xF0300200);
+        self.store::<ll::SYS_STATUS>(0x00000002); // CPLOCK
+
+        let vpp = self.vpp;
+        self.set_vpp(vpp);
+    }
+
+    /// Applies or removes the OTP programming voltage
+    fn set_vpp(&mut self, vpp: bool) {
+        self.vpp = vpp;
+        self.set::<ll::OTP_STAT, _>(|w| w.otpvpok(vpp as u8));
+    }
+
+    /// Returns the current system time, without wrapping it to 40 bits
+    fn now(&self) -> u64 {
+        self.to_local(self.clock.get())
+    }
+
+    /// Returns the current system time
+    fn local_time(&self) -> Instant {
+        // Can't panic, as the value is masked to 40 bits.
+        Instant::new(self.now() & TIME_MAX).unwrap()
+    }
+
+    /// Converts a time of the shared clock into the chip's system time
+    fn to_local(&self, global: u64) -> u64 {
+        let rate = 1.0 + self.node.clock_drift_ppm * 1e-6;
+        self.node.clock_offset.value() + (global as f64 * rate).round() as u64
+    }
+
+    /// Converts the chip's system time into a time of the shared clock
+    fn to_global(&self, local: u64) -> u64 {
+        let rate = 1.0 + self.node.clock_drift_ppm * 1e-6;
+        let since_start = local.saturating_sub(self.node.clock_offset.value());
+        (since_start as f64 / rate).round() as u64
+    }
+
+    fn select(&mut self) {
+        self.transaction = Some(Transaction::Header(Vec::new()));
+        self.selected_since = Some(self.clock.get());
+    }
+
+    fn deselect(&mut self) {
+        let selected_since = self.selected_since.take();
+
+        if self.asleep {
+            // Holding chip select long enough wakes the chip up. See user
+            // manual, section 2.4.3.
+            let held = selected_since
+                .map(|since| self.clock.get() - since >= WAKE_UP_HOLD_TIME)
+                .unwrap_or(false);
+            if held && self.get::<ll::AON_CFG0>().wake_spi() == 0b1 {
+                self.wake_up();
+            }
+
+            self.transaction = None;
+            return;
+        }
+
+        if let Some(Transaction::Data { write: true, id, start, offset }) =
+            self.transaction.take()
+        {
+            self.commit(id, start, offset);
+        }
+    }
+
+    fn transfer(&mut self, words: &mut [u8]) -> Result<(), Error> {
+        for word in words.iter_mut() {
+            *word = self.exchange(*word)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Error> {
+        for &word in words {
+            self.exchange(word)?;
+        }
+        Ok(())
+    }
+
+    /// Transfers a single byte over SPI
+    ///
+    /// Returns the byte that the DW1000 sends back.
+    fn exchange(&mut self, byte: u8) -> Result<u8, Error> {
+        let transaction = self.transaction.as_mut()
+            .ok_or(Error::NotSelected)?;
+
+        self.clock.set(self.clock.get() + SPI_BYTE_TIME);
+
+        // A sleeping chip doesn't respond.
+        if self.asleep {
+            return Ok(0);
+        }
+
+        match transaction {
+            Transaction::Header(header) => {
+                header.push(byte);
+                if let Some(next) = Transaction::parse(header) {
+                    *transaction = next;
+                }
+                Ok(0)
+            }
+            Transaction::Data { write, id, start, offset } => {
+                let (write, id, start, address) =
+                    (*write, *id, *start, *offset);
+                *offset += 1;
+
+                if write {
+                    self.write_byte(id, address, byte);
+                    Ok(0)
+                }
+                else if id == ACC_MEM {
+                    Ok(self.read_acc_mem(start, address))
+                }
+                else {
+                    Ok(self.read_byte(id, address))
+                }
+            }
+        }
+    }
+
+    fn read_byte(&self, id: u8, address: usize) -> u8 {
+        if id == TX_BUFFER {
+            // TX_BUFFER is write-only
+            return 0;
+        }
+
+        self.files[id as usize].get(address).cloned().unwrap_or(0)
+    }
+
+    /// Reads a byte from the accumulator memory
+    ///
+    /// The first byte of every read is a dummy byte (see user manual, section
+    /// 7.2.26). Without the accumulator clocks, the memory reads as zero.
+    fn read_acc_mem(&self, start: usize, address: usize) -> u8 {
+        let pmsc_ctrl0 = self.get::<ll::PMSC_CTRL0>();
+        if address == start
+            || pmsc_ctrl0.face() == 0b0
+            || pmsc_ctrl0.amce() == 0b0
+        {
+            return 0;
+        }
+
+        self.read_byte(ACC_MEM, address - 1)
+    }
+
+    fn write_byte(&mut self, id: u8, address: usize, byte: u8) {
+        if id == RX_BUFFER || id == ACC_MEM || address >= self.files[id as usize].len() {
+            return;
+        }
+
+        let read_only = ll::REGISTERS.iter().any(|reg| {
+            let start = reg.sub_id as usize;
+            reg.id == id
+                && !reg.writable
+                && start <= address && address < start + reg.len
+        });
+        if read_only {
+            return;
+        }
+
+        let target = &mut self.files[id as usize][address];
+        if id == ll::SYS_STATUS::ID {
+            // Status bits are cleared by writing 1. IRQS, HSRBP and ICRBP
+            // are read-only.
+            let read_only = match address {
+                0 => 0x01,
+                3 => 0xc0,
+                _ => 0x00,
+            };
+            *target &= !(byte & !read_only);
+        }
+        else {
+            *target = byte;
+        }
+    }
+
+    /// Handles the side effects of a completed write transaction
+    fn commit(&mut self, id: u8, start: usize, end: usize) {
+        let touched = |reg_id: u8, sub_id: u16, len: usize| {
+            let sub_id = sub_id as usize;
+            id == reg_id && start < sub_id + len && sub_id < end
+        };
+
+        if touched(ll::SYS_CTRL::ID, ll::SYS_CTRL::SUB_ID, ll::SYS_CTRL::LEN) {
+            self.sys_ctrl();
+        }
+        if touched(ll::OTP_CTRL::ID, ll::OTP_CTRL::SUB_ID, ll::OTP_CTRL::LEN) {
+            self.otp_ctrl();
+        }
+        if touched(ll::OTP_SF::ID, ll::OTP_SF::SUB_ID, ll::OTP_SF::LEN) {
+            self.otp_sf();
+        }
+        if touched(ll::EVC_CTRL::ID, ll::EVC_CTRL::SUB_ID, ll::EVC_CTRL::LEN) {
+            self.evc_ctrl();
+        }
+        if touched(ll::PMSC_CTRL0::ID, ll::PMSC_CTRL0::SUB_ID, ll::PMSC_CTRL0::LEN) {
+            self.pmsc_ctrl0();
+        }
+        if touched(ll::AON_CTRL::ID, ll::AON_CTRL::SUB_ID, ll::AON_CTRL::LEN) {
+            self.aon_ctrl();
+        }
+        if touched(ll::TC_SARC::ID, ll::TC_SARC::SUB_ID, ll::TC_SARC::LEN) {
+            self.tc_sarc();
+        }
+    }
+
+    fn sys_ctrl(&mut self) {
+        let sys_ctrl = self.get::<ll::SYS_CTRL>();
+
+        if sys_ctrl.trxoff() == 0b1 {
+            self.tx = None;
+            self.rx_since = None;
+            self.rx_stalled = false;
+        }
+        if sys_ctrl.hrbpt() == 0b1 {
+            self.toggle_host_buffer();
+        }
+        if sys_ctrl.txstrt() == 0b1 {
+            self.start_tx(
+                sys_ctrl.txdlys() == 0b1,
+                sys_ctrl.wait4resp() == 0b1,
+            );
+        }
+        if sys_ctrl.rxenab() == 0b1 && self.rx_since.is_none() {
+            let now = self.now();
+
+            let since = if sys_ctrl.rxdlye() == 0b1 {
+                // The lower 9 bits of DX_TIME are ignored.
+                let dx_time = self.get::<ll::DX_TIME>().value() & !0x1ff;
+                let ahead   = dx_time.wrapping_sub(now) & TIME_MAX;
+
+                if ahead > TIME_MAX / 2 {
+                    // The time has already passed. The chip will wait for the
+                    // system time to wrap around and enable the receiver then.
+                    self.set::<ll::SYS_STATUS, _>(|w| w.hpdwarn(0b1));
+                }
+
+                now + ahead
+            }
+            else {
+                now
+            };
+
+            self.rx_since = Some(since);
+        }
+
+        // All command bits clear themselves.
+        self.store::<ll::SYS_CTRL>(0);
+    }
+
+    /// Releases the host-side receive buffer and makes the other one visible
+    ///
+    /// Implements HRBPT, as described in the user manual, section 4.3.
+    fn toggle_host_buffer(&mut self) {
+        let hsrbp = self.get::<ll::SYS_STATUS>().hsrbp();
+
+        self.rx_full[hsrbp as usize] = false;
+        self.swap_rx_sets();
+        self.set::<ll::SYS_STATUS, _>(|w| w.hsrbp(hsrbp ^ 0b1));
+
+        if self.rx_stalled {
+            self.rx_stalled = false;
+            self.rx_since = Some(self.now());
+        }
+    }
+
+    /// Exchanges the visible receive buffer with the one behind it
+    fn swap_rx_sets(&mut self) {
+        for (i, &id) in RX_SET_FILES.iter().enumerate() {
+            core::mem::swap(
+                &mut self.files[id as usize],
+                &mut self.rx_back.files[i],
+            );
+        }
+
+        core::mem::swap(
+            &mut self.files[ll::SYS_STATUS::ID as usize][RX_SET_STATUS],
+            &mut self.rx_back.status,
+        );
+    }
+
+    /// Indicates whether the double-buffered mode is enabled
+    fn double_buffered(&self) -> bool {
+        self.get::<ll::SYS_CFG>().dis_drxb() == 0b0
+    }
+
+    fn otp_ctrl(&mut self) {
+        let otp_ctrl = self.get::<ll::OTP_CTRL>();
+
+        if otp_ctrl.ldeload() == 0b1 {
+            self.lde_loaded = true;
+        }
+        if otp_ctrl.otpread() == 0b1 {
+            let address = self.get::<ll::OTP_ADDR>().value() as usize;
+            let value   = self.otp.get(address).cloned().unwrap_or(0);
+            self.store::<ll::OTP_RDAT>(value.into());
+        }
+        if otp_ctrl.otpmrwr() == 0b1 {
+            // OTPMR selects the mode register that OTP_WDAT is written to.
+            let value = self.get::<ll::OTP_WDAT>().value() as u16;
+            let mr    = otp_ctrl.otpmr();
+            if mr & 0b0100 != 0 {
+                self.otp_mode[1] = value;
+            }
+            else if mr & 0b1000 != 0 {
+                self.otp_mode[2] = value;
+            }
+            else if mr & 0b0010 != 0 {
+                self.otp_mode[0] = value;
+            }
+        }
+        if otp_ctrl.otpprog() == 0b1 {
+            // Programming can only set bits, and requires VPP and the mode
+            // registers to be set up for programming.
+            if self.vpp && self.otp_mode == OTP_MODE_PROGRAM {
+                let address = self.get::<ll::OTP_ADDR>().value() as usize;
+                let value   = self.get::<ll::OTP_WDAT>().value();
+                if let Some(word) = self.otp.get_mut(address) {
+                    *word |= value;
+                }
+            }
+            self.set::<ll::OTP_STAT, _>(|w| w.otpprgd(0b1));
+        }
+
+        // LDELOAD and OTPREAD clear themselves once done.
+        self.set::<ll::OTP_CTRL, _>(|w| w.otpread(0).ldeload(0));
+    }
+
+    fn otp_sf(&mut self) {
+        if self.get::<ll::OTP_SF>().ldo_kick() == 0b1 {
+            let ldotune = self.otp[0x004] as u64
+                | (self.otp[0x005] as u64 & 0xff) << 32;
+            self.store::<ll::LDOTUNE>(ldotune);
+        }
+
+        // The kick bits clear themselves once done.
+        self.set::<ll::OTP_SF, _>(|w| w.ops_kick(0).ldo_kick(0));
+    }
+
+    fn evc_ctrl(&mut self) {
+        let evc_ctrl = self.get::<ll::EVC_CTRL>();
+
+        if evc_ctrl.evc_clr() == 0b1 {
+            self.store::<ll::EVC_HPW>(0);
+            self.store::<ll::EVC_TPW>(0);
+        }
+        if evc_ctrl.evc_en() == 0b1 {
+            self.counters_enabled = true;
+        }
+
+        // Both bits read back as zero. The driver relies on that when it waits
+        // for the counters to be cleared and enabled.
+        self.store::<ll::EVC_CTRL>(0);
+    }
+
+    fn pmsc_ctrl0(&mut self) {
+        match self.get::<ll::PMSC_CTRL0>().softreset() {
+            Some(ll::SoftReset::All) => {
+                self.reset();
+            }
+            Some(ll::SoftReset::Receiver) => {
+                self.rx_since = None;
+            }
+            _ => (),
+        }
+    }
+
+    fn aon_ctrl(&mut self) {
+        let aon_ctrl = self.get::<ll::AON_CTRL>();
+
+        // Saving the configuration to the AON memory enters sleep, if sleep
+        // is enabled. See user manual, section 7.2.44.2.
+        if aon_ctrl.save() == 0b1 && self.get::<ll::AON_CFG0>().sleep_en() == 0b1 {
+            self.asleep = true;
+            self.tx = None;
+            self.rx_since = None;
+            self.rx_stalled = false;
+        }
+
+        // All command bits clear themselves.
+        self.store::<ll::AON_CTRL>(0);
+    }
+
+    /// Runs the SAR ADC, if it has been enabled
+    ///
+    /// Inverts the conversion formulas from the user manual, section 6.4,
+    /// using the calibration values in OTP memory. The readings are only
+    /// available, if the biases and outputs have been enabled before.
+    fn tc_sarc(&mut self) {
+        if self.get::<ll::TC_SARC>().sar_ctrl() == 0b0
+            || self.get::<ll::RF_SAR_CFG>().value() != 0x0F
+        {
+            return;
+        }
+
+        let voltage_cal     = (self.otp[0x008] & 0xff) as f64;
+        let temperature_cal = (self.otp[0x009] & 0xff) as f64;
+
+        let raw_voltage     = voltage_cal + (self.voltage - 3.3) * 173.0;
+        let raw_temperature = temperature_cal + (self.temperature - 23.0) / 1.14;
+
+        let raw_voltage     = raw_voltage.round().clamp(0.0, 255.0) as u64;
+        let raw_temperature = raw_temperature.round().clamp(0.0, 255.0) as u64;
+        self.store::<ll::TC_SARL>(raw_voltage | raw_temperature << 8);
+    }
+
+    /// Releases the WAKEUP pin, waking up the chip if it was held long enough
+    fn release_wakeup_pin(&mut self) {
+        let since = match self.wakeup_pin_since.take() {
+            Some(since) => since,
+            None        => return,
+        };
+
+        let held = self.clock.get() - since >= WAKE_UP_HOLD_TIME;
+        if self.asleep && held && self.get::<ll::AON_CFG0>().wake_pin() == 0b1 {
+            self.wake_up();
+        }
+    }
+
+    /// Leaves the SLEEP or DEEPSLEEP state
+    ///
+    /// If ONW_LDC is set, the configuration saved to the AON memory is
+    /// restored. Otherwise the chip comes up in its reset state. Either way,
+    /// the LDE microcode, including the RX antenna delay, is lost.
+    fn wake_up(&mut self) {
+        if self.get::<ll::AON_WCFG>().onw_ldc() == 0b1 {
+            self.asleep = false;
+            self.lde_loaded = false;
+            self.store::<ll::LDE_RXANTD>(0);
+            self.store::<ll::SYS_STATUS>(0x00000002); // CPLOCK
+        }
+        else {
+            self.reset();
+        }
+    }
+
+    /// Starts a transmission, as requested by TXSTRT
+    fn start_tx(&mut self, delayed: bool, wait_for_response: bool) {
+        let tx_fctrl = self.get::<ll::TX_FCTRL>();
+        let long     = self.get::<ll::SYS_CFG>().phr_mode() == Some(ll::PhrMode::Long);
+
+        // The length extension is only sent in long frame mode.
+        let mut len = tx_fctrl.tflen() as usize;
+        if long {
+            len |= (tx_fctrl.tfle() as usize) << 7;
+        }
+        let offset = tx_fctrl.txboffs() as usize;
+
+        // The last two bytes are the FCS, which the DW1000 computes itself.
+        let data = self.files[TX_BUFFER as usize]
+            [offset..offset + len.saturating_sub(2)]
+            .to_vec();
+
+        let airtime = Airtime::new(self, len);
+        let now     = self.now();
+
+        let rmarker = if delayed {
+            // The lower 9 bits of DX_TIME are ignored.
+            let dx_time = self.get::<ll::DX_TIME>().value() & !0x1ff;
+            let ahead   = dx_time.wrapping_sub(now) & TIME_MAX;
+
+            if ahead > TIME_MAX / 2 {
+                // The time has already passed. The chip will wait for the
+                // system time to wrap around and send the frame then.
+                self.set::<ll::SYS_STATUS, _>(|w| w.hpdwarn(0b1));
+                self.count::<ll::EVC_HPW>();
+            }
+            else if ahead < TX_POWER_UP_TIME + airtime.preamble {
+                self.set::<ll::SYS_STATUS, _>(|w| w.txpute(0b1));
+                self.count::<ll::EVC_TPW>();
+            }
+
+            now + ahead
+        }
+        else {
+            (now + TX_POWER_UP_TIME + airtime.preamble + 0x1ff) & !0x1ff
+        };
+
+        self.tx = Some(Tx {
+            data,
+            phy:   self.tx_phy(),
+            start: rmarker.saturating_sub(airtime.preamble),
+            rmarker,
+            end:   rmarker + airtime.payload,
+            wait_for_response,
+        });
+    }
+
+    /// Starts the automatic acknowledgement of a frame that ended at `end`
+    fn start_ack(&mut self, seq: u8, end: u64) {
+        // Frame type acknowledgement, no addresses
+        let data = vec![0x02, 0x00, seq];
+
+        let airtime = Airtime::new(self, data.len() + 2);
+        let ack_tim = self.get::<ll::ACK_RESP_T>().ack_tim() as u64;
+        let rmarker = end + ack_tim * SYMBOL_TIME + airtime.preamble;
+
+        self.set::<ll::SYS_STATUS, _>(|w| w.aat(0b1));
+        self.tx = Some(Tx {
+            data,
+            phy:   self.tx_phy(),
+            start: end + ack_tim * SYMBOL_TIME,
+            rmarker,
+            end:   rmarker + airtime.payload,
+            wait_for_response: false,
+        });
+    }
+
+    /// Decides whether a received frame must be acknowledged automatically
+    fn should_ack(&self, data: &[u8]) -> bool {
+        let sys_cfg = self.get::<ll::SYS_CFG>();
+        if sys_cfg.autoack() == 0b0 || sys_cfg.ffen() == 0b0 || data.len() < 3 {
+            return false;
+        }
+
+        let ack_request = data[0] & 0x20 != 0;
+        let frame_type  = data[0] & 0x7;
+        let dst_mode    = (data[1] >> 2) & 0x3;
+
+        // Broadcasts are never acknowledged.
+        let broadcast = dst_mode == 0b10
+            && data.len() >= 7
+            && data[5] == 0xff && data[6] == 0xff;
+
+        ack_request
+            && (frame_type == 0b001 || frame_type == 0b011)
+            && !broadcast
+    }
+
+    /// Returns the time at which the frame wait timeout expires, if enabled
+    fn rx_deadline(&self) -> Option<u64> {
+        if self.get::<ll::SYS_CFG>().rxwtoe() == 0b0 {
+            return None;
+        }
+
+        let rx_fwto = self.get::<ll::RX_FWTO>().value() as u64;
+        self.rx_since.map(|since| since + rx_fwto * UWB_MICROSECOND)
+    }
+
+    /// Returns the time by which a preamble must be detected, if enabled
+    fn preamble_deadline(&self) -> Option<u64> {
+        let pretoc = self.get::<ll::DRX_PRETOC>().count() as u64;
+        if pretoc == 0 {
+            return None;
+        }
+
+        self.rx_since
+            .map(|since| since + pretoc * self.pac_size() * SYMBOL_TIME)
+    }
+
+    /// Returns the PAC size, in preamble symbols
+    fn pac_size(&self) -> u64 {
+        // The PAC size is encoded in DRX_TUNE2 (see user manual, table 33).
+        let drx_tune2 = self.get::<ll::DRX_TUNE2>().value();
+        8 << ((drx_tune2 >> 25) & 0b11)
+    }
+
+    /// Decides whether the receiver notices a preamble in SNIFF mode
+    ///
+    /// In SNIFF mode, the receiver alternates between listening for one PAC
+    /// more than SNIFF_ONT and being switched off for SNIFF_OFFT. The preamble
+    /// is only noticed, if a complete listening phase falls within it. Outside
+    /// of SNIFF mode, the receiver always notices the preamble.
+    fn sniff_detects(&self, preamble_start: u64, preamble_end: u64) -> bool {
+        let rx_sniff = self.get::<ll::RX_SNIFF>();
+        let enabled  = rx_sniff.sniff_ont() != 0
+            && self.get::<ll::PMSC_CTRL0>().pll2_seq_en() == 0b1;
+
+        let since = match self.rx_since {
+            Some(since) if enabled => since,
+            _                      => return true,
+        };
+
+        let on_time  = (rx_sniff.sniff_ont() as u64 + 1) * self.pac_size() * SYMBOL_TIME;
+        let off_time = rx_sniff.sniff_offt() as u64 * SNIFF_OFF_TIME;
+        let period   = on_time + off_time;
+
+        // The first listening phase that starts within the preamble
+        let cycles = preamble_start.saturating_sub(since).div_ceil(period);
+        let start = since + cycles * period;
+
+        start + on_time <= preamble_end
+    }
+
+    /// Disables the receiver, if one of the receive timeouts has expired
+    ///
+    /// `preamble_detected` tells whether a frame is currently arriving. The
+    /// preamble detection timeout doesn't apply in that case.
+    fn check_rx_timeout(&mut self, preamble_detected: bool) {
+        let now = self.now();
+
+        if !preamble_detected
+            && matches!(self.preamble_deadline(), Some(deadline) if deadline <= now)
+        {
+            self.rx_since = None;
+            self.set::<ll::SYS_STATUS, _>(|w| w.rxpto(0b1));
+        }
+        if matches!(self.rx_deadline(), Some(deadline) if deadline <= now) {
+            self.rx_since = None;
+            self.set::<ll::SYS_STATUS, _>(|w| w.rxrfto(0b1));
+        }
+    }
+
+    /// Completes any events that happened up to the current time
+    ///
+    /// Only used for chips that are not connected to an ether. The ether
+    /// processes the events of its chips itself.
+    fn update(&mut self) {
+        let finished = match &self.tx {
+            Some(tx) => tx.end <= self.now(),
+            None     => false,
+        };
+        if finished {
+            self.finish_tx();
+        }
+
+        self.check_rx_timeout(false);
+        self.update_sys_time();
+    }
+
+    fn update_sys_time(&mut self) {
+        let now = self.now();
+        self.store::<ll::SYS_TIME>(now & TIME_MAX);
+    }
+
+    /// Completes the current transmission, regardless of the current time
+    ///
+    /// Returns the completed transmission, if there was one.
+    fn finish_tx(&mut self) -> Option<Tx> {
+        let tx = self.tx.take()?;
+
+        let tx_antd  = self.get::<ll::TX_ANTD>().value() as u64;
+        let tx_rawst = tx.rmarker & TIME_MAX;
+        let tx_stamp = (tx_rawst + tx_antd) & TIME_MAX;
+        let mut tx_time = [0; 10];
+        tx_time[..5].copy_from_slice(&tx_stamp.to_le_bytes()[..5]);
+        tx_time[5..].copy_from_slice(&tx_rawst.to_le_bytes()[..5]);
+        self.store_bytes::<ll::TX_TIME>(&tx_time);
+
+        self.set::<ll::SYS_STATUS, _>(|w|
+            w
+                .txfrb(0b1)
+                .txprs(0b1)
+                .txphs(0b1)
+                .txfrs(0b1)
+        );
+
+        self.transmitted.push(Transmission {
+            data:    tx.data.clone(),
+            // Can't panic, as the value is masked to 40 bits.
+            tx_time: Instant::new(tx_stamp).unwrap(),
+        });
+
+        if tx.wait_for_response {
+            let w4r_tim = self.get::<ll::ACK_RESP_T>().w4r_tim() as u64;
+            self.rx_since = Some(tx.end + w4r_tim * UWB_MICROSECOND);
+        }
+
+        Some(tx)
+    }
+
+    /// Returns the PHY configuration used for transmission
+    fn tx_phy(&self) -> Phy {
+        let tx_fctrl  = self.get::<ll::TX_FCTRL>();
+        let chan_ctrl = self.get::<ll::CHAN_CTRL>();
+
+        Phy {
+            channel:          chan_ctrl.tx_chan_raw(),
+            prf:              tx_fctrl.txprf_raw(),
+            preamble_code:    chan_ctrl.tx_pcode(),
+            bitrate:          tx_fctrl.txbr_raw(),
+            preamble_length:  tx_fctrl.txpsr(),
+            preamble_symbols: preamble_symbols(&tx_fctrl),
+            ranging:          tx_fctrl.tr(),
+        }
+    }
+
+    /// Decides whether the receiver is configured to receive a frame
+    fn can_receive(&self, phy: &Phy) -> bool {
+        let chan_ctrl = self.get::<ll::CHAN_CTRL>();
+        let rxm110k   = self.get::<ll::SYS_CFG>().rxm110k();
+
+        chan_ctrl.rx_chan_raw() == phy.channel
+            && chan_ctrl.rxprf_raw() == phy.prf
+            && chan_ctrl.rx_pcode() == phy.preamble_code
+            && (rxm110k == 0b1) == (phy.bitrate == 0b00)
+    }
+
+    /// Receives a frame whose RMARKER arrived at the given time
+    ///
+    /// `end` is the time at which the end of the frame arrived. `car_int` is
+    /// the value of the carrier recovery integrator that the
+    /// receiver reports for this frame. `rx_power_dbm` is the power of the
+    /// received signal.
+    fn receive(&mut self,
+        data:         &[u8],
+        rmarker:      u64,
+        end:          u64,
+        phy:          Phy,
+        car_int:      i32,
+        rx_power_dbm: f64,
+    )
+        -> bool
+    {
+        let listening = matches!(self.rx_since, Some(since) if since <= rmarker);
+        if !listening {
+            return false;
+        }
+
+        let preamble_start =
+            rmarker.saturating_sub(phy.preamble_symbols as u64 * SYMBOL_TIME);
+        if !self.sniff_detects(preamble_start, rmarker) {
+            return false;
+        }
+
+        // Outside of long frame mode, the receiver doesn't understand the
+        // length extension in the PHY header.
+        let len = data.len() + 2;
+        if len > 127 && self.get::<ll::SYS_CFG>().phr_mode() != Some(ll::PhrMode::Long) {
+            self.set::<ll::SYS_STATUS, _>(|w|
+                w
+                    .rxprd(0b1)
+                    .rxsfdd(0b1)
+                    .rxphe(0b1)
+            );
+            if self.get::<ll::SYS_CFG>().rxautr() == 0b0 {
+                self.rx_since = None;
+            }
+            return true;
+        }
+
+        // Rejected frames are discarded, and the receiver re-enables itself
+        // right away (user manual, section 5.2).
+        if !self.accepts(data) {
+            self.set::<ll::SYS_STATUS, _>(|w|
+                w
+                    .rxprd(0b1)
+                    .rxsfdd(0b1)
+                    .rxphd(0b1)
+                    .affrej(0b1)
+            );
+            return false;
+        }
+
+        self.rx_since = None;
+
+        // In double-buffered mode, the frame goes into the buffer the IC
+        // pointer refers to. If that isn't the one visible to the host, it is
+        // swapped in for the duration.
+        let double_buffered = self.double_buffered();
+        let sys_status      = self.get::<ll::SYS_STATUS>();
+        let icrbp           = sys_status.icrbp();
+        let behind          = double_buffered && icrbp != sys_status.hsrbp();
+        if behind {
+            self.swap_rx_sets();
+        }
+
+        let fcs = crc16(data);
+
+        let rx_buffer = &mut self.files[RX_BUFFER as usize];
+        rx_buffer[..data.len()].copy_from_slice(data);
+        rx_buffer[data.len()..len].copy_from_slice(&fcs.to_le_bytes());
+
+        // The preamble accumulation count is a 12-bit value. Unless it
+        // saturates, it includes some of the SFD symbols, as described in the
+        // user manual, section 4.7.1, table 18.
+        let chan_ctrl = self.get::<ll::CHAN_CTRL>();
+        let standard_sfd = chan_ctrl.dwsfd() == 0b0 && chan_ctrl.rnssfd() == 0b0;
+        let sfd_symbols = match (standard_sfd, phy.bitrate) {
+            (true,  0b00) => 64,
+            (true,  _)    => 5,
+            (false, 0b00) => 82,
+            (false, 0b01) => 18,
+            (false, _)    => 10,
+        };
+        let rxpacc_nosat = phy.preamble_symbols + sfd_symbols;
+        let rxpacc       = rxpacc_nosat.min(0xfff);
+
+        let rx_finfo =
+            (len as u64 & 0x3ff)
+            | (phy.bitrate as u64) << 13
+            | (phy.ranging as u64) << 15
+            | (phy.prf as u64) << 16
+            | (phy.preamble_length as u64) << 18
+            | (rxpacc as u64) << 20;
+        self.store::<ll::RX_FINFO>(rx_finfo);
+        self.store::<ll::RXPACC_NOSAT>(rxpacc_nosat as u64);
+
+        // Invert the formulas from the user manual, section 4.7, to get
+        // register values that represent the receive power. The signal is
+        // modelled as a single path, so first path power and receive power are
+        // the same.
+        let a = match phy.prf {
+            0b10 => 121.74,
+            _    => 113.77,
+        };
+        // The formulas use the count without the SFD symbols, if it hasn't
+        // saturated.
+        let n = if rxpacc == rxpacc_nosat {
+            phy.preamble_symbols
+        }
+        else {
+            rxpacc
+        } as f64;
+        let power = 10f64.powf((rx_power_dbm + a) / 10.0) * n * n;
+        let cir_pwr  = (power / (1 << 17) as f64).round().min(65535.0) as u64;
+        let fp_ampl  = (power / 3.0).sqrt().round().min(65535.0) as u64;
+        let rx_fqual = fp_ampl << 16 | fp_ampl << 32 | cir_pwr << 48;
+        self.store::<ll::RX_FQUAL>(rx_fqual);
+
+        // The channel impulse response consists of a single path, spread
+        // over three samples.
+        let peak = fp_ampl.min(i16::MAX as u64) as i16;
+        let acc_mem = &mut self.files[ACC_MEM as usize];
+        acc_mem.iter_mut().for_each(|byte| *byte = 0);
+        for (i, &re) in [peak / 2, peak, peak / 2].iter().enumerate() {
+            let address = (FP_INDEX - 1 + i) * 4;
+            acc_mem[address..address + 2].copy_from_slice(&re.to_le_bytes());
+        }
+
+        let rx_antd  = self.get::<ll::LDE_RXANTD>().value() as u64;
+        let rx_rawst = rmarker & TIME_MAX;
+        let rx_stamp = rx_rawst.wrapping_sub(rx_antd) & TIME_MAX;
+        let mut rx_time = [0; 14];
+        rx_time[..5].copy_from_slice(&rx_stamp.to_le_bytes()[..5]);
+        rx_time[5..7].copy_from_slice(&((FP_INDEX as u16) << 6).to_le_bytes());
+        rx_time[7..9].copy_from_slice(&(fp_ampl as u16).to_le_bytes());
+        rx_time[9..].copy_from_slice(&rx_rawst.to_le_bytes()[..5]);
+        self.store_bytes::<ll::RX_TIME>(&rx_time);
+
+        // The carrier integrator is a 21-bit signed value.
+        let car_int = (car_int as u32 & 0x1fffff).to_le_bytes();
+        self.store_bytes::<ll::DRX_CAR_INT>(&car_int[..3]);
+
+        self.set::<ll::SYS_STATUS, _>(|w|
+            w
+                .rxprd(0b1)
+                .rxsfdd(0b1)
+                .rxphd(0b1)
+                .rxdfr(0b1)
+                .rxfcg(0b1)
+        );
+
+        // Without its microcode, the LDE never finishes, and the time stamp
+        // never becomes available.
+        if self.lde_loaded {
+            self.set::<ll::SYS_STATUS, _>(|w| w.ldedone(0b1));
+        }
+
+        if behind {
+            self.swap_rx_sets();
+        }
+        if double_buffered {
+            // The receiver stays on, as long as there's a free buffer.
+            let next = icrbp ^ 0b1;
+            self.rx_full[icrbp as usize] = true;
+            self.set::<ll::SYS_STATUS, _>(|w| w.icrbp(next));
+
+            if self.rx_full[next as usize] {
+                self.rx_stalled = true;
+            }
+            else {
+                self.rx_since = Some(end);
+            }
+        }
+
+        if self.should_ack(data) {
+            self.start_ack(data[2], end);
+        }
+
+        true
+    }
+
+    /// Receives a frame with an FCS error that ended at `end`
+    fn receive_corrupted(&mut self, end: u64) -> bool {
+        let listening = matches!(self.rx_since, Some(since) if since <= end);
+        if !listening {
+            return false;
+        }
+
+        self.set::<ll::SYS_STATUS, _>(|w|
+            w
+                .rxprd(0b1)
+                .rxsfdd(0b1)
+                .rxphd(0b1)
+                .rxfce(0b1)
+        );
+
+        // RXAUTR re-enables the receiver after errors.
+        if self.get::<ll::SYS_CFG>().rxautr() == 0b0 {
+            self.rx_since = None;
+        }
+
+        true
+    }
+
+    /// Decides whether frame filtering lets a frame through
+    ///
+    /// Implements the rules from the user manual, section 5.2.
+    fn accepts(&self, data: &[u8]) -> bool {
+        let sys_cfg = self.get::<ll::SYS_CFG>();
+        if sys_cfg.ffen() == 0b0 {
+            return true;
+        }
+
+        if data.len() < 3 {
+            return false;
+        }
+
+        let allowed = match data[0] & 0x7 {
+            0b000 => sys_cfg.ffab(),
+            0b001 => sys_cfg.ffad(),
+            0b010 => sys_cfg.ffaa(),
+            0b011 => sys_cfg.ffam(),
+            0b100 => sys_cfg.ffa4(),
+            0b101 => sys_cfg.ffa5(),
+            _     => sys_cfg.ffar(),
+        };
+        if allowed == 0b0 {
+            return false;
+        }
+
+        let panadr   = self.get::<ll::PANADR>();
+        let eui      = self.get::<ll::EUI>().value();
+        let dst_mode = (data[1] >> 2) & 0x3;
+        let dst      = &data[3..];
+
+        let pan_matches = |pan_id: u16|
+            pan_id == 0xffff || pan_id == panadr.pan_id();
+
+        match dst_mode {
+            // No destination address. Only a coordinator accepts this.
+            0b00 => data[0] & 0x7 == 0b010 || sys_cfg.ffbc() == 0b1,
+            0b10 if dst.len() >= 4 => {
+                let pan_id = u16::from_le_bytes([dst[0], dst[1]]);
+                let addr   = u16::from_le_bytes([dst[2], dst[3]]);
+                pan_matches(pan_id)
+                    && (addr == 0xffff || addr == panadr.short_addr())
+            }
+            0b11 if dst.len() >= 10 => {
+                let pan_id = u16::from_le_bytes([dst[0], dst[1]]);
+                let mut addr = [0; 8];
+                addr.copy_from_slice(&dst[2..10]);
+                pan_matches(pan_id) && u64::from_le_bytes(addr) == eui
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads a register from the register file
+    fn get<R>(&self) -> R::Read
+        where R: Register + Readable
+    {
+        let mut r      = R::read();
+        let buffer     = R::buffer(&mut r);
+        let header_len = buffer.len() - R::LEN;
+        let start      = R::SUB_ID as usize;
+
+        buffer[header_len..].copy_from_slice(
+            &self.files[R::ID as usize][start..start + R::LEN]
+        );
+
+        r
+    }
+
+    /// Modifies fields of a register, bypassing write protection
+    fn set<R, F>(&mut self, f: F)
+        where
+            R: Register + Writable,
+            F: FnOnce(&mut R::Write) -> &mut R::Write,
+    {
+        let mut w      = R::write();
+        let buffer     = R::buffer(&mut w);
+        let header_len = buffer.len() - R::LEN;
+        let start      = R::SUB_ID as usize;
+        let file       = &mut self.files[R::ID as usize][start..start + R::LEN];
+
+        buffer[header_len..].copy_from_slice(file);
+        f(&mut w);
+        file.copy_from_slice(&R::buffer(&mut w)[header_len..]);
+    }
+
+    /// Overwrites a register with a value, bypassing write protection
+    fn store<R: Register>(&mut self, value: u64) {
+        self.store_bytes::<R>(&value.to_le_bytes()[..R::LEN]);
+    }
+
+    /// Overwrites a register with raw bytes, bypassing write protection
+    fn store_bytes<R: Register>(&mut self, bytes: &[u8]) {
+        let start = R::SUB_ID as usize;
+        self.files[R::ID as usize][start..start + bytes.len()]
+            .copy_from_slice(bytes);
+    }
+
+    /// Increments an event counter, if counters are enabled
+    fn count<R: Register>(&mut self) {
+        if self.counters_enabled {
+            let start = R::SUB_ID as usize;
+            let file  = &self.files[R::ID as usize];
+            let value = u16::from_le_bytes([file[start], file[start + 1]]);
+            self.store::<R>((value as u64 + 1) & 0xfff);
+        }
+    }
+}
+
+
+/// An SPI transaction in progress
+enum Transaction {
+    /// The header has not been received completely
+    Header(Vec<u8>),
+
+    /// The header has been received. Data is being transferred.
+    Data {
+        write:  bool,
+        id:     u8,
+        start:  usize,
+        offset: usize,
+    },
+}
+
+impl Transaction {
+    /// Parses the header, as written by `ll::init_header`
+    ///
+    /// Returns `None`, if more header bytes are needed.
+    fn parse(header: &[u8]) -> Option<Self> {
+        let write  = header[0] & 0x80 != 0;
+        let id     = header[0] & 0x3f;
+        let sub_id = header[0] & 0x40 != 0;
+
+        let offset = match header.len() {
+            1 if !sub_id              => 0,
+            2 if header[1] & 0x80 == 0 => (header[1] & 0x7f) as usize,
+            3 =>
+                (header[1] & 0x7f) as usize | (header[2] as usize) << 7,
+            _ => return None,
+        };
+
+        Some(Transaction::Data {
+            write,
+            id,
+            start: offset,
+            offset,
+        })
+    }
+}
+
+
+/// A transmission in progress
+///
+/// All times are in the transmitter's system time.
+struct Tx {
+    /// The frame data, without FCS
+    data: Vec<u8>,
+
+    /// The PHY configuration the frame is sent with
+    phy: Phy,
+
+    /// When the preamble starts
+    start: u64,
+
+    /// When the RMARKER leaves the transmitter
+    rmarker: u64,
+
+    /// When the transmission ends
+    end: u64,
+
+    /// Whether to enable the receiver after the transmission (WAIT4RESP)
+    wait_for_response: bool,
+}
+
+
+/// The receive buffer that is hidden from the host in double-buffered mode
+struct RxSet {
+    /// The contents of the register files listed in `RX_SET_FILES`
+    files: Vec<Vec<u8>>,
+
+    /// The frame status bits belonging to this buffer
+    status: u8,
+}
+
+impl RxSet {
+    fn new() -> Self {
+        RxSet {
+            files: RX_SET_FILES
+                .iter()
+                .map(|&id| vec![0; FILE_LENGTHS[id as usize]])
+                .collect(),
+            status: 0,
+        }
+    }
+}
+
+
+/// The PHY configuration of a frame
+#[derive(Clone, Copy)]
+struct Phy {
+    channel:          u8,
+    prf:              u8,
+    preamble_code:    u8,
+    bitrate:          u8,
+    preamble_length:  u8,
+    preamble_symbols: u16,
+    ranging:          u8,
+}
+
+
+/// The shared state of an [`Ether`]
+struct Medium {
+    /// The clock shared by all nodes, in DW1000 time units
+    clock: Rc<Cell<u64>>,
+
+    /// The chips connected to the ether
+    nodes: Vec<Rc<RefCell<State>>>,
+
+    /// Frames that are on their way to a receiver
+    in_flight: Vec<Signal>,
+}
+
+impl Medium {
+    /// Processes all events that happened up to the current time, in order
+    fn update(&mut self) {
+        let now = self.clock.get();
+
+        loop {
+            let next_tx = self.nodes.iter()
+                .enumerate()
+                .filter_map(|(i, node)| {
+                    let node = node.borrow();
+                    node.tx.as_ref().map(|tx| (node.to_global(tx.end), i))
+                })
+                .min();
+            let next_rx = self.in_flight.iter()
+                .enumerate()
+                .map(|(i, signal)| (signal.end, i))
+                .min();
+
+            match (next_tx, next_rx) {
+                (Some((tx_end, i)), next_rx)
+                    if tx_end <= now
+                        && !matches!(next_rx, Some((rx_end, _)) if rx_end < tx_end)
+                => {
+                    self.transmit(i);
+                }
+                (_, Some((rx_end, i))) if rx_end <= now => {
+                    let signal = self.in_flight.remove(i);
+                    self.arrive(signal);
+                }
+                _ => break,
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let preamble_detected = self.in_flight.iter()
+                .any(|signal| signal.receiver == i && signal.start <= now);
+
+            let mut node = node.borrow_mut();
+            node.check_rx_timeout(preamble_detected);
+            node.update_sys_time();
+        }
+    }
+
+    /// Completes the transmission of a node and sends the frame on its way
+    fn transmit(&mut self, sender: usize) {
+        let mut state = self.nodes[sender].borrow_mut();
+        let tx = match state.finish_tx() {
+            Some(tx) => tx,
+            None     => return,
+        };
+
+        // Time stamps refer to the transmitter. The signal leaves the antenna
+        // a bit later.
+        let delay   = state.node.tx_antenna_delay as u64;
+        let start   = state.to_global(tx.start + delay);
+        let rmarker = state.to_global(tx.rmarker + delay);
+        let end     = state.to_global(tx.end + delay);
+        let node    = state.node;
+        drop(state);
+
+        for (i, receiver) in self.nodes.iter().enumerate() {
+            if i == sender {
+                continue;
+            }
+
+            let distance =
+                distance(node.position, receiver.borrow().node.position);
+            let time_of_flight =
+                (distance / SPEED_OF_LIGHT * 1e9 * TICKS_PER_NS as f64).round()
+                    as u64;
+
+            self.in_flight.push(Signal {
+                receiver:        i,
+                data:            tx.data.clone(),
+                phy:             tx.phy,
+                clock_drift_ppm: node.clock_drift_ppm,
+                path_loss_db:    path_loss_db(&tx.phy, distance),
+                start:           start + time_of_flight,
+                rmarker:         rmarker + time_of_flight,
+                end:             end + time_of_flight,
+            });
+        }
+    }
+
+    /// Hands a frame that has fully arrived to its receiver
+    fn arrive(&mut self, signal: Signal) {
+        let mut state = self.nodes[signal.receiver].borrow_mut();
+
+        // The receiver needs to listen from the start of the preamble.
+        let listening = match state.rx_since {
+            Some(since) => state.to_global(since) <= signal.start,
+            None        => false,
+        };
+        if !listening || !state.can_receive(&signal.phy) {
+            return;
+        }
+
+        // The receiver gives up, if the preamble detection timeout expires
+        // before the preamble arrives, or the frame wait timeout expires
+        // before the frame is complete.
+        let start = state.to_local(signal.start);
+        let end   = state.to_local(signal.end);
+        if matches!(state.preamble_deadline(), Some(deadline) if deadline < start)
+            || matches!(state.rx_deadline(), Some(deadline) if deadline < end)
+        {
+            return;
+        }
+
+        let rmarker = state.to_local(signal.rmarker)
+            + state.node.rx_antenna_delay as u64;
+        let car_int = carrier_integrator(
+            &signal.phy,
+            state.node.clock_drift_ppm - signal.clock_drift_ppm,
+        );
+        let rx_power_dbm = TX_POWER_DBM - signal.path_loss_db;
+
+        state.receive(
+            &signal.data,
+            rmarker,
+            end,
+            signal.phy,
+            car_int,
+            rx_power_dbm,
+        );
+    }
+}
+
+
+/// A frame on its way to a receiver
+///
+/// All times are in the shared clock of the ether, and refer to the moment the
+/// signal arrives at the receiver's antenna.
+struct Signal {
+    /// The index of the receiving node
+    receiver: usize,
+
+    /// The frame data, without FCS
+    data: Vec<u8>,
+
+    /// The PHY configuration the frame was sent with
+    phy: Phy,
+
+    /// The clock drift of the transmitter, in ppm
+    clock_drift_ppm: f64,
+
+    /// The attenuation of the signal on its way to the receiver
+    path_loss_db: f64,
+
+    /// When the preamble arrives
+    start: u64,
+
+    /// When the RMARKER arrives
+    rmarker: u64,
+
+    /// When the end of the frame arrives
+    end: u64,
+}
+
+
+/// Computes the distance between two positions, in meters
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Computes the free-space path loss over a distance, in dB
+///
+/// Distances below 10 cm are treated as 10 cm, as the model breaks down in the
+/// near field.
+fn path_loss_db(phy: &Phy, distance: f64) -> f64 {
+    let distance = distance.max(0.1);
+    let wavelength = SPEED_OF_LIGHT / carrier_hz(phy.channel);
+
+    20.0 * (4.0 * core::f64::consts::PI * distance / wavelength).log10()
+}
+
+/// Returns the center frequency of a channel, in Hz
+fn carrier_hz(channel: u8) -> f64 {
+    match channel {
+        1     => 3494.4e6,
+        2 | 4 => 3993.6e6,
+        3     => 4492.8e6,
+        _     => 6489.6e6,
+    }
+}
+
+/// Computes the value of the carrier recovery integrator
+///
+/// `drift_ppm` is the amount by which the receiver's clock runs faster than
+/// the transmitter's. The scaling is taken from the user manual, section
+/// 7.2.40.11 (DRX_CAR_INT).
+fn carrier_integrator(phy: &Phy, drift_ppm: f64) -> i32 {
+    let hz_per_step = match phy.bitrate {
+        0b00 => 998.4e6 / 2.0 / 8192.0 / 131072.0,
+        _    => 998.4e6 / 2.0 / 1024.0 / 131072.0,
+    };
+
+    (drift_ppm * 1e-6 * carrier_hz(phy.channel) / hz_per_step).round() as i32
+}
+
+
+/// Duration of a frame on the air
+///
+/// This is an approximation, based on the frame format described in the user
+/// manual, section 3.
+struct Airtime {
+    /// From the start of the preamble to the RMARKER
+    preamble: u64,
+
+    /// From the RMARKER to the end of the frame
+    payload: u64,
+}
+
+impl Airtime {
+    fn new(state: &State, len: usize) -> Self {
+        let tx_fctrl  = state.get::<ll::TX_FCTRL>();
+        let chan_ctrl = state.get::<ll::CHAN_CTRL>();
+
+        let symbol_ps = match tx_fctrl.txprf() {
+            Some(PulseRepetitionFrequency::Mhz16) => 993_590,
+            _                                     => 1_017_630,
+        };
+        let preamble_symbols = preamble_symbols(&tx_fctrl) as u64;
+        let (sfd_symbols, phr_kbps, data_kbps) = match tx_fctrl.txbr() {
+            Some(BitRate::Kbps110)                             => (64, 110,  110),
+            Some(BitRate::Kbps850) if chan_ctrl.dwsfd() == 0b1 => (16, 850,  850),
+            Some(BitRate::Kbps850)                             => ( 8, 850,  850),
+            _                                                  => ( 8, 850, 6800),
+        };
+
+        // Reed-Solomon encoding adds 48 parity bits to every 330 data bits.
+        let data_bits = (len as u64 * 8) * 378 / 330;
+
+        let preamble_ps = (preamble_symbols + sfd_symbols) * symbol_ps;
+        let payload_ns  = 21 * 1_000_000 / phr_kbps
+            + data_bits * 1_000_000 / data_kbps;
+
+        Airtime {
+            preamble: preamble_ps * TICKS_PER_NS / 1000,
+            payload:  payload_ns * TICKS_PER_NS,
+        }
+    }
+}
+
+
+/// Returns the preamble length configured in TX_FCTRL, in symbols
+fn preamble_symbols(tx_fctrl: &ll::tx_fctrl::R) -> u16 {
+    match tx_fctrl.preamble_length() {
+        Some(PreambleLength::Symbols64)   => 64,
+        Some(PreambleLength::Symbols128)  => 128,
+        Some(PreambleLength::Symbols256)  => 256,
+        Some(PreambleLength::Symbols512)  => 512,
+        Some(PreambleLength::Symbols1024) => 1024,
+        Some(PreambleLength::Symbols1536) => 1536,
+        Some(PreambleLength::Symbols2048) => 2048,
+        _                                 => 4096,
+    }
+}
+
+
+/// Computes the IEEE 802.15.4 FCS (CRC-16/KERMIT)
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0 .. 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0x8408 } else { crc >> 1 };
+        }
+    }
+
+    crc
+}
@@ -0,0 +1,66 @@
+use dw1000::configs::{
+    CoarseGain,
+    PulseRepetitionFrequency,
+    TxGain,
+    TxPower,
+    UwbChannel,
+};
+
+
+#[test]
+fn tx_power_should_decode_recommended_values() {
+    let tx_power = UwbChannel::Channel5
+        .get_recommended_tx_power(PulseRepetitionFrequency::Mhz16, true);
+
+    assert_eq!(
+        tx_power,
+        TxPower::Smart {
+            boost_norm: TxGain { coarse: CoarseGain::Db10,   fine: 0x08 },
+            boost_500:  TxGain { coarse: CoarseGain::Db12_5, fine: 0x08 },
+            boost_250:  TxGain { coarse: CoarseGain::Db15,   fine: 0x08 },
+            boost_125:  TxGain { coarse: CoarseGain::Db15,   fine: 0x0e },
+        },
+    );
+    assert_eq!(tx_power.to_register_value(), 0x0E082848);
+}
+
+#[test]
+fn tx_power_should_round_trip_through_register_value() {
+    let channels = [
+        UwbChannel::Channel1,
+        UwbChannel::Channel2,
+        UwbChannel::Channel3,
+        UwbChannel::Channel4,
+        UwbChannel::Channel5,
+        UwbChannel::Channel7,
+    ];
+    let prfs = [
+        PulseRepetitionFrequency::Mhz16,
+        PulseRepetitionFrequency::Mhz64,
+    ];
+
+    for &channel in &channels {
+        for &prf in &prfs {
+            for &smart_power in &[true, false] {
+                let tx_power =
+                    channel.get_recommended_tx_power(prf, smart_power);
+                let value = tx_power.to_register_value();
+
+                assert_eq!(tx_power.is_smart(), smart_power);
+                assert_eq!(
+                    TxPower::from_register_value(value, smart_power),
+                    tx_power,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn tx_gain_should_add_coarse_and_fine_gain() {
+    let gain = TxGain { coarse: CoarseGain::Db7_5, fine: 5 };
+    assert_eq!(gain.db(), Some(10.0));
+
+    let gain = TxGain { coarse: CoarseGain::Off, fine: 5 };
+    assert_eq!(gain.db(), None);
+}
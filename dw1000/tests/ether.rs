@@ -0,0 +1,680 @@
+use dw1000::{
+    configs::{
+        BitRate,
+        PreambleLength,
+        UwbChannel,
+    },
+    mac,
+    ranging::{
+        self,
+        Message as _,
+    },
+    sim,
+    DW1000,
+    Ready,
+    RxConfig,
+    TxConfig,
+};
+
+
+type Dw1000 = DW1000<sim::Spi, sim::ChipSelect, Ready>;
+
+
+/// The time of flight for a distance of 30 m, in DW1000 time units
+const TOF_30M: u64 = 6404;
+
+
+fn init(chip: &sim::Chip, address: u16) -> Dw1000 {
+    let mut dw1000 = DW1000::new(chip.spi(), chip.chip_select())
+        .init()
+        .unwrap();
+    dw1000
+        .set_address(mac::PanId(0x0d57), mac::ShortAddress(address))
+        .unwrap();
+    dw1000
+}
+
+fn node(x: f64) -> sim::Node {
+    sim::Node {
+        position: [x, 0.0, 0.0],
+        .. sim::Node::default()
+    }
+}
+
+fn broadcast(dw1000: Dw1000, payload: &[u8]) -> Dw1000 {
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut sending = dw1000
+        .send(payload, destination, None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap()
+}
+
+
+#[test]
+fn frames_should_reach_all_nodes_that_are_receiving() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+    let c = ether.add_node(node(20.0));
+    let d = ether.add_node(node(30.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+    let c = init(&c, 3);
+    let _d = init(&d, 4);
+
+    let mut receiving_b = b.receive(RxConfig::default()).unwrap();
+    let mut receiving_c = c.receive(RxConfig::default()).unwrap();
+
+    broadcast(a, b"hello");
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving_b.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+    let message = nb::block!(receiving_c.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+
+    // D's receiver was never enabled, so it should have nothing to report.
+    assert_eq!(d.peek::<dw1000::ll::SYS_STATUS>().rxdfr(), 0b0);
+}
+
+#[test]
+fn time_stamps_should_reflect_distance_and_clock_offset() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(sim::Node {
+        clock_offset: dw1000::time::Duration::from_nanos(1_000_000),
+        .. node(30.0)
+    });
+
+    let a_chip = a.clone();
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b.receive(RxConfig::default()).unwrap();
+    broadcast(a, b"hello");
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let tx_time = a_chip.take_transmitted()[0].tx_time;
+
+    assert_eq!(
+        message.rx_time.duration_since(tx_time).value(),
+        1_000_000 * 64 + TOF_30M,
+    );
+}
+
+#[test]
+fn configured_antenna_delays_should_compensate_physical_ones() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(sim::Node {
+        tx_antenna_delay: 16_000,
+        .. node(0.0)
+    });
+    let b = ether.add_node(sim::Node {
+        rx_antenna_delay: 16_500,
+        .. node(30.0)
+    });
+
+    let a_chip = a.clone();
+    let mut a = init(&a, 1);
+    let mut b = init(&b, 2);
+    a.set_antenna_delay(0, 16_000).unwrap();
+    b.set_antenna_delay(16_500, 0).unwrap();
+
+    let mut receiving = b.receive(RxConfig::default()).unwrap();
+    broadcast(a, b"hello");
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let tx_time = a_chip.take_transmitted()[0].tx_time;
+
+    assert_eq!(message.rx_time.duration_since(tx_time).value(), TOF_30M);
+}
+
+#[test]
+fn frames_on_other_channels_should_not_be_received() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b_chip = b.clone();
+    let b = init(&b, 2);
+
+    let _receiving = b
+        .receive(RxConfig {
+            channel: UwbChannel::Channel2,
+            .. RxConfig::default()
+        })
+        .unwrap();
+    broadcast(a, b"hello");
+
+    assert_eq!(b_chip.peek::<dw1000::ll::SYS_STATUS>().rxdfr(), 0b0);
+}
+
+#[test]
+fn ranging_should_measure_distance() {
+    const DISTANCE_MM: u64 = 12_345;
+
+    let ether  = sim::Ether::new();
+    let anchor = ether.add_node(sim::Node {
+        clock_offset:     dw1000::time::Duration::from_nanos(123_456_789),
+        clock_drift_ppm:  7.5,
+        tx_antenna_delay: 16_450,
+        rx_antenna_delay: 16_450,
+        .. node(0.0)
+    });
+    let tag = ether.add_node(sim::Node {
+        clock_drift_ppm:  -12.0,
+        tx_antenna_delay: 16_450,
+        rx_antenna_delay: 16_450,
+        .. node(DISTANCE_MM as f64 / 1000.0)
+    });
+
+    let mut anchor = init(&anchor, 1);
+    let mut tag    = init(&tag, 2);
+    anchor.set_antenna_delay(16_450, 16_450).unwrap();
+    tag.set_antenna_delay(16_450, 16_450).unwrap();
+
+    let mut buffer = [0; 128];
+
+    // The anchor pings the tag
+    let mut receiving = tag.receive(RxConfig::default()).unwrap();
+    let mut sending = ranging::Ping::new(&mut anchor).unwrap()
+        .send(anchor).unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let anchor = sending.finish_sending().unwrap();
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let ping = ranging::Ping::decode::<sim::Spi, sim::ChipSelect>(&message)
+        .unwrap()
+        .unwrap();
+    let mut tag = receiving.finish_receiving().unwrap();
+
+    // The tag replies with a ranging request
+    let mut receiving = anchor.receive(RxConfig::default()).unwrap();
+    let mut sending = ranging::Request::new(&mut tag, &ping).unwrap()
+        .send(tag).unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let tag = sending.finish_sending().unwrap();
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let request =
+        ranging::Request::decode::<sim::Spi, sim::ChipSelect>(&message)
+            .unwrap()
+            .unwrap();
+    let mut anchor = receiving.finish_receiving().unwrap();
+
+    // The anchor sends the ranging response
+    let mut receiving = tag.receive(RxConfig::default()).unwrap();
+    let mut sending = ranging::Response::new(&mut anchor, &request).unwrap()
+        .send(anchor).unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let response =
+        ranging::Response::decode::<sim::Spi, sim::ChipSelect>(&message)
+            .unwrap()
+            .unwrap();
+
+    let distance_mm = ranging::compute_distance_mm(&response).unwrap();
+    assert!(
+        (distance_mm as i64 - DISTANCE_MM as i64).abs() < 10,
+        "Measured {} mm, expected {} mm", distance_mm, DISTANCE_MM,
+    );
+}
+
+#[test]
+fn ranging_messages_should_embed_actual_tx_time() {
+    let ether  = sim::Ether::new();
+    let a_chip = ether.add_node(node(0.0));
+
+    let mut a = init(&a_chip, 1);
+    a.set_antenna_delay(16_450, 16_450).unwrap();
+
+    let ping = ranging::Ping::new(&mut a).unwrap();
+    let mut sending = ping.send(a).unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    // The DW1000 ignores the lower 9 bits of the delayed TX time. The time
+    // stamp in the message must match the one it actually used.
+    assert_eq!(
+        ping.payload.ping_tx_time.value(),
+        a_chip.peek::<dw1000::ll::TX_TIME>().tx_stamp(),
+    );
+}
+
+#[test]
+fn single_sided_ranging_should_correct_clock_offset() {
+    const DISTANCE_MM: u64 = 7_500;
+
+    let ether     = sim::Ether::new();
+    let initiator = ether.add_node(sim::Node {
+        clock_drift_ppm:  -9.0,
+        tx_antenna_delay: 16_450,
+        rx_antenna_delay: 16_450,
+        .. node(0.0)
+    });
+    let responder = ether.add_node(sim::Node {
+        clock_offset:     dw1000::time::Duration::from_nanos(987_654_321),
+        clock_drift_ppm:  11.0,
+        tx_antenna_delay: 16_450,
+        rx_antenna_delay: 16_450,
+        .. node(DISTANCE_MM as f64 / 1000.0)
+    });
+
+    let mut initiator = init(&initiator, 1);
+    let mut responder = init(&responder, 2);
+    initiator.set_antenna_delay(16_450, 16_450).unwrap();
+    responder.set_antenna_delay(16_450, 16_450).unwrap();
+
+    let rx_config = RxConfig::default();
+    let mut buffer = [0; 128];
+
+    // The initiator polls the responder
+    let mut receiving = responder.receive(rx_config).unwrap();
+    let recipient =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(2));
+    let mut sending = ranging::Poll::new(&mut initiator, recipient).unwrap()
+        .send(initiator).unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let initiator = sending.finish_sending().unwrap();
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let poll = ranging::Poll::decode::<sim::Spi, sim::ChipSelect>(&message)
+        .unwrap()
+        .unwrap();
+    let mut responder = receiving.finish_receiving().unwrap();
+
+    // The responder replies
+    let mut receiving = initiator.receive(rx_config).unwrap();
+    let mut sending = ranging::PollResponse::new(&mut responder, &poll)
+        .unwrap()
+        .send(responder).unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let response =
+        ranging::PollResponse::decode::<sim::Spi, sim::ChipSelect>(&message)
+            .unwrap()
+            .unwrap();
+    let carrier_integrator = receiving.read_carrier_integrator().unwrap();
+
+    // The responder's clock is faster, so the integrator must be negative.
+    assert!(carrier_integrator < 0);
+
+    let distance_mm = ranging::compute_distance_mm_single_sided(
+        &response,
+        carrier_integrator,
+        rx_config,
+    )
+    .unwrap();
+    assert!(
+        (distance_mm as i64 - DISTANCE_MM as i64).abs() < 20,
+        "Measured {} mm, expected {} mm", distance_mm, DISTANCE_MM,
+    );
+}
+
+#[test]
+fn rx_power_should_follow_free_space_path_loss() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b.receive(RxConfig::default()).unwrap();
+    broadcast(a, b"hello");
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+
+    // Free-space path loss over 10 m on channel 5 (6489.6 MHz), with a
+    // transmit power of -14.3 dBm
+    let wavelength = 299_792_458.0 / 6489.6e6;
+    let path_loss = 20.0 * (4.0 * std::f64::consts::PI * 10.0 / wavelength)
+        .log10();
+    let expected = (-14.3 - path_loss) as f32;
+
+    let rx_power = message.rx_quality.rx_power_dbm();
+    assert!(
+        (rx_power - expected).abs() < 0.1,
+        "RX power {} dBm, expected {} dBm", rx_power, expected,
+    );
+}
+
+#[test]
+fn acknowledgement_should_be_sent_automatically() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b
+        .receive(RxConfig {
+            auto_ack: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(2));
+    let config = TxConfig {
+        ack_request: true,
+        .. TxConfig::default()
+    };
+    let mut sending = a.send(b"hello", destination, None, config).unwrap();
+    nb::block!(sending.wait_ack()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+    assert!(message.frame.header.ack_request);
+}
+
+#[test]
+fn missing_acknowledgement_should_time_out() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    // B receives the frame, but doesn't acknowledge it.
+    let mut receiving = b.receive(RxConfig::default()).unwrap();
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(2));
+    let config = TxConfig {
+        ack_request: true,
+        .. TxConfig::default()
+    };
+    let mut sending = a.send(b"hello", destination, None, config).unwrap();
+    match nb::block!(sending.wait_ack()) {
+        Err(dw1000::Error::AckTimeout) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    sending.finish_sending().unwrap();
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+}
+
+#[test]
+fn send_and_receive_should_not_miss_response() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b.receive(RxConfig::default()).unwrap();
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(2));
+    let mut exchanging = a
+        .send_and_receive(
+            b"ping",
+            destination,
+            None,
+            TxConfig::default(),
+            RxConfig::default(),
+            0,
+        )
+        .unwrap();
+    nb::block!(exchanging.wait_sent()).unwrap();
+
+    // B replies as soon as it has received the frame.
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"ping");
+    let b = receiving.finish_receiving().unwrap();
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(1));
+    let mut sending = b
+        .send(b"pong", destination, None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let message = nb::block!(exchanging.wait_response(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"pong");
+    exchanging.finish_exchange().unwrap();
+}
+
+#[test]
+fn frames_should_be_addressed_using_extended_addresses() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+    let c = ether.add_node(node(20.0));
+    a.set_otp(0x000, 0x0000000a);
+    b.set_otp(0x000, 0x0000000b);
+    c.set_otp(0x000, 0x0000000c);
+
+    let mut a = init(&a, 1);
+    let mut b = init(&b, 2);
+    let mut c = init(&c, 3);
+    let eui_a = a.set_extended_address_from_otp().unwrap();
+    let eui_b = b.set_extended_address_from_otp().unwrap();
+    c.set_extended_address_from_otp().unwrap();
+
+    let mut receiving_b = b.receive(RxConfig::default()).unwrap();
+    let mut receiving_c = c.receive(RxConfig::default()).unwrap();
+
+    let destination = mac::Address::Extended(mac::PanId(0x0d57), eui_b);
+    let mut sending = a
+        .send(
+            b"hello",
+            destination,
+            None,
+            TxConfig {
+                extended_source: true,
+                .. TxConfig::default()
+            },
+        )
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving_b.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+    assert_eq!(message.frame.header.destination, destination);
+    assert_eq!(
+        message.frame.header.source,
+        mac::Address::Extended(mac::PanId(0x0d57), eui_a),
+    );
+
+    // C's filter rejected the frame, as it's addressed to another node.
+    match receiving_c.wait(&mut buffer) {
+        Err(nb::Error::Other(dw1000::Error::FrameFilteringRejection)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn clock_offset_should_suggest_xtal_trim() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(sim::Node {
+        clock_drift_ppm: -4.5,
+        .. node(10.0)
+    });
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let rx_config = RxConfig::default();
+    let mut receiving = b.receive(rx_config).unwrap();
+    broadcast(a, b"hello");
+
+    let mut buffer = [0; 128];
+    nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let offset = receiving.read_clock_offset(rx_config).unwrap();
+
+    // B's clock is slower, so it needs to speed up by three trim steps.
+    assert!((offset.ppm - 4.5).abs() < 0.1, "Offset: {} ppm", offset.ppm);
+    assert_eq!(offset.suggested_xtal_trim, dw1000::hl::XTAL_TRIM_DEFAULT - 3);
+
+    let mut b = receiving.finish_receiving().unwrap();
+    b.set_xtal_trim(offset.suggested_xtal_trim).unwrap();
+    assert_eq!(b.get_xtal_trim().unwrap(), offset.suggested_xtal_trim);
+}
+
+#[test]
+fn clock_offset_should_be_measured_at_110_kbps() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(sim::Node {
+        clock_drift_ppm: -4.5,
+        .. node(10.0)
+    });
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let rx_config = RxConfig {
+        bitrate:                  BitRate::Kbps110,
+        expected_preamble_length: PreambleLength::Symbols2048,
+        .. RxConfig::default()
+    };
+    let mut receiving = b.receive(rx_config).unwrap();
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let tx_config = TxConfig {
+        bitrate:         BitRate::Kbps110,
+        preamble_length: PreambleLength::Symbols2048,
+        .. TxConfig::default()
+    };
+    let mut sending = a.send(b"hello", destination, None, tx_config).unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    let mut buffer = [0; 128];
+    nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let offset = receiving.read_clock_offset(rx_config).unwrap();
+
+    assert!((offset.ppm - 4.5).abs() < 0.1, "Offset: {} ppm", offset.ppm);
+    assert_eq!(offset.suggested_xtal_trim, dw1000::hl::XTAL_TRIM_DEFAULT - 3);
+}
+
+#[test]
+fn raw_frames_should_be_received_as_sent() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(30.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b
+        .receive(RxConfig {
+            frame_filtering: false,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let mut sending = a.send_raw(b"\x7fproprietary", None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let tx_time = sending.finish_sending().unwrap()
+        .ll().tx_time().read().unwrap().tx_stamp();
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait_raw(&mut buffer)).unwrap();
+    let len = message.data.len();
+    assert_eq!(&message.data[..len - 2], b"\x7fproprietary");
+    assert_eq!(message.rx_time.value(), tx_time + TOF_30M);
+}
+
+#[test]
+fn long_frames_should_be_received_in_long_frame_mode() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(10.0));
+
+    let a = init(&a, 1);
+    let b = init(&b, 2);
+
+    let mut receiving = b
+        .receive(RxConfig {
+            long_frames: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let mut chunk = [0; 1000];
+    chunk.iter_mut().enumerate().for_each(|(i, byte)| *byte = i as u8);
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(2));
+    let mut sending = a
+        .send(
+            &chunk,
+            destination,
+            None,
+            TxConfig {
+                long_frames: true,
+                .. TxConfig::default()
+            },
+        )
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    let mut buffer = [0; 1023];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, &chunk[..]);
+}
+
+#[test]
+fn preloaded_reply_should_carry_patched_tx_time() {
+    let ether = sim::Ether::new();
+    let a = ether.add_node(node(0.0));
+    let b = ether.add_node(node(30.0));
+
+    let a = init(&a, 1);
+    let mut b = init(&b, 2);
+
+    // B prepares its reply before anything happens. The TX time is filled in
+    // later.
+    let a_address =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(1));
+    let reply = b
+        .preload(0, b"reply:\0\0\0\0\0", a_address, &TxConfig::default())
+        .unwrap();
+
+    let mut receiving_b = b.receive(RxConfig::default()).unwrap();
+    let a = broadcast(a, b"request");
+    let mut receiving_a = a.receive(RxConfig::default()).unwrap();
+
+    let mut buffer = [0; 128];
+    let request = nb::block!(receiving_b.wait(&mut buffer)).unwrap();
+    let mut b = receiving_b.finish_receiving().unwrap();
+
+    let reply_time = request.rx_time + dw1000::time::Duration::from_nanos(5_000_000);
+    let tx_time    = reply_time.value() & !0x1ff;
+    b.patch_preloaded(&reply, 6, &tx_time.to_le_bytes()[..5]).unwrap();
+    let mut sending = b
+        .send_preloaded(&reply, Some(reply_time), TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    let message = nb::block!(receiving_a.wait(&mut buffer)).unwrap();
+    assert_eq!(&message.frame.payload[..6], b"reply:");
+
+    let mut reported = [0; 8];
+    reported[..5].copy_from_slice(&message.frame.payload[6..]);
+    assert_eq!(u64::from_le_bytes(reported), tx_time);
+    assert_eq!(message.rx_time.value(), tx_time + TOF_30M);
+}
@@ -0,0 +1,178 @@
+use core::convert::Infallible;
+
+use embedded_hal::digital::v2::OutputPin;
+
+use dw1000::{
+    ll,
+    otp::{
+        self,
+        VoltageCalibration,
+    },
+    sim,
+    DW1000,
+    Error,
+    Ready,
+};
+
+
+type Dw1000 = DW1000<sim::Spi, sim::ChipSelect, Ready>;
+
+
+fn init(chip: &sim::Chip) -> Dw1000 {
+    DW1000::new(chip.spi(), chip.chip_select())
+        .init()
+        .unwrap()
+}
+
+
+/// A programming voltage switch that isn't connected to anything
+struct Disconnected;
+
+impl OutputPin for Disconnected {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+
+#[test]
+fn typed_accessors_should_follow_documented_layout() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x000, 0x89abcdef);
+    chip.set_otp(0x001, 0x01234567);
+    chip.set_otp(0x004, 0x28924422);
+    chip.set_otp(0x005, 0x00000088);
+    chip.set_otp(0x006, 0x12345678);
+    chip.set_otp(0x007, 0x9abcdef0);
+    chip.set_otp(0x008, 0x0000b09a);
+    chip.set_otp(0x009, 0x00000081);
+    chip.set_otp(0x01e, 0x00000211);
+
+    let mut dw1000 = init(&chip);
+    let mut otp = dw1000.otp();
+
+    assert_eq!(otp.eui().unwrap(), 0x01234567_89abcdef);
+    assert_eq!(otp.alternative_eui().unwrap(), 0);
+    assert_eq!(otp.ldotune().unwrap(), Some(0x88_28924422));
+    assert_eq!(otp.chip_id().unwrap(), 0x12345678);
+    assert_eq!(otp.lot_id().unwrap(), 0x9abcdef0);
+    assert_eq!(
+        otp.voltage_calibration().unwrap(),
+        VoltageCalibration { at_3_3_v: 0x9a, at_3_7_v: 0xb0 },
+    );
+    assert_eq!(otp.temperature_calibration().unwrap(), 0x81);
+    assert_eq!(otp.xtal_trim().unwrap(), Some(0x11));
+}
+
+#[test]
+fn unprogrammed_values_should_be_reported_as_missing() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    let mut otp = dw1000.otp();
+
+    assert_eq!(otp.ldotune().unwrap(), None);
+    assert_eq!(otp.xtal_trim().unwrap(), None);
+}
+
+#[test]
+fn read_word_should_reject_addresses_outside_of_memory() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    match dw1000.otp().read_word(otp::WORDS) {
+        Err(Error::OtpAddressOutOfRange) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn read_word_should_disable_reading_afterwards() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x002, 0xdeadbeef);
+    let mut dw1000 = init(&chip);
+
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0xdeadbeef);
+    assert_eq!(chip.peek::<ll::OTP_CTRL>().otprden(), 0b0);
+}
+
+#[test]
+fn write_word_should_program_memory() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    let mut vpp = chip.vpp_pin();
+    let mut delay = chip.delay();
+
+    dw1000.otp()
+        .write_word(0x002, 0xdeadbeef, &mut vpp, &mut delay)
+        .unwrap();
+
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0xdeadbeef);
+
+    // The programming voltage is switched off again, and the system clock is
+    // back to automatic selection.
+    assert_eq!(chip.peek::<ll::OTP_STAT>().otpvpok(), 0b0);
+    assert_eq!(
+        chip.peek::<ll::PMSC_CTRL0>().sysclks(),
+        Some(ll::SysClks::Auto),
+    );
+}
+
+#[test]
+fn programming_should_require_mode_registers_to_be_set_up() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    chip.vpp_pin().set_high().unwrap();
+
+    // Just setting OTPPROG, without setting up the mode registers first,
+    // doesn't program anything.
+    dw1000.ll().otp_wdat().write(|w| w.value(0xdeadbeef)).unwrap();
+    dw1000.ll().otp_addr().write(|w| w.value(0x002)).unwrap();
+    dw1000.ll().otp_ctrl().write(|w| w.otpprog(0b1)).unwrap();
+    dw1000.ll().otp_ctrl().write(|w| w).unwrap();
+
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0);
+}
+
+#[test]
+fn write_word_should_not_clear_programmed_bits() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x002, 0x000000ff);
+    let mut dw1000 = init(&chip);
+    let mut vpp = chip.vpp_pin();
+    let mut delay = chip.delay();
+
+    let result = dw1000.otp()
+        .write_word(0x002, 0x0000ff00, &mut vpp, &mut delay);
+    match result {
+        Err(Error::OtpAlreadyProgrammed) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0x000000ff);
+
+    // Setting more bits is fine.
+    dw1000.otp()
+        .write_word(0x002, 0x0000ffff, &mut vpp, &mut delay)
+        .unwrap();
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0x0000ffff);
+}
+
+#[test]
+fn write_word_should_require_programming_voltage() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    let mut delay = chip.delay();
+
+    let result = dw1000.otp()
+        .write_word(0x002, 0xdeadbeef, &mut Disconnected, &mut delay);
+    match result {
+        Err(Error::OtpVpp) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert_eq!(dw1000.otp().read_word(0x002).unwrap(), 0);
+}
@@ -0,0 +1,254 @@
+use dw1000::{
+    configs::{
+        BitRate,
+        PulseRepetitionFrequency,
+        UwbChannel,
+    },
+    mac,
+    ranging::{
+        self,
+        ComputeDistanceError,
+        PollResponse,
+        RxMessage,
+    },
+    time::{
+        Duration,
+        Instant,
+        TIME_MAX,
+    },
+    RxConfig,
+};
+
+
+/// Reference vectors for the asymmetric DS-TWR formula
+///
+/// Each entry consists of the ping round-trip time (Ra), ping reply time (Da),
+/// request round-trip time (Rb), request reply time (Db), and the expected
+/// time of flight.
+const VECTORS: &[(u64, u64, u64, u64, i64)] = &[
+    // Symmetric reply times (10 ms), 1000 time units time of flight
+    (640_002_000, 640_000_000, 640_002_000, 640_000_000, 1000),
+
+    // Very different reply times (1 ms and 20 ms), about 30 m
+    (1_280_012_808, 64_000_000, 64_012_808, 1_280_000_000, 6404),
+
+    // Responder clock runs 20 ppm fast
+    (128_006_000, 64_000_000, 64_007_280, 128_002_560, 2999),
+
+    // Times close to the 40-bit limit, which overflow 64-bit products
+    (
+        (1 << 39) + 10_000, 1 << 39,
+        (1 << 39) + 10_000, 1 << 39,
+        5000,
+    ),
+
+    // Reply times longer than the round-trip times, due to noise
+    (1000, 1100, 1000, 1000, -24),
+
+    // All time stamps identical
+    (0, 0, 0, 0, 0),
+];
+
+
+fn duration(value: u64) -> Duration {
+    Duration::new(value).unwrap()
+}
+
+fn response(ra: u64, da: u64, rb: u64, db: u64)
+    -> RxMessage<ranging::Response>
+{
+    let request_tx_time = Instant::new(1_000_000).unwrap();
+
+    RxMessage {
+        rx_time: request_tx_time + duration(rb),
+        source:  mac::Address::Short(mac::PanId(0), mac::ShortAddress(0)),
+        payload: ranging::Response {
+            ping_reply_time:      duration(da),
+            ping_round_trip_time: duration(ra),
+            request_tx_time,
+            request_reply_time:   duration(db),
+        },
+    }
+}
+
+fn poll_response(round_trip_time: u64, reply_time: u64)
+    -> RxMessage<PollResponse>
+{
+    let poll_tx_time = Instant::new(1_000_000).unwrap();
+
+    RxMessage {
+        rx_time: poll_tx_time + duration(round_trip_time),
+        source:  mac::Address::Short(mac::PanId(0), mac::ShortAddress(0)),
+        payload: PollResponse {
+            poll_tx_time,
+            poll_reply_time: duration(reply_time),
+        },
+    }
+}
+
+
+#[test]
+fn time_of_flight_should_match_reference_vectors() {
+    for &(ra, da, rb, db, expected) in VECTORS {
+        let time_of_flight = ranging::compute_time_of_flight(
+            duration(ra),
+            duration(da),
+            duration(rb),
+            duration(db),
+        );
+
+        assert_eq!(time_of_flight, Ok(expected), "{:?}", (ra, da, rb, db));
+    }
+}
+
+#[test]
+fn time_of_flight_should_reject_implausible_results() {
+    let time_of_flight = ranging::compute_time_of_flight(
+        duration(1000),
+        duration(0),
+        duration(100_000),
+        duration(0),
+    );
+
+    assert_eq!(
+        time_of_flight,
+        Err(ComputeDistanceError::ImplausibleTimeOfFlight),
+    );
+}
+
+#[test]
+fn distance_should_be_computed_from_response() {
+    let response =
+        response(1_280_012_808, 64_000_000, 64_012_808, 1_280_000_000);
+
+    // 6404 time units are just under 30 m
+    assert_eq!(ranging::compute_distance_mm(&response), Ok(29_997));
+}
+
+#[test]
+fn distance_should_wrap_around_with_time_stamps() {
+    let mut response =
+        response(640_002_000, 640_000_000, 640_002_000, 640_000_000);
+    response.payload.request_tx_time = Instant::new(TIME_MAX - 1000).unwrap();
+    response.rx_time =
+        response.payload.request_tx_time + duration(640_002_000);
+
+    assert_eq!(ranging::compute_distance_mm(&response), Ok(4684));
+}
+
+#[test]
+fn single_sided_distance_should_correct_clock_offset() {
+    // Reply time of 10 ms on the remote clock, which runs about 10 ppm slower
+    // than the local one. 6404 time units of time of flight.
+    let response = poll_response(640_019_208, 640_000_000);
+    let rx_config = RxConfig {
+        bitrate: BitRate::Kbps6800,
+        channel: UwbChannel::Channel5,
+        ..RxConfig::default()
+    };
+
+    assert_eq!(
+        ranging::compute_distance_mm_single_sided(&response, 17_449, rx_config),
+        Ok(29_997),
+    );
+}
+
+#[test]
+fn single_sided_distance_should_correct_clock_offset_at_110_kbps() {
+    // Same as above, but the carrier integrator has a finer resolution at
+    // 110 kbps.
+    let response = poll_response(640_019_207, 640_000_000);
+    let rx_config = RxConfig {
+        bitrate: BitRate::Kbps110,
+        channel: UwbChannel::Channel5,
+        ..RxConfig::default()
+    };
+
+    assert_eq!(
+        ranging::compute_distance_mm_single_sided(
+            &response, 139_586, rx_config,
+        ),
+        Ok(29_997),
+    );
+}
+
+#[test]
+fn negative_time_of_flight_should_be_reported() {
+    let response = response(1000, 1100, 1000, 1000);
+
+    assert_eq!(
+        ranging::compute_distance_mm(&response),
+        Err(ComputeDistanceError::NegativeTimeOfFlight),
+    );
+}
+
+#[test]
+fn range_bias_should_match_table_entries() {
+    let bias = ranging::range_bias_mm(
+        -61.0,
+        UwbChannel::Channel5,
+        PulseRepetitionFrequency::Mhz16,
+    );
+    assert_eq!(bias, -198);
+
+    let bias = ranging::range_bias_mm(
+        -77.0,
+        UwbChannel::Channel2,
+        PulseRepetitionFrequency::Mhz64,
+    );
+    assert_eq!(bias, 0);
+
+    let bias = ranging::range_bias_mm(
+        -95.0,
+        UwbChannel::Channel7,
+        PulseRepetitionFrequency::Mhz64,
+    );
+    assert_eq!(bias, 284);
+}
+
+#[test]
+fn range_bias_should_be_interpolated() {
+    // Halfway between -81 dBm (0 mm) and -83 dBm (36 mm)
+    let bias = ranging::range_bias_mm(
+        -82.0,
+        UwbChannel::Channel5,
+        PulseRepetitionFrequency::Mhz16,
+    );
+    assert_eq!(bias, 18);
+}
+
+#[test]
+fn range_bias_should_be_clamped_outside_of_table() {
+    let bias = ranging::range_bias_mm(
+        -40.0,
+        UwbChannel::Channel4,
+        PulseRepetitionFrequency::Mhz16,
+    );
+    assert_eq!(bias, -274);
+
+    let bias = ranging::range_bias_mm(
+        -120.0,
+        UwbChannel::Channel1,
+        PulseRepetitionFrequency::Mhz64,
+    );
+    assert_eq!(bias, 86);
+}
+
+#[test]
+fn range_bias_correction_should_subtract_bias() {
+    let corrected = ranging::correct_range_bias_mm(
+        1000,
+        -61.0,
+        UwbChannel::Channel5,
+        PulseRepetitionFrequency::Mhz16,
+    );
+    assert_eq!(corrected, 1198);
+
+    let corrected = ranging::correct_range_bias_mm(
+        50,
+        -95.0,
+        UwbChannel::Channel5,
+        PulseRepetitionFrequency::Mhz16,
+    );
+    assert_eq!(corrected, 0);
+}
@@ -0,0 +1,1456 @@
+use embedded_hal::{
+    blocking::spi::{
+        Transfer as _,
+        Write as _,
+    },
+    digital::v2::OutputPin as _,
+};
+
+use dw1000::{
+    configs::{
+        BitRate,
+        CoarseGain,
+        PreambleLength,
+        PulseRepetitionFrequency,
+        SfdSequence,
+        SniffMode,
+        TxGain,
+        TxPower,
+        UwbChannel,
+    },
+    ll,
+    mac,
+    sim,
+    time::Duration,
+    ClockOffset,
+    DW1000,
+    Ready,
+    RxConfig,
+    RxQuality,
+    SleepConfig,
+    TxConfig,
+};
+
+
+type Dw1000 = DW1000<sim::Spi, sim::ChipSelect, Ready>;
+
+
+fn init(chip: &sim::Chip) -> Dw1000 {
+    let mut dw1000 = DW1000::new(chip.spi(), chip.chip_select())
+        .init()
+        .unwrap();
+    dw1000
+        .set_address(mac::PanId(0x0d57), mac::ShortAddress(0x0001))
+        .unwrap();
+    dw1000
+}
+
+fn frame(destination: mac::Address, payload: &[u8]) -> Vec<u8> {
+    let frame = mac::Frame {
+        header: mac::Header {
+            frame_type:      mac::FrameType::Data,
+            version:         mac::FrameVersion::Ieee802154_2006,
+            security:        mac::Security::None,
+            frame_pending:   false,
+            ack_request:     false,
+            pan_id_compress: false,
+            destination,
+            source:          mac::Address::Short(
+                mac::PanId(0x0d57),
+                mac::ShortAddress(0x0002),
+            ),
+            seq:             0,
+        },
+        content: mac::FrameContent::Data,
+        payload,
+        footer:  [0; 2],
+    };
+
+    let mut buffer = [0; 1023];
+    let len = frame.encode(&mut buffer, mac::WriteFooter::No);
+    buffer[..len].to_vec()
+}
+
+
+#[test]
+fn init_should_apply_default_configuration() {
+    let chip = sim::Chip::new();
+    let _dw1000 = init(&chip);
+
+    assert_eq!(chip.peek::<ll::DEV_ID>().ridtag(), 0xDECA);
+    assert_eq!(chip.peek::<ll::AGC_TUNE1>().value(), 0x8870);
+    assert_eq!(chip.peek::<ll::LDE_CFG1>().ntm(), 0xD);
+    assert_eq!(chip.peek::<ll::OTP_CTRL>().ldeload(), 0);
+}
+
+#[test]
+fn init_should_load_ldotune_from_otp() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x004, 0x12345678);
+    chip.set_otp(0x005, 0x9a);
+
+    let _dw1000 = init(&chip);
+
+    assert_eq!(chip.peek::<ll::LDOTUNE>().value(), 0x9a12345678);
+}
+
+#[test]
+fn init_should_load_xtal_trim_from_otp() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x01e, 0x13);
+
+    let mut dw1000 = init(&chip);
+
+    assert_eq!(chip.peek::<ll::FS_XTALT>().xtalt(), 0x13);
+    assert_eq!(chip.peek::<ll::FS_XTALT>().xtalt_res(), 0b011);
+    assert_eq!(dw1000.get_xtal_trim().unwrap(), 0x13);
+}
+
+#[test]
+fn init_should_use_default_xtal_trim_without_otp() {
+    let chip = sim::Chip::new();
+
+    let mut dw1000 = init(&chip);
+
+    assert_eq!(dw1000.get_xtal_trim().unwrap(), dw1000::hl::XTAL_TRIM_DEFAULT);
+}
+
+#[test]
+fn set_xtal_trim_should_reject_invalid_values() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    dw1000.set_xtal_trim(0x1f).unwrap();
+    assert_eq!(chip.peek::<ll::FS_XTALT>().xtalt(), 0x1f);
+
+    match dw1000.set_xtal_trim(0x20) {
+        Err(dw1000::Error::InvalidConfiguration) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert_eq!(chip.peek::<ll::FS_XTALT>().xtalt(), 0x1f);
+}
+
+#[test]
+fn read_only_registers_should_ignore_writes() {
+    let chip = sim::Chip::new();
+    let mut spi = chip.spi();
+    let mut cs  = chip.chip_select();
+
+    // Write to DEV_ID (0x00), which is read-only
+    cs.set_low().unwrap();
+    spi.write(&[0x80, 0xff, 0xff, 0xff, 0xff]).unwrap();
+    cs.set_high().unwrap();
+
+    assert_eq!(chip.peek::<ll::DEV_ID>().ridtag(), 0xDECA);
+}
+
+#[test]
+fn write_only_registers_should_read_as_zero() {
+    let chip = sim::Chip::new();
+    let mut spi = chip.spi();
+    let mut cs  = chip.chip_select();
+
+    // Write to TX_BUFFER (0x09), then read it back
+    cs.set_low().unwrap();
+    spi.write(&[0x89, 0x12, 0x34]).unwrap();
+    cs.set_high().unwrap();
+
+    let mut buffer = [0x09, 0xff, 0xff];
+    cs.set_low().unwrap();
+    spi.transfer(&mut buffer).unwrap();
+    cs.set_high().unwrap();
+
+    assert_eq!(buffer[1..], [0x00, 0x00]);
+}
+
+#[test]
+fn spi_should_require_chip_select() {
+    let chip = sim::Chip::new();
+    let mut spi = chip.spi();
+
+    assert_eq!(spi.write(&[0x00]), Err(sim::Error::NotSelected));
+}
+
+#[test]
+fn sys_status_bits_should_be_cleared_by_writing_one() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    assert_eq!(dw1000.ll().sys_status().read().unwrap().cplock(), 0b1);
+
+    dw1000.ll().sys_status().write(|w| w.cplock(0b0)).unwrap();
+    assert_eq!(dw1000.ll().sys_status().read().unwrap().cplock(), 0b1);
+
+    dw1000.ll().sys_status().write(|w| w.cplock(0b1)).unwrap();
+    assert_eq!(dw1000.ll().sys_status().read().unwrap().cplock(), 0b0);
+}
+
+#[test]
+fn enum_fields_should_be_accessible_typed_and_raw() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    dw1000.ll().pmsc_ctrl0().modify(|_, w| w.sysclks(ll::SysClks::Pll))
+        .unwrap();
+    let pmsc_ctrl0 = dw1000.ll().pmsc_ctrl0().read().unwrap();
+    assert_eq!(pmsc_ctrl0.sysclks(), Some(ll::SysClks::Pll));
+    assert_eq!(pmsc_ctrl0.sysclks_raw(), 0b10);
+
+    dw1000.ll().pmsc_ctrl0().modify(|_, w| w.sysclks_raw(0b11)).unwrap();
+    let pmsc_ctrl0 = dw1000.ll().pmsc_ctrl0().read().unwrap();
+    assert_eq!(pmsc_ctrl0.sysclks(), None);
+    assert_eq!(pmsc_ctrl0.sysclks_raw(), 0b11);
+
+    dw1000.ll().tx_fctrl()
+        .modify(|_, w| w.txprf(PulseRepetitionFrequency::Mhz64))
+        .unwrap();
+    assert_eq!(chip.peek::<ll::TX_FCTRL>().txprf_raw(), 0b10);
+
+    // The preamble length is split across TXPSR and PE.
+    dw1000.ll().tx_fctrl()
+        .modify(|_, w| w.preamble_length(PreambleLength::Symbols1536))
+        .unwrap();
+    let tx_fctrl = chip.peek::<ll::TX_FCTRL>();
+    assert_eq!(tx_fctrl.preamble_length(), Some(PreambleLength::Symbols1536));
+    assert_eq!(tx_fctrl.txpsr(), 0b10);
+    assert_eq!(tx_fctrl.pe(), 0b01);
+
+    dw1000.ll().chan_ctrl()
+        .modify(|_, w| w.rx_chan(UwbChannel::Channel7))
+        .unwrap();
+    assert_eq!(chip.peek::<ll::CHAN_CTRL>().rx_chan_raw(), 7);
+
+    // Receiver clock selection uses the same encoding as SYSCLKS.
+    dw1000.ll().pmsc_ctrl0().modify(|_, w| w.rxclks(ll::SysClks::Pll))
+        .unwrap();
+    assert_eq!(chip.peek::<ll::PMSC_CTRL0>().rxclks_raw(), 0b10);
+    assert_eq!(
+        chip.peek::<ll::PMSC_CTRL0>().softreset(),
+        Some(ll::SoftReset::Clear),
+    );
+}
+
+#[test]
+fn send_should_transmit_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut sending = dw1000
+        .send(b"hello", destination, None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let transmitted = chip.take_transmitted();
+    assert_eq!(transmitted.len(), 1);
+
+    let frame = mac::Frame::decode(&transmitted[0].data, false).unwrap();
+    assert_eq!(frame.payload, b"hello");
+    assert_eq!(frame.header.destination, destination);
+    assert_eq!(
+        frame.header.source,
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0001)),
+    );
+}
+
+#[test]
+fn send_raw_should_transmit_data_as_is() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut sending = dw1000
+        .send_raw(&[0xaa, 0x55, 0x01, 0x02], None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let transmitted = chip.take_transmitted();
+    assert_eq!(transmitted.len(), 1);
+    assert_eq!(transmitted[0].data, [0xaa, 0x55, 0x01, 0x02]);
+    assert_eq!(chip.peek::<ll::TX_FCTRL>().tflen(), 4 + 2);
+}
+
+#[test]
+fn send_raw_should_reject_invalid_frames() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let dw1000 = match dw1000.send_raw(&[0; 126], None, TxConfig::default()) {
+        Err(dw1000::Error::FrameTooLong) => init(&chip),
+        result => panic!("Unexpected result: {:?}", result),
+    };
+
+    let config = TxConfig {
+        ack_request: true,
+        .. TxConfig::default()
+    };
+    match dw1000.send_raw(b"hello", None, config) {
+        Err(dw1000::Error::InvalidConfiguration) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn send_should_transmit_long_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let payload = [0x5a; 600];
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let config = TxConfig {
+        long_frames: true,
+        .. TxConfig::default()
+    };
+    let mut sending = dw1000
+        .send(&payload, destination, None, config)
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let transmitted = chip.take_transmitted();
+    let frame = mac::Frame::decode(&transmitted[0].data, false).unwrap();
+    assert_eq!(frame.payload, &payload[..]);
+
+    let len = transmitted[0].data.len() + 2;
+    let tx_fctrl = chip.peek::<ll::TX_FCTRL>();
+    assert_eq!(tx_fctrl.tflen() as usize, len & 0x7f);
+    assert_eq!(tx_fctrl.tfle() as usize, len >> 7);
+    assert_eq!(chip.peek::<ll::SYS_CFG>().phr_mode(), Some(ll::PhrMode::Long));
+}
+
+#[test]
+fn send_should_reject_frames_that_are_too_long() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    match dw1000.send(&[0; 200], destination, None, TxConfig::default()) {
+        Err(dw1000::Error::FrameTooLong) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    let dw1000 = init(&chip);
+    let config = TxConfig {
+        long_frames: true,
+        .. TxConfig::default()
+    };
+    match dw1000.send_raw(&[0; 1022], None, config) {
+        Err(dw1000::Error::FrameTooLong) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn preloaded_frames_should_be_sent_from_their_offset() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let config = TxConfig::default();
+    let first = dw1000
+        .preload(0, b"first", destination, &config)
+        .unwrap();
+    let second = dw1000
+        .preload(200, b"second", destination, &config)
+        .unwrap();
+    assert_eq!(first.payload_len(), 5);
+    assert_eq!(second.offset(), 200);
+
+    let mut sending = dw1000
+        .send_preloaded(&second, None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let mut dw1000 = sending.finish_sending().unwrap();
+    assert_eq!(chip.peek::<ll::TX_FCTRL>().txboffs(), 200);
+
+    dw1000.patch_preloaded(&first, 1, b"XY").unwrap();
+    let mut sending = dw1000
+        .send_preloaded(&first, None, TxConfig::default())
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    let transmitted = chip.take_transmitted();
+    let frames: Vec<_> = transmitted
+        .iter()
+        .map(|t| mac::Frame::decode(&t.data, false).unwrap())
+        .collect();
+    assert_eq!(frames[0].payload, b"second");
+    assert_eq!(frames[0].header.seq, second.seq().unwrap());
+    assert_eq!(frames[1].payload, b"fXYst");
+    assert_eq!(frames[1].header.seq, first.seq().unwrap());
+}
+
+#[test]
+fn preload_should_reject_data_outside_of_tx_buffer() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    match dw1000.preload_raw(1000, &[0; 30]) {
+        Err(dw1000::Error::TxBufferOutOfRange) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    let frame = dw1000.preload_raw(0, b"raw").unwrap();
+    match dw1000.patch_preloaded(&frame, 2, b"xy") {
+        Err(dw1000::Error::TxBufferOutOfRange) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    let config = TxConfig {
+        ack_request: true,
+        .. TxConfig::default()
+    };
+    match dw1000.send_preloaded(&frame, None, config) {
+        Err(dw1000::Error::InvalidConfiguration) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn send_should_program_tx_power() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+
+    // By default, the recommended smart TX power setting is used.
+    let config = TxConfig {
+        channel:                    UwbChannel::Channel2,
+        pulse_repetition_frequency: PulseRepetitionFrequency::Mhz64,
+        .. TxConfig::default()
+    };
+    let mut sending = dw1000.send(b"hello", destination, None, config)
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let dw1000 = sending.finish_sending().unwrap();
+
+    assert_eq!(chip.peek::<ll::TX_POWER>().value(), 0x07274767);
+    assert_eq!(chip.peek::<ll::SYS_CFG>().dis_stxp(), 0b0);
+
+    // Manual TX power control disables smart TX power.
+    let gain = TxGain { coarse: CoarseGain::Db7_5, fine: 10 };
+    let config = TxConfig {
+        tx_power: Some(TxPower::Manual { phr: gain, shr_data: gain }),
+        .. TxConfig::default()
+    };
+    let mut sending = dw1000.send(b"hello", destination, None, config)
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    sending.finish_sending().unwrap();
+
+    assert_eq!(chip.peek::<ll::TX_POWER>().value(), 0x6a6a6a6a);
+    assert_eq!(chip.peek::<ll::SYS_CFG>().dis_stxp(), 0b1);
+}
+
+#[test]
+fn wait_ack_should_require_ack_request() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut sending = dw1000
+        .send(b"hello", destination, None, TxConfig::default())
+        .unwrap();
+
+    match sending.wait_ack() {
+        Err(nb::Error::Other(dw1000::Error::NoAckRequested)) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn unrelated_frames_should_not_extend_ack_timeout() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0002));
+    let config = TxConfig {
+        ack_request: true,
+        ack_timeout: 1000,
+        .. TxConfig::default()
+    };
+    let mut sending = dw1000.send(b"hello", destination, None, config)
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+    let tx_done = chip.sys_time();
+
+    // Keep the channel busy with frames that aren't the acknowledgement.
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut result = Err(nb::Error::WouldBlock);
+    for _ in 0 .. 10 {
+        chip.advance(Duration::from_nanos(300_000));
+        chip.deliver(&frame(broadcast, b"noise"));
+
+        result = sending.wait_ack();
+        if !matches!(result, Err(nb::Error::WouldBlock)) {
+            break;
+        }
+    }
+
+    match result {
+        Err(nb::Error::Other(dw1000::Error::AckTimeout)) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    let elapsed = chip.sys_time().duration_since(tx_done);
+    assert!(elapsed.value() < Duration::from_nanos(1_500_000).value());
+}
+
+#[test]
+fn ack_timeout_should_start_at_end_of_transmission() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0002));
+    let config = TxConfig {
+        ack_request: true,
+        ack_timeout: 1000,
+        .. TxConfig::default()
+    };
+    let mut sending = dw1000.send(b"hello", destination, None, config)
+        .unwrap();
+
+    // The transmission finishes long before the host gets around to checking.
+    chip.advance(Duration::from_nanos(900_000));
+    nb::block!(sending.wait()).unwrap();
+
+    // An unrelated frame makes the driver restart the receiver. It must only
+    // get what's left of the timeout, counted from the end of the frame.
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    chip.deliver(&frame(broadcast, b"noise"));
+    assert!(matches!(sending.wait_ack(), Err(nb::Error::WouldBlock)));
+
+    chip.advance(Duration::from_nanos(500_000));
+    match sending.wait_ack() {
+        Err(nb::Error::Other(dw1000::Error::AckTimeout)) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn delayed_send_should_transmit_at_requested_time() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    dw1000.set_antenna_delay(0, 100).unwrap();
+
+    let tx_time = dw1000.sys_time().unwrap() + Duration::from_nanos(10_000_000);
+
+    let mut sending = dw1000
+        .send(
+            b"delayed",
+            mac::Address::broadcast(&mac::AddressMode::Short),
+            Some(tx_time),
+            TxConfig::default(),
+        )
+        .unwrap();
+    nb::block!(sending.wait()).unwrap();
+
+    let transmitted = chip.take_transmitted();
+    assert_eq!(
+        transmitted[0].tx_time.value(),
+        (tx_time.value() & !0x1ff) + 100,
+    );
+}
+
+#[test]
+fn delayed_send_should_report_if_too_late() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    let tx_time = dw1000.sys_time().unwrap();
+
+    let mut sending = dw1000
+        .send(
+            b"delayed",
+            mac::Address::broadcast(&mac::AddressMode::Short),
+            Some(tx_time),
+            TxConfig::default(),
+        )
+        .unwrap();
+
+    match sending.wait() {
+        Err(nb::Error::Other(dw1000::Error::DelayedSendTooLate)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn receive_should_return_delivered_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let mut buffer = [0; 128];
+    assert!(receiving.wait(&mut buffer).is_err());
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0001));
+    let delivered_at = chip.sys_time();
+    assert!(chip.deliver(&frame(destination, b"hello")));
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+    assert_eq!(message.frame.header.destination, destination);
+    assert_eq!(message.rx_time.value(), delivered_at.value());
+}
+
+#[test]
+fn receive_should_filter_frames_for_other_nodes() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let _receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let other =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0003));
+    assert!(!chip.deliver(&frame(other, b"hello")));
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+}
+
+#[test]
+fn receive_should_accept_all_frames_without_filtering() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let _receiving = dw1000
+        .receive(RxConfig {
+            frame_filtering: false,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let other =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0003));
+    assert!(chip.deliver(&frame(other, b"hello")));
+}
+
+#[test]
+fn wait_raw_should_return_undecoded_data() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            frame_filtering: false,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    // Not a valid MAC frame
+    let delivered_at = chip.sys_time();
+    assert!(chip.deliver(&[0xff, 0xff, 0x12, 0x34]));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait_raw(&mut buffer)).unwrap();
+    assert_eq!(message.data.len(), 4 + 2);
+    assert_eq!(&message.data[..4], [0xff, 0xff, 0x12, 0x34]);
+    assert_eq!(message.rx_time.value(), delivered_at.value());
+}
+
+#[test]
+fn receive_should_return_long_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            long_frames: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let payload = [0xa5; 900];
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0001));
+    assert!(chip.deliver(&frame(destination, &payload)));
+
+    let mut buffer = [0; 1023];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, &payload[..]);
+}
+
+#[test]
+fn long_frames_should_cause_phy_error_without_long_frame_mode() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let destination =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0001));
+    assert!(chip.deliver(&frame(destination, &[0; 200])));
+
+    let mut buffer = [0; 1023];
+    match nb::block!(receiving.wait(&mut buffer)) {
+        Err(dw1000::Error::Phy) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn frames_should_not_be_received_while_receiver_is_off() {
+    let chip = sim::Chip::new();
+    let _dw1000 = init(&chip);
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(!chip.deliver(&frame(broadcast, b"hello")));
+}
+
+#[test]
+fn receive_should_report_rx_quality() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let rx_quality = message.rx_quality;
+
+    // The simulation delivers frames at -80 dBm, using the default preamble
+    // length of 128 symbols. The count includes 5 symbols of the standard SFD.
+    assert_eq!(rx_quality.rxpacc, 133);
+    assert_eq!(rx_quality.rxpacc_nosat, 133);
+    assert!((rx_quality.rx_power_dbm() + 80.0).abs() < 0.1);
+    assert!((rx_quality.first_path_power_dbm() + 80.0).abs() < 0.1);
+}
+
+#[test]
+fn rx_quality_should_only_correct_unsaturated_preamble_count() {
+    let unsaturated = RxQuality {
+        std_noise:    0,
+        fp_index:     0,
+        fp_ampl1:     1000,
+        fp_ampl2:     1000,
+        fp_ampl3:     1000,
+        cir_pwr:      1000,
+        rxpacc:       133,
+        rxpacc_nosat: 133,
+        pulse_repetition_frequency: PulseRepetitionFrequency::Mhz16,
+        bitrate:      BitRate::Kbps6800,
+        sfd_sequence: SfdSequence::IEEE,
+    };
+    let saturated = RxQuality {
+        rxpacc:       128,
+        rxpacc_nosat: 200,
+        .. unsaturated
+    };
+
+    // The 5 SFD symbols are subtracted from the unsaturated count, so both
+    // end up with the same count of 128.
+    assert_eq!(unsaturated.rx_power_dbm(), saturated.rx_power_dbm());
+    assert_eq!(
+        unsaturated.first_path_power_dbm(),
+        saturated.first_path_power_dbm(),
+    );
+}
+
+#[test]
+fn wait_should_only_read_received_bytes() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 1023];
+    let before = chip.sys_time();
+    nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let after = chip.sys_time();
+
+    // Every byte transferred over SPI takes a microsecond. Reading all of
+    // RX_BUFFER would take more than a millisecond.
+    assert!(
+        after.duration_since(before).value()
+            < Duration::from_nanos(200_000).value()
+    );
+}
+
+#[test]
+fn buffers_should_be_accessible_at_offsets() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            frame_filtering: false,
+            .. RxConfig::default()
+        })
+        .unwrap();
+    assert!(chip.deliver(&[0x10, 0x11, 0x12, 0x13, 0x14]));
+
+    let mut bytes = [0; 3];
+    receiving.ll().rx_buffer().read_at(2, &mut bytes).unwrap();
+    assert_eq!(bytes, [0x12, 0x13, 0x14]);
+
+    // Offsets beyond 127 require the extended address format.
+    let mut dw1000 = receiving.finish_receiving().unwrap();
+    dw1000.ll().tx_buffer().write_at(300, b"data").unwrap();
+    dw1000.ll().tx_fctrl().modify(|_, w|
+        w
+            .tflen(4 + 2)
+            .txboffs(300)
+    ).unwrap();
+    dw1000.ll().sys_ctrl().modify(|_, w| w.txstrt(0b1)).unwrap();
+    chip.advance(Duration::from_nanos(1_000_000));
+
+    assert_eq!(chip.take_transmitted()[0].data, b"data");
+}
+
+#[test]
+#[should_panic]
+fn buffer_access_should_not_extend_beyond_register() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    dw1000.ll().tx_buffer().write_at(1020, b"data!").unwrap();
+}
+
+#[test]
+fn cir_should_be_read_from_accumulator_memory() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    let fp_index = message.rx_quality.fp_index >> 6;
+
+    let first_sample = fp_index - 2;
+    let mut samples = [(0, 0); 5];
+    receiving.read_cir(first_sample, &mut samples).unwrap();
+
+    let peak = message.rx_quality.fp_ampl1 as i16;
+    assert_eq!(
+        samples,
+        [(0, 0), (peak / 2, 0), (peak, 0), (peak / 2, 0), (0, 0)],
+    );
+
+    // The accumulator clocks must be disabled again afterwards.
+    let pmsc_ctrl0 = chip.peek::<ll::PMSC_CTRL0>();
+    assert_eq!(pmsc_ctrl0.face(), 0b0);
+    assert_eq!(pmsc_ctrl0.amce(), 0b0);
+}
+
+#[test]
+fn cir_outside_of_accumulator_memory_should_be_rejected() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let mut samples = [(0, 0); 16];
+    match receiving.read_cir(1016 - 15, &mut samples) {
+        Err(dw1000::Error::CirOutOfRange) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    receiving.read_cir(1016 - 16, &mut samples).unwrap();
+}
+
+#[test]
+fn send_and_receive_should_reject_invalid_response_delay() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let destination = mac::Address::broadcast(&mac::AddressMode::Short);
+    let result = dw1000.send_and_receive(
+        b"ping",
+        destination,
+        None,
+        TxConfig::default(),
+        RxConfig::default(),
+        1 << 20,
+    );
+
+    match result {
+        Err(dw1000::Error::InvalidConfiguration) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn receive_should_time_out_waiting_for_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            frame_wait_timeout: Some(100),
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    assert!(matches!(receiving.wait(&mut [0; 128]), Err(nb::Error::WouldBlock)));
+
+    chip.advance(Duration::from_nanos(200_000));
+    match receiving.wait(&mut [0; 128]) {
+        Err(nb::Error::Other(dw1000::Error::FrameWaitTimeout)) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    // The receiver has been disabled.
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(!chip.deliver(&frame(broadcast, b"hello")));
+
+    // The timeout must not affect the next receive operation.
+    let dw1000 = receiving.finish_receiving().unwrap();
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+    assert!(matches!(receiving.wait(&mut [0; 128]), Err(nb::Error::WouldBlock)));
+}
+
+#[test]
+fn receive_should_time_out_waiting_for_preamble() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    // With the default preamble length, the PAC size is 8 symbols, so this
+    // is a timeout of roughly 80 µs.
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            preamble_detection_timeout: Some(10),
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    chip.advance(Duration::from_nanos(100_000));
+    match receiving.wait(&mut [0; 128]) {
+        Err(nb::Error::Other(dw1000::Error::PreambleDetectionTimeout)) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn receive_should_return_frame_that_arrives_before_timeout() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            frame_wait_timeout:         Some(1000),
+            preamble_detection_timeout: Some(100),
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+}
+
+#[test]
+fn receive_should_reject_zero_timeouts() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let result = dw1000.receive(RxConfig {
+        frame_wait_timeout: Some(0),
+        .. RxConfig::default()
+    });
+
+    match result {
+        Err(dw1000::Error::InvalidConfiguration) => {}
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn delayed_receive_should_enable_receiver_at_requested_time() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    let rx_time = dw1000.sys_time().unwrap() + Duration::from_nanos(10_000_000);
+
+    let mut receiving = dw1000
+        .receive_delayed(rx_time, RxConfig::default())
+        .unwrap();
+
+    // The receiver is not enabled yet.
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(!chip.deliver(&frame(broadcast, b"too early")));
+
+    chip.advance(Duration::from_nanos(10_000_000));
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+}
+
+#[test]
+fn delayed_receive_should_report_if_too_late() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+
+    let rx_time = dw1000.sys_time().unwrap();
+
+    let mut receiving = dw1000
+        .receive_delayed(rx_time, RxConfig::default())
+        .unwrap();
+
+    match receiving.wait(&mut [0; 128]) {
+        Err(nb::Error::Other(dw1000::Error::DelayedReceiveTooLate)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+
+    // The receiver must not be enabled once the system time wraps around.
+    chip.advance(Duration::from_nanos(4_000_000_000));
+    chip.advance(Duration::from_nanos(4_000_000_000));
+    chip.advance(Duration::from_nanos(4_000_000_000));
+    chip.advance(Duration::from_nanos(4_000_000_000));
+    chip.advance(Duration::from_nanos(2_000_000_000));
+    assert!(!chip.deliver(&frame(broadcast, b"hello")));
+
+    // The warning must not affect the next receive operation.
+    let dw1000 = receiving.finish_receiving().unwrap();
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+    assert!(matches!(receiving.wait(&mut [0; 128]), Err(nb::Error::WouldBlock)));
+}
+
+#[test]
+fn single_buffered_receiver_should_stop_after_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let _receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"first")));
+    assert!(!chip.deliver(&frame(broadcast, b"second")));
+}
+
+#[test]
+fn double_buffered_receiver_should_receive_into_both_buffers() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            double_buffered: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"first")));
+    assert!(chip.deliver(&frame(broadcast, b"second")));
+
+    // Both buffers are full, so the receiver has stopped.
+    assert!(!chip.deliver(&frame(broadcast, b"third")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"first");
+
+    // Reading the first frame has freed up its buffer.
+    assert!(chip.deliver(&frame(broadcast, b"fourth")));
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"second");
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"fourth");
+    assert!(matches!(receiving.wait(&mut buffer), Err(nb::Error::WouldBlock)));
+}
+
+#[test]
+fn double_buffered_receiver_should_start_in_sync() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let config = RxConfig {
+        double_buffered: true,
+        .. RxConfig::default()
+    };
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+
+    // Leave the buffer pointers out of sync, by receiving a frame that is
+    // never read.
+    let receiving = dw1000.receive(config).unwrap();
+    assert!(chip.deliver(&frame(broadcast, b"unread")));
+    let dw1000 = receiving.finish_receiving().unwrap();
+
+    let mut receiving = dw1000.receive(config).unwrap();
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+}
+
+#[test]
+fn continuous_receive_should_yield_many_frames() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            continuous: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut buffer = [0; 128];
+    for payload in &[b"first", b"other", b"third"] {
+        assert!(chip.deliver(&frame(broadcast, *payload)));
+
+        let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+        assert_eq!(message.frame.payload, *payload);
+    }
+}
+
+#[test]
+fn continuous_receive_should_report_errors_per_frame() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            continuous: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let mut buffer = [0; 128];
+    assert!(chip.deliver_corrupted());
+    match receiving.wait(&mut buffer) {
+        Err(nb::Error::Other(dw1000::Error::Fcs)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert!(matches!(receiving.wait(&mut buffer), Err(nb::Error::WouldBlock)));
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+}
+
+#[test]
+fn continuous_receive_should_recover_from_frame_filtering_rejection() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            continuous: true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    // Addressed to another node, so frame filtering rejects it.
+    let other =
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0099));
+    let mut buffer = [0; 128];
+    assert!(!chip.deliver(&frame(other, b"other")));
+    assert_eq!(chip.peek::<ll::SYS_STATUS>().affrej(), 0b1);
+    match receiving.wait(&mut buffer) {
+        Err(nb::Error::Other(dw1000::Error::FrameFilteringRejection)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert!(matches!(receiving.wait(&mut buffer), Err(nb::Error::WouldBlock)));
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.frame.payload, b"hello");
+    assert!(matches!(receiving.wait(&mut buffer), Err(nb::Error::WouldBlock)));
+}
+
+#[test]
+fn receive_errors_should_stop_receiver_without_continuous_mode() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    assert!(chip.deliver_corrupted());
+    assert!(!chip.deliver_corrupted());
+    match receiving.wait(&mut [0; 128]) {
+        Err(nb::Error::Other(dw1000::Error::Fcs)) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn continuous_receive_should_work_with_double_buffering() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let mut receiving = dw1000
+        .receive(RxConfig {
+            double_buffered: true,
+            continuous:      true,
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    let mut buffer = [0; 128];
+    for payload in &[b"first", b"other", b"third"] {
+        assert!(chip.deliver(&frame(broadcast, *payload)));
+
+        let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+        assert_eq!(message.frame.payload, *payload);
+    }
+}
+
+#[test]
+fn wake_up_should_restore_configuration() {
+    let chip = sim::Chip::new();
+    let mut dw1000 = init(&chip);
+    dw1000.set_antenna_delay(100, 50).unwrap();
+
+    let sleeping = dw1000.enter_sleep(SleepConfig::default()).unwrap();
+    assert!(chip.is_asleep());
+
+    let mut dw1000 = sleeping.wake_up(&mut chip.delay()).unwrap();
+    assert!(!chip.is_asleep());
+
+    assert_eq!(
+        dw1000.get_address().unwrap(),
+        mac::Address::Short(mac::PanId(0x0d57), mac::ShortAddress(0x0001)),
+    );
+    assert_eq!(dw1000.get_tx_antenna_delay().unwrap().value(), 50);
+
+    // Receiving requires the LDE microcode and the RX antenna delay.
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    let delivered_at = chip.sys_time();
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    let mut buffer = [0; 128];
+    let message = nb::block!(receiving.wait(&mut buffer)).unwrap();
+    assert_eq!(message.rx_time.value(), delivered_at.value() - 100);
+}
+
+#[test]
+fn wake_up_without_preserved_configuration_should_initialize() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let sleeping = dw1000
+        .enter_sleep(SleepConfig {
+            preserve_config: false,
+            .. SleepConfig::default()
+        })
+        .unwrap();
+    let mut dw1000 = sleeping.wake_up(&mut chip.delay()).unwrap();
+
+    assert_eq!(
+        dw1000.get_address().unwrap(),
+        mac::Address::Short(mac::PanId(0xffff), mac::ShortAddress(0xffff)),
+    );
+    assert_eq!(chip.peek::<ll::AGC_TUNE1>().value(), 0x8870);
+
+    let mut receiving = dw1000.receive(RxConfig::default()).unwrap();
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+    let mut buffer = [0; 128];
+    assert!(nb::block!(receiving.wait(&mut buffer)).is_ok());
+}
+
+#[test]
+fn wake_up_pin_should_wake_up_chip() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let sleeping = dw1000
+        .enter_sleep(SleepConfig {
+            wake_on_spi: false,
+            .. SleepConfig::default()
+        })
+        .unwrap();
+
+    let _dw1000 = sleeping
+        .wake_up_with_pin(&mut chip.wakeup_pin(), &mut chip.delay())
+        .unwrap();
+    assert!(!chip.is_asleep());
+}
+
+#[test]
+fn wake_up_should_fail_if_wake_up_source_is_disabled() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let sleeping = dw1000
+        .enter_sleep(SleepConfig {
+            wake_on_pin: false,
+            .. SleepConfig::default()
+        })
+        .unwrap();
+
+    let result =
+        sleeping.wake_up_with_pin(&mut chip.wakeup_pin(), &mut chip.delay());
+    match result {
+        Err(dw1000::Error::WakeUpFailed) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert!(chip.is_asleep());
+}
+
+#[test]
+fn enter_sleep_should_require_wake_up_source() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let result = dw1000.enter_sleep(SleepConfig {
+        wake_on_spi: false,
+        wake_on_pin: false,
+        .. SleepConfig::default()
+    });
+
+    match result {
+        Err(dw1000::Error::InvalidConfiguration) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+    assert!(!chip.is_asleep());
+}
+
+#[test]
+fn sniff_mode_should_be_configured() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let receiving = dw1000
+        .receive(RxConfig {
+            sniff_mode: Some(SniffMode { on_time: 2, off_time: 100 }),
+            .. RxConfig::default()
+        })
+        .unwrap();
+
+    assert_eq!(chip.peek::<ll::RX_SNIFF>().sniff_ont(), 2);
+    assert_eq!(chip.peek::<ll::RX_SNIFF>().sniff_offt(), 100);
+    assert_eq!(chip.peek::<ll::PMSC_CTRL0>().pll2_seq_en(), 0b1);
+
+    // The next receive operation doesn't use SNIFF mode.
+    let dw1000 = receiving.finish_receiving().unwrap();
+    let _receiving = dw1000.receive(RxConfig::default()).unwrap();
+
+    assert_eq!(chip.peek::<ll::RX_SNIFF>().sniff_ont(), 0);
+    assert_eq!(chip.peek::<ll::RX_SNIFF>().sniff_offt(), 0);
+    assert_eq!(chip.peek::<ll::PMSC_CTRL0>().pll2_seq_en(), 0b0);
+}
+
+#[test]
+fn sniff_mode_should_miss_preamble_during_off_phase() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+    let broadcast = mac::Address::broadcast(&mac::AddressMode::Short);
+
+    // Listen for 2 PACs (16 µs), then switch off for about 260 µs.
+    let config = RxConfig {
+        sniff_mode: Some(SniffMode { on_time: 1, off_time: 255 }),
+        .. RxConfig::default()
+    };
+
+    // The 128 µs preamble covers the first listening phase.
+    let receiving = dw1000.receive(config).unwrap();
+    chip.advance(Duration::from_nanos(20_000));
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+
+    // The 128 µs preamble falls into the off phase.
+    let dw1000 = receiving.finish_receiving().unwrap();
+    let receiving = dw1000.receive(config).unwrap();
+    chip.advance(Duration::from_nanos(200_000));
+    assert!(!chip.deliver(&frame(broadcast, b"hello")));
+
+    // Without SNIFF mode, the receiver is always listening.
+    let dw1000 = receiving.finish_receiving().unwrap();
+    let _receiving = dw1000.receive(RxConfig::default()).unwrap();
+    chip.advance(Duration::from_nanos(200_000));
+    assert!(chip.deliver(&frame(broadcast, b"hello")));
+}
+
+#[test]
+fn sniff_mode_should_reject_invalid_on_time() {
+    let chip = sim::Chip::new();
+    let dw1000 = init(&chip);
+
+    let result = dw1000.receive(RxConfig {
+        sniff_mode: Some(SniffMode { on_time: 0, off_time: 10 }),
+        .. RxConfig::default()
+    });
+
+    match result {
+        Err(dw1000::Error::InvalidConfiguration) => (),
+        result => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn temperature_and_voltage_should_be_measured() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x008, 0x00001e9a); // 0x9a at 3.3 V
+    chip.set_otp(0x009, 0x00000081); // 0x81 at 23 °C
+    chip.set_temperature(34.4);
+    chip.set_voltage(3.0);
+
+    let mut dw1000 = init(&chip);
+    let reading = dw1000
+        .read_temperature_and_voltage(&mut chip.delay())
+        .unwrap();
+
+    assert_eq!(reading.raw_temperature, 0x81 + 10);
+    assert_eq!(reading.raw_voltage, 0x9a - 52);
+    assert!((reading.temperature - 34.4).abs() < 0.01);
+    assert!((reading.voltage - 3.0).abs() < 0.01);
+
+    // The SAR ADC is disabled afterwards.
+    assert_eq!(chip.peek::<ll::TC_SARC>().sar_ctrl(), 0b0);
+}
+
+#[test]
+fn extended_address_should_be_loaded_from_otp() {
+    let chip = sim::Chip::new();
+    chip.set_otp(0x000, 0x89abcdef);
+    chip.set_otp(0x001, 0x01234567);
+    let mut dw1000 = init(&chip);
+
+    let eui = dw1000.set_extended_address_from_otp().unwrap();
+
+    assert_eq!(eui, mac::ExtendedAddress(0x01234567_89abcdef));
+    assert_eq!(chip.peek::<ll::EUI>().value(), 0x01234567_89abcdef);
+    assert_eq!(
+        dw1000.get_extended_address().unwrap(),
+        mac::Address::Extended(mac::PanId(0x0d57), eui),
+    );
+}
+
+#[test]
+fn clock_offset_should_depend_on_bitrate() {
+    // A remote clock that's 4.5 ppm slower, on channel 5. The carrier
+    // integrator counts in steps of 998.4 MHz / 2^28, or 2^31 at 110 kbps.
+    let rx_config = RxConfig::default();
+    let offset = ClockOffset::new(-7852, rx_config, 16);
+    assert!((offset.ppm - 4.5).abs() < 0.001, "Offset: {} ppm", offset.ppm);
+    assert_eq!(offset.suggested_xtal_trim, 13);
+
+    let rx_config = RxConfig {
+        bitrate: BitRate::Kbps110,
+        .. RxConfig::default()
+    };
+    let offset = ClockOffset::new(-62815, rx_config, 16);
+    assert!((offset.ppm - 4.5).abs() < 0.001, "Offset: {} ppm", offset.ppm);
+    assert_eq!(offset.suggested_xtal_trim, 13);
+}
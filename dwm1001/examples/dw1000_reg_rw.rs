@@ -33,9 +33,9 @@ fn main() -> ! {
             w
                 .tflen(0b100_1001)
                 .tfle(0b10_1)
-                .txbr(0b10)
+                .txbr_raw(0b10)
                 .tr(0b1)
-                .txprf(0b01)
+                .txprf_raw(0b01)
                 .txpsr(0b01)
                 .pe(0b10)
                 .txboffs(0b1101_0010_11)
@@ -51,15 +51,15 @@ fn main() -> ! {
         .read()
         .expect("Failed to read from register");
 
-    assert_eq!(tx_fctrl.tflen(),    0b1001001);
-    assert_eq!(tx_fctrl.tfle(),     0b101);
-    assert_eq!(tx_fctrl.txbr(),     0b10);
-    assert_eq!(tx_fctrl.tr(),       0b1);
-    assert_eq!(tx_fctrl.txprf(),    0b01);
-    assert_eq!(tx_fctrl.txpsr(),    0b01);
-    assert_eq!(tx_fctrl.pe(),       0b10);
-    assert_eq!(tx_fctrl.txboffs(),  0b1101001011);
-    assert_eq!(tx_fctrl.ifsdelay(), 0b01100110);
+    assert_eq!(tx_fctrl.tflen(),     0b1001001);
+    assert_eq!(tx_fctrl.tfle(),      0b101);
+    assert_eq!(tx_fctrl.txbr_raw(),  0b10);
+    assert_eq!(tx_fctrl.tr(),        0b1);
+    assert_eq!(tx_fctrl.txprf_raw(), 0b01);
+    assert_eq!(tx_fctrl.txpsr(),     0b01);
+    assert_eq!(tx_fctrl.pe(),        0b10);
+    assert_eq!(tx_fctrl.txboffs(),   0b1101001011);
+    assert_eq!(tx_fctrl.ifsdelay(),  0b01100110);
 
     print!("Success!\n");
 